@@ -1,20 +1,58 @@
-use eon_core::FileData;
 use eon_core::OsFileProvider;
-use clap::Parser;
+use eon_core::CompiledProgram;
+use eon_core::TestFunction;
+use eon_core::addons::StaticModule;
+use eon_core::coverage::Coverage;
+use eon_core::debugger::{DebugEvent, Debugger, StepMode};
+use eon_core::vm::{Vm, VmError, VmStatus};
+use eon_core::statics::Report;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::any::Any;
 use std::io;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 mod host_funcs;
+mod manifest;
 use host_funcs::*;
+use manifest::{Manifest, Project, SearchDirs};
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None, arg_required_else_help = true)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    arg_required_else_help = true,
+    args_conflicts_with_subcommands = true
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    run: RunArgs,
+}
+#[derive(Subcommand, Debug)]
+enum Command {
+    #[command(about = "Compile and execute an Eon program")]
+    Run(RunArgs),
+    #[command(about = "Check an Eon program for errors without executing it")]
+    Check(CheckArgs),
+    #[command(about = "Format Eon source files in place")]
+    Fmt(FmtArgs),
+    #[command(about = "Run the `test fn`s of an Eon program or project")]
+    Test(TestArgs),
+    #[command(about = "Run an Eon program under an interactive debugger")]
+    Debug(ProjectArgs),
+}
+#[derive(clap::Args, Debug, Clone)]
+struct ProjectArgs {
     #[arg(
-        help = "The main Eon file to compile and execute",
+        help = "The main Eon file. Defaults to the `entry` of ./eon.toml",
         value_name = "FILE"
     )]
-    file: String,
+    file: Option<String>,
     #[arg(
         short,
         long,
@@ -29,6 +67,133 @@ struct Args {
         help = "Override the default shared objects directory (~/.eon/shared_objects)."
     )]
     shared_objects: Option<String>,
+}
+#[derive(clap::Args, Debug, Clone)]
+struct LimitArgs {
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Fail with a stack overflow when more than N calls are active (default: 100000)"
+    )]
+    max_call_depth: Option<usize>,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Fail with a stack overflow when the value stack grows past N values (default: 4194304)"
+    )]
+    max_stack_size: Option<usize>,
+    #[arg(long, value_name = "N", help = "Stop the program after N instructions")]
+    fuel: Option<u64>,
+    #[arg(long, value_name = "SECONDS", help = "Stop the program after SECONDS seconds")]
+    timeout: Option<f64>,
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "Stop the program when its heap objects need more than BYTES bytes"
+    )]
+    max_memory: Option<usize>,
+    #[arg(long, help = "Refuse to load shared libraries, for running untrusted programs")]
+    sandbox: bool,
+}
+impl LimitArgs {
+    fn apply(&self, vm: &mut Vm) {
+        vm.set_fuel(self.fuel);
+        vm.set_deadline(
+            self.timeout
+                .map(|seconds| Instant::now() + Duration::from_secs_f64(seconds)),
+        );
+        vm.set_max_memory(self.max_memory);
+        vm.set_sandboxed(self.sandbox);
+        if let Some(depth) = self.max_call_depth {
+            vm.set_max_call_depth(depth);
+        }
+        if let Some(size) = self.max_stack_size {
+            vm.set_max_stack_size(size);
+        }
+    }
+}
+#[derive(clap::Args, Debug)]
+struct CheckArgs {
+    #[command(flatten)]
+    project: ProjectArgs,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = MessageFormat::Human,
+        help = "How to print diagnostics"
+    )]
+    message_format: MessageFormat,
+}
+#[derive(clap::Args, Debug)]
+struct TestArgs {
+    #[command(flatten)]
+    project: ProjectArgs,
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Only run tests whose name contains NAME"
+    )]
+    filter: Option<String>,
+    #[arg(long, help = "Stop after the first failing test")]
+    fail_fast: bool,
+    #[arg(long, help = "Record which lines the tests execute and print a summary per file")]
+    coverage: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "coverage",
+        help = "Write the coverage as an lcov tracefile to FILE"
+    )]
+    lcov: Option<PathBuf>,
+    #[arg(long, requires = "coverage", help = "Include the prelude in the coverage")]
+    coverage_include_prelude: bool,
+    #[command(flatten)]
+    limits: LimitArgs,
+}
+#[derive(clap::Args, Debug)]
+struct FmtArgs {
+    #[arg(
+        help = "Files or directories to format. Defaults to the current directory",
+        value_name = "PATHS"
+    )]
+    paths: Vec<PathBuf>,
+    #[arg(
+        long,
+        help = "Don't write the files; list the ones that aren't formatted and exit with status 1"
+    )]
+    check: bool,
+}
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum MessageFormat {
+    Human,
+    Json,
+}
+#[derive(clap::Args, Debug)]
+struct RunArgs {
+    #[command(flatten)]
+    project: ProjectArgs,
+    #[arg(
+        long,
+        help = "Count instructions per function and line and time calls, then print the hottest ones"
+    )]
+    profile: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "profile",
+        help = "Write the profile as folded stacks (for flamegraph tools) to FILE"
+    )]
+    profile_output: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 10,
+        requires = "profile",
+        help = "How many functions and lines the profile summary lists"
+    )]
+    profile_top: usize,
+    #[command(flatten)]
+    limits: LimitArgs,
     #[arg(
         help = "Arguments to pass to the Eon program",
         value_name = "ARGS",
@@ -38,94 +203,546 @@ struct Args {
 }
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
-    let mut source_files = Vec::new();
-    let contents = match std::fs::read_to_string(&args.file) {
-        Ok(c) => c,
-        Err(err) => {
-            eprintln!("Could not open file '{}': {}", args.file, err);
+    match args.command {
+        Some(Command::Run(run_args)) => run(run_args),
+        Some(Command::Check(check_args)) => check(check_args),
+        Some(Command::Fmt(fmt_args)) => fmt(fmt_args),
+        Some(Command::Test(test_args)) => test(test_args),
+        Some(Command::Debug(project_args)) => debug(project_args),
+        None => run(args.run),
+    }
+}
+fn load_project(args: &ProjectArgs) -> (String, Box<OsFileProvider>) {
+    let current_dir = std::env::current_dir().expect("Can't get current directory.");
+    let home_dir = home::home_dir().expect("Can't get home directory.");
+    let manifest = Manifest::find(&current_dir).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        exit(1);
+    });
+    let dirs = SearchDirs {
+        current_dir: &current_dir,
+        home_dir: &home_dir,
+        modules: args.modules.as_deref(),
+        shared_objects: args.shared_objects.as_deref(),
+    };
+    let project: Project = manifest::resolve_project(manifest.as_ref(), args.file.as_deref(), dirs)
+        .unwrap_or_else(|err| {
+            eprintln!("{err}");
             exit(1);
-        }
+        });
+    if let Err(err) = std::fs::metadata(&project.entry) {
+        eprintln!("Could not open file '{}': {}", project.entry.display(), err);
+        exit(1);
+    }
+    let linked: Vec<&str> = static_modules().iter().map(|module| module.name).collect();
+    if let Err(err) = project.check_native_modules(&linked) {
+        eprintln!("{err}");
+        exit(1);
+    }
+    let mut file_provider = OsFileProvider::with_roots(project.roots, project.shared_objects_dir);
+    for module in static_modules() {
+        file_provider.add_static_module(module);
+    }
+    (project.main_file_name, file_provider)
+}
+// native modules compiled into the binary
+fn static_modules() -> Vec<&'static StaticModule> {
+    #[cfg(feature = "static-modules")]
+    return vec![
+        &eon_module_os::MODULE,
+        &eon_module_random::MODULE,
+        &eon_module_term::MODULE,
+        &eon_module_time::MODULE,
+    ];
+    #[cfg(not(feature = "static-modules"))]
+    vec![]
+}
+fn new_vm(program: CompiledProgram, test: Option<&TestFunction>) -> Vm {
+    let mut vm = match test {
+        Some(test) => Vm::for_test(program, test),
+        None => Vm::new(program),
     };
-    let main_file_path: PathBuf = args.file.clone().into();
-    source_files.push(FileData::new(
-        main_file_path.clone(),
-        main_file_path.clone(),
-        contents,
-    ));
-    source_files.push(FileData::new(
-        "prelude.en".into(),
-        "prelude.en".into(),
-        eon_core::prelude::PRELUDE.to_string(),
-    ));
-    let modules_dir: PathBuf = match args.modules {
-        Some(modules) => {
-            let current_dir = std::env::current_dir().expect("Can't get current directory.");
-            current_dir.join(modules)
+    for module in static_modules() {
+        vm.add_static_module(module);
+    }
+    vm
+}
+fn check(args: CheckArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let (main_file_name, file_provider) = load_project(&args.project);
+    let Err(err) = eon_core::check(&main_file_name, file_provider) else {
+        return Ok(());
+    };
+    match args.message_format {
+        MessageFormat::Human => err.emit(),
+        MessageFormat::Json => {
+            for report in err.reports() {
+                println!("{}", report_to_json(&report));
+            }
         }
+    }
+    exit(1);
+}
+fn test(args: TestArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let files = match &args.project.file {
+        Some(file) => vec![PathBuf::from(file)],
         None => {
-            let home_dir = home::home_dir().expect("Can't get home directory.");
-            home_dir.join(".eon/modules")
+            let current_dir = std::env::current_dir().expect("Can't get current directory.");
+            let Some(manifest) = Manifest::find(&current_dir).unwrap_or_else(|err| {
+                eprintln!("{err}");
+                exit(1);
+            }) else {
+                eprintln!("No FILE given and no {} found in the current directory", manifest::MANIFEST_FILE);
+                exit(1);
+            };
+            let mut roots = manifest.project.source_roots.clone();
+            if roots.is_empty() {
+                roots.push(PathBuf::from("."));
+            }
+            let mut files = vec![];
+            for root in roots {
+                collect_source_files(&manifest.dir.join(root), &mut files)?;
+            }
+            files
         }
     };
-    let shared_objects_dir: PathBuf = match args.shared_objects {
-        Some(shared_objects_dir) => {
-            let current_dir = std::env::current_dir().expect("Can't get current directory.");
-            current_dir.join(shared_objects_dir)
+    let mut programs = vec![];
+    let mut roots: Vec<PathBuf> = vec![];
+    for file in files {
+        let project_args = ProjectArgs {
+            file: Some(file.to_string_lossy().into_owned()),
+            ..args.project.clone()
+        };
+        let (main_file_name, file_provider) = load_project(&project_args);
+        for root in file_provider.roots() {
+            if !roots.contains(root) {
+                roots.push(root.clone());
+            }
         }
-        None => {
-            let home_dir = home::home_dir().expect("Can't get home directory.");
-            home_dir.join(".eon/shared_objects")
+        match eon_core::compile_tests(&main_file_name, file_provider) {
+            Ok(program) => programs.push(program),
+            Err(err) => {
+                err.emit();
+                exit(1);
+            }
         }
+    }
+    let matches = |test: &TestFunction| {
+        args.filter
+            .as_ref()
+            .is_none_or(|filter| test.name.contains(filter.as_str()))
     };
-    let main_file_dir = if main_file_path.is_absolute() {
-        main_file_path.parent().unwrap()
+    let total: usize = programs.iter().map(|program| program.tests().len()).sum();
+    let selected: usize = programs
+        .iter()
+        .map(|program| program.tests().iter().filter(|test| matches(test)).count())
+        .sum();
+    println!("running {selected} tests");
+    // runtime errors other than panics abort the VM with a Rust panic; report those as failures.
+    // Any other Rust panic is a bug in the VM, so the previous hook still prints it
+    let previous_hook = Arc::new(std::panic::take_hook());
+    {
+        let previous_hook = previous_hook.clone();
+        std::panic::set_hook(Box::new(move |info| {
+            if !panic_message(info.payload()).contains("[traceback]") {
+                previous_hook(info);
+            }
+        }));
+    }
+    let mut passed = 0;
+    let mut failures = vec![];
+    let mut coverage = Coverage::new();
+    'programs: for program in &programs {
+        for test in program.tests().iter().filter(|test| matches(test)) {
+            let name = format!("{}::{}", test.file.display(), test.name);
+            print!("test {name} ... ");
+            io::stdout().flush().unwrap();
+            let mut vm = new_vm(program.clone(), Some(test));
+            args.limits.apply(&mut vm);
+            if args.coverage {
+                vm.enable_coverage();
+            }
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| run_vm(&mut vm)));
+            if let Some(test_coverage) = vm.coverage() {
+                coverage.merge(&test_coverage);
+            }
+            let error = match result {
+                Ok(Ok(())) => None,
+                Ok(Err(error)) => Some(error.to_string()),
+                Err(payload) => Some(panic_message(&*payload)),
+            };
+            match error {
+                None => {
+                    println!("ok");
+                    passed += 1;
+                }
+                Some(error) => {
+                    println!("FAILED");
+                    failures.push((name, test.clone(), error));
+                    if args.fail_fast {
+                        break 'programs;
+                    }
+                }
+            }
+        }
+    }
+    drop(std::panic::take_hook());
+    if let Ok(previous_hook) = Arc::try_unwrap(previous_hook) {
+        std::panic::set_hook(previous_hook);
+    }
+    if !failures.is_empty() {
+        println!("\nfailures:");
+        for (name, test, error) in &failures {
+            println!("\n---- {name} ({}:{}) ----", test.file.display(), test.line);
+            print!("{error}");
+        }
+    }
+    println!(
+        "\ntest result: {}. {} passed; {} failed; {} filtered out",
+        if failures.is_empty() { "ok" } else { "FAILED" },
+        passed,
+        failures.len(),
+        total - selected
+    );
+    if args.coverage {
+        if !args.coverage_include_prelude {
+            coverage.exclude_prelude();
+        }
+        println!("\ncoverage:\n{}", coverage.summary());
+        if let Some(path) = &args.lcov {
+            let lcov = coverage.lcov(|file| {
+                roots
+                    .iter()
+                    .map(|root| root.join(file))
+                    .find(|path| path.exists())
+                    .and_then(|path| std::fs::canonicalize(path).ok())
+                    .map_or(file.to_string(), |path| path.display().to_string())
+            });
+            std::fs::write(path, lcov)?;
+        }
+    }
+    if !failures.is_empty() {
+        exit(1);
+    }
+    Ok(())
+}
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else {
+        "unknown error".to_string()
+    }
+}
+fn fmt(args: FmtArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let paths = if args.paths.is_empty() {
+        vec![PathBuf::from(".")]
     } else {
-        &std::env::current_dir()
-            .unwrap()
-            .join(main_file_path.parent().unwrap())
+        args.paths
     };
-    let file_provider = OsFileProvider::new(main_file_dir.into(), modules_dir, shared_objects_dir);
-    let main_file_name = main_file_path.file_name().unwrap().to_str().unwrap();
-    match eon_core::compile_bytecode(main_file_name, file_provider) {
+    let mut files = vec![];
+    for path in &paths {
+        collect_source_files(path, &mut files)?;
+    }
+    let mut failed = false;
+    for file in files {
+        let source = std::fs::read_to_string(&file)?;
+        let file_data = eon_core::FileData::new(file.clone(), file.clone(), source);
+        match eon_core::formatter::format(&file_data) {
+            Ok(formatted) if formatted == file_data.source => {}
+            Ok(formatted) => {
+                if args.check {
+                    println!("{}", file.display());
+                    failed = true;
+                } else {
+                    std::fs::write(&file, formatted)?;
+                }
+            }
+            Err(err) => {
+                err.emit();
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        exit(1);
+    }
+    Ok(())
+}
+fn collect_source_files(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries: Vec<_> = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    entries.sort();
+    for entry in entries {
+        let hidden = entry
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if entry.is_dir() {
+            collect_source_files(&entry, files)?;
+        } else if entry.extension().is_some_and(|ext| ext == "en") {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+fn report_to_json(report: &Report) -> serde_json::Value {
+    serde_json::json!({
+        "severity": report.severity,
+        "message": report.message,
+        "labels": report.labels.iter().map(|label| serde_json::json!({
+            "primary": label.primary,
+            "file": label.file.display().to_string(),
+            "byte_start": label.range.start,
+            "byte_end": label.range.end,
+            "line_start": label.start_line,
+            "column_start": label.start_column,
+            "line_end": label.end_line,
+            "column_end": label.end_column,
+            "message": label.message,
+        })).collect::<Vec<_>>(),
+        "notes": report.notes,
+    })
+}
+fn run(args: RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let (main_file_name, file_provider) = load_project(&args.project);
+    match eon_core::compile_bytecode(&main_file_name, file_provider) {
         Ok(program) => {
-            let mut vm = eon_core::vm::Vm::new(program);
-            loop {
-                vm.run();
-                vm.gc();
-                if vm.is_done() {
+            let mut vm = new_vm(program, None);
+            args.limits.apply(&mut vm);
+            if args.profile {
+                vm.enable_profiling();
+            }
+            let result = run_vm(&mut vm);
+            if let Some(profile) = vm.profile() {
+                eprint!("\n{}", profile.summary(args.profile_top));
+                if let Some(path) = &args.profile_output {
+                    std::fs::write(path, profile.folded_stacks())?;
+                }
+            }
+            if let Err(error) = result {
+                eprint!("{error}");
+                exit(1);
+            }
+            Ok(())
+        }
+        Err(err) => {
+            err.emit();
+            exit(1);
+        }
+    }
+}
+const DEBUG_HELP: &str = "\
+break [FILE:]LINE   set a breakpoint (b)
+delete ID           remove a breakpoint
+continue            run to the next breakpoint (c)
+step                step to the next line, entering calls (s)
+next                step to the next line of this function (n)
+finish              run until this function returns (out)
+backtrace           list the call stack (bt)
+locals [FRAME]      show the variables of a frame, 0 is the innermost
+print NAME          show a variable of the innermost frame (p)
+list                show the source around the current line (l)
+quit                stop debugging (q)";
+fn debug(args: ProjectArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let (main_file_name, file_provider) = load_project(&args);
+    let roots = file_provider.roots().to_vec();
+    let program = match eon_core::compile_bytecode(&main_file_name, file_provider) {
+        Ok(program) => program,
+        Err(err) => {
+            err.emit();
+            exit(1);
+        }
+    };
+    let main_file = Path::new(&main_file_name)
+        .file_name()
+        .map_or(main_file_name.clone(), |name| name.to_string_lossy().into_owned());
+    let mut vm = new_vm(program, None);
+    let mut debugger = Debugger::new();
+    println!("Debugging {main_file_name}. Type `help` for a list of commands.");
+    let mut started = false;
+    let stdin = io::stdin();
+    loop {
+        print!("(eon) ");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            continue;
+        };
+        let arg = words.next();
+        let mode = match command {
+            "c" | "continue" | "r" | "run" => StepMode::Continue,
+            "s" | "step" => StepMode::In,
+            "n" | "next" => StepMode::Over,
+            "out" | "finish" => StepMode::Out,
+            "b" | "break" => {
+                let Some(arg) = arg else {
+                    for breakpoint in debugger.breakpoints() {
+                        println!("{}: {}:{}", breakpoint.id, breakpoint.file, breakpoint.line);
+                    }
+                    continue;
+                };
+                let (file, line) = match arg.rsplit_once(':') {
+                    Some((file, line)) => (file, line),
+                    None => (main_file.as_str(), arg),
+                };
+                let Ok(line) = line.parse() else {
+                    println!("Expected a line number, found `{line}`");
+                    continue;
+                };
+                match debugger.add_breakpoint(&vm, file, line) {
+                    Some(breakpoint) => println!(
+                        "Breakpoint {} at {}:{}",
+                        breakpoint.id, breakpoint.file, breakpoint.line
+                    ),
+                    None => println!("No code at or after {file}:{line}"),
+                }
+                continue;
+            }
+            "d" | "delete" => {
+                match arg.and_then(|arg| arg.parse().ok()) {
+                    Some(id) if debugger.remove_breakpoint(id) => {}
+                    _ => println!("No such breakpoint"),
+                }
+                continue;
+            }
+            "bt" | "backtrace" => {
+                for (i, frame) in vm.frames().iter().enumerate() {
+                    println!("#{i} {} at {}:{}", frame.function, frame.file, frame.line);
+                }
+                continue;
+            }
+            "locals" => {
+                let frame = arg.and_then(|arg| arg.parse().ok()).unwrap_or(0);
+                for local in vm.locals(frame) {
+                    println!("{} = {}", local.name, local.value);
+                }
+                continue;
+            }
+            "p" | "print" => {
+                let Some(name) = arg else {
+                    println!("Usage: print NAME");
+                    continue;
+                };
+                match vm.locals(0).into_iter().find(|local| local.name == name) {
+                    Some(local) => println!("{} = {}", local.name, local.value),
+                    None => println!("No variable `{name}` in the current frame"),
+                }
+                continue;
+            }
+            "l" | "list" => {
+                print_source(&roots, &vm);
+                continue;
+            }
+            "q" | "quit" => return Ok(()),
+            "h" | "help" => {
+                println!("{DEBUG_HELP}");
+                continue;
+            }
+            _ => {
+                println!("Unknown command `{command}`. Type `help` for a list of commands.");
+                continue;
+            }
+        };
+        if !started && mode != StepMode::Continue {
+            // the first step stops at the first line of the program
+            started = true;
+            print_location(&vm);
+            continue;
+        }
+        started = true;
+        loop {
+            let event = debugger.resume(&mut vm, mode);
+            vm.gc();
+            match event {
+                DebugEvent::PendingHostFunc(idx) => handle_host_func(&mut vm, idx),
+                DebugEvent::Breakpoint(id) => {
+                    print!("Breakpoint {id}, ");
+                    print_location(&vm);
+                    break;
+                }
+                DebugEvent::Step => {
+                    print_location(&vm);
+                    break;
+                }
+                DebugEvent::Done => {
+                    println!("Program finished");
                     return Ok(());
                 }
-                if let Some(error) = vm.get_error() {
+                DebugEvent::Error(error) => {
                     eprint!("{error}");
                     exit(1);
                 }
-                if let Some(pending_host_func) = vm.get_pending_host_func() {
-                    let host_func_args: HostFunctionArgs =
-                        HostFunctionArgs::from_vm(&mut vm, pending_host_func);
-                    match host_func_args {
-                        HostFunctionArgs::PrintString(s) => {
-                            print!("{s}");
-                            io::stdout().flush().unwrap();
-                            HostFunctionRet::PrintString.into_vm(&mut vm);
-                        }
-                        HostFunctionArgs::Readline => {
-                            let mut input = String::new();
-                            io::stdin().read_line(&mut input).unwrap();
-                            if input.ends_with('\n') {
-                                input.pop();
-                                if input.ends_with('\r') {
-                                    input.pop();
-                                }
-                            }
-                            HostFunctionRet::Readline(input).into_vm(&mut vm);
-                        }
-                    }
-                }
             }
         }
-        Err(err) => {
-            err.emit();
-            exit(1);
+    }
+}
+fn print_location(vm: &Vm) {
+    let location = vm.source_location();
+    println!("{} at {}:{}", location.function, location.file, location.line);
+}
+fn print_source(roots: &[PathBuf], vm: &Vm) {
+    let location = vm.source_location();
+    let source = roots
+        .iter()
+        .find_map(|root| std::fs::read_to_string(root.join(&location.file)).ok());
+    let Some(source) = source else {
+        println!("Source of {} is not available", location.file);
+        return;
+    };
+    let current = location.line as usize;
+    let first = current.saturating_sub(5).max(1);
+    for (i, line) in source.lines().enumerate().skip(first - 1).take(11) {
+        let marker = if i + 1 == current { "->" } else { "  " };
+        println!("{marker} {:4} {line}", i + 1);
+    }
+}
+fn run_vm(vm: &mut Vm) -> Result<(), Box<VmError>> {
+    loop {
+        vm.run();
+        vm.gc();
+        match vm.status() {
+            VmStatus::Done => return Ok(()),
+            VmStatus::Error(error) => return Err(error),
+            VmStatus::PendingHostFunc(pending_host_func) => {
+                handle_host_func(vm, pending_host_func)
+            }
+            // the limits given on the command line are final
+            VmStatus::OutOfSteps | VmStatus::Timeout | VmStatus::OutOfMemory => {
+                return Err(vm.limit_error().expect("the vm stopped without reaching a limit"));
+            }
         }
     }
-}
\ No newline at end of file
+}
+fn handle_host_func(vm: &mut Vm, pending_host_func: u16) {
+    let host_func_args: HostFunctionArgs = HostFunctionArgs::from_vm(vm, pending_host_func);
+    match host_func_args {
+        HostFunctionArgs::PrintString(s) => {
+            print!("{s}");
+            io::stdout().flush().unwrap();
+            HostFunctionRet::PrintString.into_vm(vm);
+        }
+        HostFunctionArgs::Readline => {
+            let mut input = String::new();
+            io::stdin().read_line(&mut input).unwrap();
+            if input.ends_with('\n') {
+                input.pop();
+                if input.ends_with('\r') {
+                    input.pop();
+                }
+            }
+            HostFunctionRet::Readline(input).into_vm(vm);
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use serde::Deserialize;
+use std::path::{Component, Path, PathBuf};
+pub(crate) const MANIFEST_FILE: &str = "eon.toml";
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Manifest {
+    pub(crate) project: ProjectSection,
+    #[serde(skip)]
+    pub(crate) dir: PathBuf,
+}
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ProjectSection {
+    #[serde(rename = "name")]
+    pub(crate) _name: Option<String>,
+    pub(crate) entry: Option<PathBuf>,
+    #[serde(default)]
+    pub(crate) source_roots: Vec<PathBuf>,
+    #[serde(default)]
+    pub(crate) module_paths: Vec<PathBuf>,
+    pub(crate) shared_objects: Option<PathBuf>,
+    #[serde(default)]
+    pub(crate) native_modules: Vec<String>,
+}
+impl Manifest {
+    pub(crate) fn find(dir: &Path) -> Result<Option<Manifest>, String> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|err| format!("Could not read '{}': {}", path.display(), err))?;
+        Self::parse(&contents, dir)
+            .map(Some)
+            .map_err(|err| format!("Invalid manifest '{}': {}", path.display(), err))
+    }
+    pub(crate) fn parse(contents: &str, dir: &Path) -> Result<Manifest, toml::de::Error> {
+        let mut manifest: Manifest = toml::from_str(contents)?;
+        manifest.dir = dir.to_path_buf();
+        Ok(manifest)
+    }
+}
+#[derive(Debug)]
+pub(crate) struct Project {
+    pub(crate) entry: PathBuf,
+    pub(crate) main_file_name: String,
+    pub(crate) roots: Vec<PathBuf>,
+    pub(crate) shared_objects_dir: PathBuf,
+    pub(crate) native_modules: Vec<String>,
+}
+pub(crate) struct SearchDirs<'a> {
+    pub(crate) current_dir: &'a Path,
+    pub(crate) home_dir: &'a Path,
+    pub(crate) modules: Option<&'a str>,
+    pub(crate) shared_objects: Option<&'a str>,
+}
+pub(crate) fn resolve_project(
+    manifest: Option<&Manifest>,
+    file: Option<&str>,
+    dirs: SearchDirs,
+) -> Result<Project, String> {
+    let entry = match (file, manifest) {
+        (Some(file), _) => normalize(&dirs.current_dir.join(file)),
+        (None, Some(manifest)) => match &manifest.project.entry {
+            Some(entry) => normalize(&manifest.dir.join(entry)),
+            None => return Err(format!("No FILE given and {MANIFEST_FILE} has no `entry`")),
+        },
+        (None, None) => {
+            return Err(format!(
+                "No FILE given and no {MANIFEST_FILE} found in the current directory"
+            ));
+        }
+    };
+    let mut roots: Vec<PathBuf> = manifest
+        .map(|manifest| {
+            manifest
+                .project
+                .source_roots
+                .iter()
+                .map(|root| normalize(&manifest.dir.join(root)))
+                .collect()
+        })
+        .unwrap_or_default();
+    let main_file_name = match roots.iter().find_map(|root| entry.strip_prefix(root).ok()) {
+        Some(relative) => relative.to_path_buf(),
+        None => {
+            roots.insert(0, entry.parent().unwrap().to_path_buf());
+            PathBuf::from(entry.file_name().unwrap())
+        }
+    };
+    if let Some(manifest) = manifest {
+        for path in &manifest.project.module_paths {
+            roots.push(normalize(&manifest.dir.join(path)));
+        }
+    }
+    roots.push(match dirs.modules {
+        Some(modules) => normalize(&dirs.current_dir.join(modules)),
+        None => dirs.home_dir.join(".eon/modules"),
+    });
+    let shared_objects_dir = if let Some(shared_objects) = dirs.shared_objects {
+        normalize(&dirs.current_dir.join(shared_objects))
+    } else if let Some(manifest) = manifest
+        && let Some(shared_objects) = &manifest.project.shared_objects
+    {
+        normalize(&manifest.dir.join(shared_objects))
+    } else {
+        dirs.home_dir.join(".eon/shared_objects")
+    };
+    Ok(Project {
+        entry,
+        main_file_name: main_file_name.to_str().unwrap().to_string(),
+        roots,
+        shared_objects_dir,
+        native_modules: manifest
+            .map(|manifest| manifest.project.native_modules.clone())
+            .unwrap_or_default(),
+    })
+}
+impl Project {
+    // `linked` are the modules compiled into the binary, which don't need a shared object
+    pub(crate) fn check_native_modules(&self, linked: &[&str]) -> Result<(), String> {
+        for name in &self.native_modules {
+            if linked.contains(&name.as_str()) {
+                continue;
+            }
+            let module_file = format!("{name}.en");
+            if !self.roots.iter().any(|root| root.join(&module_file).is_file()) {
+                return Err(format!(
+                    "Native module `{name}` is required by {MANIFEST_FILE} but {module_file} was not found in any module search path"
+                ));
+            }
+            let shared_object = self.shared_objects_dir.join(format!(
+                "{}eon_module_{}{}",
+                std::env::consts::DLL_PREFIX,
+                name,
+                std::env::consts::DLL_SUFFIX
+            ));
+            if !shared_object.is_file() {
+                return Err(format!(
+                    "Native module `{name}` is required by {MANIFEST_FILE} but '{}' does not exist",
+                    shared_object.display()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+fn normalize(path: &Path) -> PathBuf {
+    let mut ret = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(ret.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                ret.pop();
+            }
+            _ => ret.push(component),
+        }
+    }
+    ret
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn dirs<'a>(current_dir: &'a Path) -> SearchDirs<'a> {
+        SearchDirs {
+            current_dir,
+            home_dir: Path::new("/home/user"),
+            modules: None,
+            shared_objects: None,
+        }
+    }
+    #[test]
+    fn parse_manifest() {
+        let manifest = Manifest::parse(
+            r#"
+[project]
+name = "snake"
+entry = "src/main.en"
+source_roots = ["src", "lib"]
+module_paths = ["vendor"]
+shared_objects = "target/release"
+native_modules = ["term", "time"]
+"#,
+            Path::new("/proj"),
+        )
+        .unwrap();
+        assert_eq!(manifest.project._name.as_deref(), Some("snake"));
+        assert_eq!(manifest.project.source_roots.len(), 2);
+        assert_eq!(manifest.project.native_modules, vec!["term", "time"]);
+    }
+    #[test]
+    fn unknown_key_is_rejected() {
+        assert!(Manifest::parse("[project]\nentri = \"main.en\"\n", Path::new("/proj")).is_err());
+    }
+    #[test]
+    fn roots_in_search_order() {
+        let manifest = Manifest::parse(
+            r#"
+[project]
+entry = "src/app/main.en"
+source_roots = ["src", "./lib"]
+module_paths = ["vendor", "../shared"]
+shared_objects = "target/release"
+"#,
+            Path::new("/proj"),
+        )
+        .unwrap();
+        let project = resolve_project(Some(&manifest), None, dirs(Path::new("/proj"))).unwrap();
+        assert_eq!(project.main_file_name, "app/main.en");
+        assert_eq!(
+            project.roots,
+            vec![
+                PathBuf::from("/proj/src"),
+                PathBuf::from("/proj/lib"),
+                PathBuf::from("/proj/vendor"),
+                PathBuf::from("/shared"),
+                PathBuf::from("/home/user/.eon/modules"),
+            ]
+        );
+        assert_eq!(
+            project.shared_objects_dir,
+            PathBuf::from("/proj/target/release")
+        );
+    }
+    #[test]
+    fn file_argument_overrides_entry() {
+        let manifest = Manifest::parse(
+            "[project]\nentry = \"src/main.en\"\nsource_roots = [\"src\"]\n",
+            Path::new("/proj"),
+        )
+        .unwrap();
+        let mut search = dirs(Path::new("/proj"));
+        search.modules = Some("modules");
+        search.shared_objects = Some("so");
+        let project =
+            resolve_project(Some(&manifest), Some("examples/demo.en"), search).unwrap();
+        assert_eq!(project.main_file_name, "demo.en");
+        assert_eq!(
+            project.roots,
+            vec![
+                PathBuf::from("/proj/examples"),
+                PathBuf::from("/proj/src"),
+                PathBuf::from("/proj/modules"),
+            ]
+        );
+        assert_eq!(project.shared_objects_dir, PathBuf::from("/proj/so"));
+    }
+    #[test]
+    fn no_manifest_and_no_file() {
+        assert!(resolve_project(None, None, dirs(Path::new("/proj"))).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::vm::{PackedValue, ValueTrait, Vm};
+use crate::vm::{Finalizer, PackedValue, ProgramCounter, ValueTrait, Vm};
 use crate::{
     FileAst, FileData, ItemKind,
     ast::{FileDatabase, Type, TypeDefKind, TypeKind},
@@ -6,10 +6,15 @@ use crate::{
 };
 use core::str;
 use std::{
+    any::{Any, TypeId},
+    cell::Cell,
     ffi::{c_char, c_void},
     fs::{self, read_to_string},
+    hash::{Hash, Hasher},
+    panic::{self, AssertUnwindSafe},
     path::{Path, PathBuf},
     rc::Rc,
+    sync::Once,
 };
 use utils::swrite;
 #[repr(C)]
@@ -32,6 +37,15 @@ pub struct EonVmFunctions {
     pub deconstruct_array: unsafe extern "C" fn(vm: *mut c_void),
     pub deconstruct_variant: unsafe extern "C" fn(vm: *mut c_void),
     pub array_len: unsafe extern "C" fn(vm: *mut c_void) -> usize,
+    pub push_handle:
+        unsafe extern "C" fn(vm: *mut c_void, ptr: *mut c_void, tag: u64, finalizer: Option<Finalizer>),
+    pub view_handle: unsafe extern "C" fn(vm: *mut c_void) -> HandleView,
+    pub push_handle_view: unsafe extern "C" fn(vm: *mut c_void, view: HandleView),
+    pub push_function: unsafe extern "C" fn(vm: *mut c_void, func: u32),
+    pub pop_function: unsafe extern "C" fn(vm: *mut c_void) -> u32,
+    // false if the call failed, in which case nothing is pushed
+    pub call_function: unsafe extern "C" fn(vm: *mut c_void, func: u32, nargs: u8) -> bool,
+    pub foreign_panic: unsafe extern "C" fn(vm: *mut c_void, name: StringView, message: StringView),
 }
 pub const EON_VM_FUNCS: EonVmFunctions = EonVmFunctions {
     push_int: EON_vm_push_int,
@@ -52,6 +66,13 @@ pub const EON_VM_FUNCS: EonVmFunctions = EonVmFunctions {
     deconstruct_array: EON_vm_deconstruct_array,
     deconstruct_variant: EON_vm_deconstruct_enum,
     array_len: EON_vm_array_len,
+    push_handle: EON_vm_push_handle,
+    view_handle: EON_vm_view_handle,
+    push_handle_view: EON_vm_push_handle_view,
+    push_function: EON_vm_push_function,
+    pop_function: EON_vm_pop_function,
+    call_function: EON_vm_call_function,
+    foreign_panic: EON_vm_foreign_panic,
 };
 #[unsafe(no_mangle)]
 unsafe extern "C" fn EON_vm_push_int(vm: *mut c_void, n: i64) {
@@ -173,6 +194,128 @@ unsafe extern "C" fn EON_vm_array_len(vm: *mut c_void) -> usize {
     let vm = unsafe { (vm as *mut Vm<PackedValue>).as_mut().unwrap() };
     vm.array_len()
 }
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct HandleView {
+    pub ptr: *mut c_void,
+    pub tag: u64,
+    // the vm's object, only valid until the foreign function returns
+    pub owner: *const c_void,
+}
+#[unsafe(no_mangle)]
+unsafe extern "C" fn EON_vm_push_handle(
+    vm: *mut c_void,
+    ptr: *mut c_void,
+    tag: u64,
+    finalizer: Option<Finalizer>,
+) {
+    let vm = unsafe { (vm as *mut Vm<PackedValue>).as_mut().unwrap() };
+    unsafe { vm.push_handle(ptr, tag, finalizer) };
+}
+#[unsafe(no_mangle)]
+unsafe extern "C" fn EON_vm_view_handle(vm: *mut c_void) -> HandleView {
+    let vm = unsafe { (vm as *mut Vm<PackedValue>).as_mut().unwrap() };
+    vm.view_handle()
+}
+#[unsafe(no_mangle)]
+unsafe extern "C" fn EON_vm_push_handle_view(vm: *mut c_void, view: HandleView) {
+    let vm = unsafe { (vm as *mut Vm<PackedValue>).as_mut().unwrap() };
+    unsafe { vm.push_handle_view(view) };
+}
+#[unsafe(no_mangle)]
+unsafe extern "C" fn EON_vm_push_function(vm: *mut c_void, func: u32) {
+    let vm = unsafe { (vm as *mut Vm<PackedValue>).as_mut().unwrap() };
+    vm.push_function(ProgramCounter(func));
+}
+#[unsafe(no_mangle)]
+unsafe extern "C" fn EON_vm_pop_function(vm: *mut c_void) -> u32 {
+    let vm = unsafe { (vm as *mut Vm<PackedValue>).as_mut().unwrap() };
+    vm.pop_function().0
+}
+#[unsafe(no_mangle)]
+unsafe extern "C" fn EON_vm_call_function(vm: *mut c_void, func: u32, nargs: u8) -> bool {
+    let vm = unsafe { (vm as *mut Vm<PackedValue>).as_mut().unwrap() };
+    vm.call_function(ProgramCounter(func), nargs)
+}
+#[unsafe(no_mangle)]
+unsafe extern "C" fn EON_vm_foreign_panic(vm: *mut c_void, name: StringView, message: StringView) {
+    let vm = unsafe { (vm as *mut Vm<PackedValue>).as_mut().unwrap() };
+    vm.fail_foreign_function(name.to_owned(), message.to_owned());
+}
+thread_local! {
+    // foreign functions running inside `catch_foreign_panic` on this thread
+    static FOREIGN_CALLS: Cell<u32> = const { Cell::new(0) };
+}
+static QUIET_PANIC_HOOK: Once = Once::new();
+/// Runs the body of a foreign function for the generated bindings and catches a panic. The panic
+/// hook doesn't print it, since `report_panic` turns it into an error of the program.
+pub fn catch_foreign_panic<R>(f: impl FnOnce() -> R) -> Result<R, Box<dyn Any + Send>> {
+    QUIET_PANIC_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if FOREIGN_CALLS.get() == 0 {
+                previous(info);
+            }
+        }));
+    });
+    FOREIGN_CALLS.set(FOREIGN_CALLS.get() + 1);
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    FOREIGN_CALLS.set(FOREIGN_CALLS.get() - 1);
+    result
+}
+/// Called by the generated bindings when a foreign function panics, so the program fails with an
+/// error instead of unwinding into the vm.
+///
+/// # Safety
+/// The caller must ensure that `vm` is a valid pointer to a VM instance and `vm_funcs` is a valid reference.
+pub unsafe fn report_panic(
+    vm: *mut c_void,
+    vm_funcs: &EonVmFunctions,
+    name: &str,
+    payload: Box<dyn Any + Send>,
+) {
+    let message = match payload.downcast_ref::<&str>() {
+        Some(s) => s.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(s) => s.clone(),
+            None => "panicked".to_string(),
+        },
+    };
+    unsafe {
+        (vm_funcs.foreign_panic)(
+            vm,
+            StringView::from_string(name),
+            StringView::from_string(&message),
+        )
+    };
+}
+pub type ForeignFunction = unsafe extern "C" fn(vm: *mut c_void, vm_funcs: *const EonVmFunctions);
+// A native module linked into the binary. `generate_bindings_for_crate` emits one as `MODULE`
+// in the module's crate.
+pub struct StaticModule {
+    pub name: &'static str,
+    // (path like `os.en`, source) of the module's Eon files
+    pub files: &'static [(&'static str, &'static str)],
+    pub functions: &'static [(&'static str, ForeignFunction)],
+}
+impl StaticModule {
+    pub fn function(&self, symbol: &str) -> Option<ForeignFunction> {
+        self.functions
+            .iter()
+            .find(|(name, _)| *name == symbol)
+            .map(|(_, f)| *f)
+    }
+    // whether `path` is where the module's shared library would be loaded from
+    pub fn provides_library(&self, path: &str) -> bool {
+        let filename = format!(
+            "{}eon_module_{}{}",
+            std::env::consts::DLL_PREFIX,
+            self.name,
+            std::env::consts::DLL_SUFFIX
+        );
+        Path::new(path).file_name() == Some(filename.as_ref())
+    }
+}
 use std::env::current_dir;
 pub fn generate_bindings_for_crate() {
     let current_dir = current_dir().unwrap();
@@ -188,6 +331,8 @@ pub fn generate_bindings_for_crate() {
     toplevel_eon_file.pop();
     toplevel_eon_file = toplevel_eon_file.join(format!("{package_name}.en"));
     let output = &mut String::new();
+    // the `StaticModule` written at the end
+    let mut module = ModuleTable::default();
     write_header(output, &package_name);
     let mut file_db = FileDatabase::new();
     {
@@ -200,10 +345,11 @@ pub fn generate_bindings_for_crate() {
         let file_id = file_db.add(file_data);
         let file_data = file_db.get(file_id).unwrap();
         let ast = parse_or_err(file_id, file_data).unwrap();
-        add_items_from_ast(ast, output);
+        module.files.push(format!("{package_name}.en"));
+        add_items_from_ast(ast, output, &format!("ffi::{package_name}"), &mut module);
     }
     let mut prefixes = vec![package_name.clone()];
-    find_eon_files(&package_dir, &mut prefixes, &mut file_db, output).unwrap();
+    find_eon_files(&package_dir, &mut prefixes, &mut file_db, output, &mut module).unwrap();
     {
         output.push_str(
             r#"
@@ -212,6 +358,7 @@ pub fn generate_bindings_for_crate() {
 "#,
         );
     }
+    write_static_module(output, &package_name, &module);
     let output_path = current_dir.join("src").join("lib.rs");
     std::fs::write(&output_path, output).unwrap();
     let status = std::process::Command::new("cargo")
@@ -235,7 +382,34 @@ pub mod ffi {{
     "#
     )
 }
-fn add_items_from_ast(ast: Rc<FileAst>, output: &mut String) {
+#[derive(Default)]
+struct ModuleTable {
+    // relative to the modules directory
+    files: Vec<String>,
+    // (symbol, path of the generated function)
+    functions: Vec<(String, String)>,
+}
+fn write_static_module(output: &mut String, package_name: &str, module: &ModuleTable) {
+    swrite!(
+        output,
+        "pub static MODULE: eon_core::addons::StaticModule = eon_core::addons::StaticModule {{ name: \"{package_name}\", files: &["
+    );
+    for file in &module.files {
+        // lib.rs is in modules/<package>/rust_project/src
+        swrite!(output, "(\"{file}\", include_str!(\"../../../{file}\")),");
+    }
+    output.push_str("], functions: &[");
+    for (symbol, path) in &module.functions {
+        swrite!(output, "(\"{symbol}\", {path}),");
+    }
+    output.push_str("], };");
+}
+fn add_items_from_ast(
+    ast: Rc<FileAst>,
+    output: &mut String,
+    rust_module: &str,
+    module: &mut ModuleTable,
+) {
     for item in ast.items.iter() {
         match &*item.kind {
             ItemKind::TypeDef(tydef) => match &**tydef {
@@ -409,6 +583,9 @@ fn add_items_from_ast(ast: Rc<FileAst>, output: &mut String) {
                 let elems: Vec<_> = ast.name.split(std::path::MAIN_SEPARATOR_STR).collect();
                 let package_name = elems.last().unwrap().to_string();
                 let symbol = make_foreign_func_name(&f.name.v, &elems);
+                module
+                    .functions
+                    .push((symbol.clone(), format!("{rust_module}::{}", f.name.v)));
                 output.push_str("/// # Safety\n");
                 output.push_str("/// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.\n");
                 swrite!(output, "#[unsafe(export_name = \"{symbol}\")]");
@@ -419,6 +596,9 @@ fn add_items_from_ast(ast: Rc<FileAst>, output: &mut String) {
                 );
                 output.push_str("unsafe {");
                 output.push_str("let vm_funcs: &EonVmFunctions = &*vm_funcs;");
+                output.push_str(
+                    "let result = catch_foreign_panic(|| {",
+                );
                 for (name, ty) in f.args.iter().rev() {
                     let Some(ty) = ty else { panic!() };
                     if matches!(&*ty.kind, TypeKind::Void) {
@@ -456,6 +636,12 @@ fn add_items_from_ast(ast: Rc<FileAst>, output: &mut String) {
                 }
                 output.push_str(");");
                 output.push_str("ret.to_vm_unsafe(vm, vm_funcs);");
+                output.push_str("});");
+                let qualified_name = format!("{}.{}", elems.join("."), f.name.v);
+                swrite!(
+                    output,
+                    "if let Err(payload) = result {{ report_panic(vm, vm_funcs, \"{qualified_name}\", payload); }}"
+                );
                 output.push('}');
                 output.push('}');
             }
@@ -483,8 +669,13 @@ pub(crate) fn name_of_ty(ty: &Rc<Type>) -> String {
             let mut s = ident.v.clone();
             if s == "option" {
                 s = "Option".into();
+            } else if s == "result" {
+                s = "Result".into();
             } else if s == "array" {
                 s = "Vec".into();
+            } else if s == "handle" {
+                // the native type behind a handle is only known to the module's code
+                return "Handle".into();
             }
             s.push('<');
             for param in params {
@@ -494,7 +685,7 @@ pub(crate) fn name_of_ty(ty: &Rc<Type>) -> String {
             s.push('>');
             s
         }
-        TypeKind::Function(..) => "FunctionNotSupported".into(),
+        TypeKind::Function(..) => "Function".into(),
         TypeKind::Poly(..) => "PolyNotSupported".into(),
     }
 }
@@ -503,13 +694,14 @@ fn find_eon_files(
     prefixes: &mut Vec<String>,
     file_db: &mut FileDatabase,
     output: &mut String,
+    module: &mut ModuleTable,
 ) -> std::io::Result<()> {
     for entry in fs::read_dir(search_dir)? {
         let entry = entry?;
         let path = entry.path();
         if path.is_dir() {
             prefixes.push(path.file_name().unwrap().to_str().unwrap().to_string());
-            find_eon_files(&path, prefixes, file_db, output)?;
+            find_eon_files(&path, prefixes, file_db, output, module)?;
             prefixes.pop();
         } else if let Some(ext) = path.extension()
             && ext == "en" {
@@ -538,7 +730,11 @@ fn find_eon_files(
                 let file_id = file_db.add(file_data);
                 let file_data = file_db.get(file_id).unwrap();
                 let ast = parse_or_err(file_id, file_data).unwrap();
-                add_items_from_ast(ast, output);
+                let mut file = prefixes.join("/");
+                swrite!(&mut file, "/{no_extension}.en");
+                module.files.push(file);
+                let rust_module = format!("ffi::{}::{no_extension}", prefixes[0]);
+                add_items_from_ast(ast, output, &rust_module, module);
                 output.push('}');
             }
     }
@@ -631,7 +827,11 @@ where
                     let t = T::from_vm_unsafe(vm, vm_funcs);
                     Some(t)
                 }
-                1 => None,
+                1 => {
+                    // none still carries a void payload
+                    <()>::from_vm_unsafe(vm, vm_funcs);
+                    None
+                }
                 _ => panic!("unexpected tag for Option type {tag}"),
             }
         }
@@ -714,6 +914,131 @@ where
         }
     }
 }
+// A native value stored in the vm, seen by Eon code as an opaque `handle<T>`. The vm drops the
+// value once the handle becomes unreachable. A new handle that is never returned to the vm
+// drops its value itself.
+pub struct Handle {
+    view: HandleView,
+    // set until the value is handed to the vm
+    finalizer: Option<Finalizer>,
+}
+impl Handle {
+    pub fn new<T: Send + 'static>(value: T) -> Self {
+        Self {
+            view: HandleView {
+                ptr: Box::into_raw(Box::new(value)) as *mut c_void,
+                tag: handle_tag::<T>(),
+                owner: std::ptr::null(),
+            },
+            finalizer: Some(drop_boxed::<T>),
+        }
+    }
+    /// The value if the handle holds a `T`, or None if it holds another type.
+    ///
+    /// # Safety
+    /// A handle read from the vm points at the vm's object and is only valid until the foreign
+    /// function it was passed to returns. The caller must not use it, or the reference, after that.
+    pub unsafe fn get<T: 'static>(&self) -> Option<&T> {
+        (self.view.tag == handle_tag::<T>()).then(|| unsafe { &*(self.view.ptr as *const T) })
+    }
+    /// Like [`Handle::get`], with a mutable reference.
+    ///
+    /// # Safety
+    /// Same as [`Handle::get`].
+    pub unsafe fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        (self.view.tag == handle_tag::<T>()).then(|| unsafe { &mut *(self.view.ptr as *mut T) })
+    }
+}
+impl Drop for Handle {
+    fn drop(&mut self) {
+        if let Some(finalizer) = self.finalizer.take() {
+            unsafe { finalizer(self.view.ptr) }
+        }
+    }
+}
+// a hash of the TypeId. Handles are only read by the module that created them, so the tag only
+// has to agree within one build of that module
+fn handle_tag<T: 'static>() -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    TypeId::of::<T>().hash(&mut hasher);
+    hasher.finish()
+}
+unsafe extern "C" fn drop_boxed<T>(ptr: *mut c_void) {
+    drop(unsafe { Box::from_raw(ptr as *mut T) });
+}
+impl VmFfiType for Handle {
+    unsafe fn from_vm_unsafe(vm: *mut c_void, vm_funcs: &EonVmFunctions) -> Self {
+        unsafe {
+            let view = (vm_funcs.view_handle)(vm);
+            (vm_funcs.pop)(vm);
+            Self {
+                view,
+                finalizer: None,
+            }
+        }
+    }
+    unsafe fn to_vm_unsafe(mut self, vm: *mut c_void, vm_funcs: &EonVmFunctions) {
+        unsafe {
+            match self.finalizer.take() {
+                Some(finalizer) => {
+                    (vm_funcs.push_handle)(vm, self.view.ptr, self.view.tag, Some(finalizer))
+                }
+                None => (vm_funcs.push_handle_view)(vm, self.view),
+            }
+        }
+    }
+}
+// An Eon function passed to a native function. It can only be called until the native function
+// returns.
+pub struct Function {
+    func: u32,
+    vm: *mut c_void,
+    vm_funcs: *const EonVmFunctions,
+}
+impl Function {
+    /// Calls the function and returns its result, or None if the call failed. The error is
+    /// reported once the native function returns, and whatever it returns is ignored. Hitting the
+    /// vm's fuel, deadline or memory limit during the call is such an error and can't be resumed.
+    ///
+    /// # Safety
+    /// The function holds a pointer to the vm that passed it in, so it must only be called before
+    /// the foreign function that received it returns.
+    pub unsafe fn call<A: VmFfiArgs, R: VmFfiType>(&self, args: A) -> Option<R> {
+        unsafe {
+            let vm_funcs = &*self.vm_funcs;
+            let nargs = args.push_args(self.vm, vm_funcs);
+            if !(vm_funcs.call_function)(self.vm, self.func, nargs) {
+                return None;
+            }
+            Some(R::from_vm_unsafe(self.vm, vm_funcs))
+        }
+    }
+}
+impl VmFfiType for Function {
+    unsafe fn from_vm_unsafe(vm: *mut c_void, vm_funcs: &EonVmFunctions) -> Self {
+        unsafe {
+            Self {
+                func: (vm_funcs.pop_function)(vm),
+                vm,
+                vm_funcs,
+            }
+        }
+    }
+    unsafe fn to_vm_unsafe(self, vm: *mut c_void, vm_funcs: &EonVmFunctions) {
+        unsafe { (vm_funcs.push_function)(vm, self.func) }
+    }
+}
+// The arguments of a call back into Eon
+pub trait VmFfiArgs {
+    /// # Safety
+    /// The caller must ensure that `vm` is a valid pointer to a VM instance and `vm_funcs` is a valid reference.
+    unsafe fn push_args(self, vm: *mut c_void, vm_funcs: &EonVmFunctions) -> u8;
+}
+impl VmFfiArgs for () {
+    unsafe fn push_args(self, _vm: *mut c_void, _vm_funcs: &EonVmFunctions) -> u8 {
+        0
+    }
+}
 macro_rules! replace_expr {
     ($t:tt, $e:expr_2021) => {
         $e
@@ -736,6 +1061,14 @@ macro_rules! tuple_impls {
                 (vm_funcs.construct_struct)(vm, count as u16);
             }}
         }
+        impl< $($name: VmFfiType),+ > VmFfiArgs for ( $($name,)+ ) {
+            unsafe fn push_args(self, vm: *mut c_void, vm_funcs: &EonVmFunctions) -> u8 { unsafe {
+                #[allow(non_snake_case)]
+                let ($($name,)+) = self;
+                $( $name.to_vm_unsafe(vm, vm_funcs); )+
+                [$( replace_expr!($name, 1) ),+].len() as u8
+            }}
+        }
     };
 }
 tuple_impls!(A);
@@ -10,6 +10,8 @@ pub(crate) enum Line {
         lineno: usize,
         file_id: u32,
         func_id: u32,
+        // byte range of the expression the instruction was emitted for
+        span: (u32, u32),
     },
     Label(Label),
 }
@@ -33,6 +35,7 @@ impl LineVariant for Instr {
             lineno: st.curr_lineno,
             file_id: st.curr_file,
             func_id: st.curr_func,
+            span: st.curr_span,
         }
     }
 }
@@ -85,6 +88,8 @@ pub enum Instr {
     Jump(Label),
     JumpIf(Label),
     Call(usize, Label),
+    // a call whose result is returned right away; it reuses the caller's frame
+    TailCall(usize, Label),
     CallFuncObj,
     CallExtern(u32),
     Return(u32),
@@ -154,6 +159,9 @@ impl Display for Instr {
             Instr::Call(nargs, addr) => {
                 write!(f, "call {} {}", nargs, addr)
             }
+            Instr::TailCall(nargs, addr) => {
+                write!(f, "tail_call {} {}", nargs, addr)
+            }
             Instr::CallExtern(func_id) => write!(f, "call_extern {func_id}"),
             Instr::CallFuncObj => write!(f, "call_func_obj"),
             Instr::Return(nargs) => write!(f, "return {nargs}"),
@@ -268,6 +276,12 @@ fn instr_to_vminstr(
                 .get(label)
                 .unwrap_or_else(|| panic!("Could not find label: {label}")) as u32,
         )),
+        Instr::TailCall(nargs, label) => VmInstr::TailCall(CallData::new(
+            *nargs as u32,
+            *label_to_idx
+                .get(label)
+                .unwrap_or_else(|| panic!("Could not find label: {label}")) as u32,
+        )),
         Instr::CallExtern(func_id) => VmInstr::CallExtern(*func_id),
         Instr::CallFuncObj => VmInstr::CallFuncObj,
         Instr::Return(nargs) => VmInstr::Return(*nargs),
@@ -261,6 +261,7 @@ pub(crate) struct Item {
     pub(crate) kind: Rc<ItemKind>,
     pub(crate) loc: Location,
     pub(crate) id: NodeId,
+    pub(crate) public: bool,
 }
 impl std::hash::Hash for Item {
     fn hash<H: Hasher>(&self, state: &mut H) {
@@ -324,6 +325,7 @@ pub(crate) struct FuncDef {
     pub(crate) args: Vec<ArgMaybeAnnotated>,
     pub(crate) ret_type: Option<Rc<Type>>,
     pub(crate) body: Rc<Expr>,
+    pub(crate) test: bool,
 }
 #[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub(crate) struct FuncDecl {
@@ -416,6 +418,11 @@ pub(crate) enum ExprKind {
     FuncAp(Rc<Expr>, Vec<Rc<Expr>>),
     MemberFuncAp(Option<Rc<Expr>>, Rc<Identifier>, Vec<Rc<Expr>>),
     Tuple(Vec<Rc<Expr>>),
+    StructLiteral(
+        Option<Rc<Identifier>>,
+        Option<Rc<Expr>>,
+        Vec<(Rc<Identifier>, Rc<Expr>)>,
+    ),
     MemberAccess(Rc<Expr>, Rc<Identifier>),
     MemberAccessLeadingDot(Rc<Identifier>),
     IndexAccess(Rc<Expr>, Rc<Expr>),
@@ -0,0 +1,115 @@
+use crate::vm::{ProgramCounter, Vm};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+// Line coverage of one or more runs. Only lines that have code are listed, so blank lines,
+// comments and declarations never count as misses.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    // file name -> line -> how many times the line was executed
+    pub files: BTreeMap<String, BTreeMap<u32, u64>>,
+}
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    // adds the counts of another run, e.g. of a different test
+    pub fn merge(&mut self, other: &Coverage) {
+        for (file, lines) in &other.files {
+            let counts = self.files.entry(file.clone()).or_default();
+            for (line, count) in lines {
+                *counts.entry(*line).or_default() += count;
+            }
+        }
+    }
+    pub fn exclude_prelude(&mut self) {
+        self.files.remove("prelude.en");
+    }
+    // (lines executed, lines with code) of a file
+    pub fn file_summary(&self, file: &str) -> (usize, usize) {
+        let Some(lines) = self.files.get(file) else {
+            return (0, 0);
+        };
+        let hit = lines.values().filter(|count| **count > 0).count();
+        (hit, lines.len())
+    }
+    // `path` maps a file name to the path written after `SF:`
+    pub fn lcov(&self, path: impl Fn(&str) -> String) -> String {
+        let mut out = String::new();
+        for (file, lines) in &self.files {
+            let (hit, found) = self.file_summary(file);
+            writeln!(out, "TN:").unwrap();
+            writeln!(out, "SF:{}", path(file)).unwrap();
+            for (line, count) in lines {
+                writeln!(out, "DA:{line},{count}").unwrap();
+            }
+            writeln!(out, "LH:{hit}").unwrap();
+            writeln!(out, "LF:{found}").unwrap();
+            writeln!(out, "end_of_record").unwrap();
+        }
+        out
+    }
+    pub fn summary(&self) -> String {
+        let width = self
+            .files
+            .keys()
+            .map(|file| file.len())
+            .max()
+            .unwrap_or(0)
+            .max("total".len());
+        let percent = |hit: usize, found: usize| {
+            if found == 0 {
+                100.0
+            } else {
+                hit as f64 * 100.0 / found as f64
+            }
+        };
+        let mut out = String::new();
+        let (mut total_hit, mut total_found) = (0, 0);
+        for file in self.files.keys() {
+            let (hit, found) = self.file_summary(file);
+            total_hit += hit;
+            total_found += found;
+            writeln!(
+                out,
+                "{file:<width$}  {hit:>5}/{found:<5} {:>6.2}%",
+                percent(hit, found)
+            )
+            .unwrap();
+        }
+        writeln!(
+            out,
+            "{:<width$}  {total_hit:>5}/{total_found:<5} {:>6.2}%",
+            "total",
+            percent(total_hit, total_found)
+        )
+        .unwrap();
+        out
+    }
+}
+impl Vm {
+    // Records which instructions are executed from now on.
+    pub fn enable_coverage(&mut self) {
+        self.coverage = Some(vec![0; self.program.len()]);
+    }
+    pub fn coverage(&self) -> Option<Coverage> {
+        let executed = self.coverage.as_ref()?;
+        let mut coverage = Coverage::new();
+        for (pc, count) in executed.iter().enumerate() {
+            // error locations are looked up with the pc after the failing instruction
+            let location = self.pc_to_error_location(ProgramCounter::new(pc + 1));
+            // code emitted before any source line, like loading native libraries
+            if location.lineno == 0 {
+                continue;
+            }
+            let line = coverage
+                .files
+                .entry(location.filename)
+                .or_default()
+                .entry(location.lineno)
+                .or_default();
+            // a line runs as often as its most executed instruction
+            *line = (*line).max(*count);
+        }
+        Some(coverage)
+    }
+}
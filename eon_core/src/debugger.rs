@@ -0,0 +1,334 @@
+use crate::translate_bytecode::BytecodeIndex;
+use crate::vm::{ManagedObjectKind, PackedValue, ValueKind, ValueTrait, Vm, VmError, VmStatus};
+use std::path::Path;
+// Source-level information the translator emits next to the bytecode so a paused `Vm` can be
+// inspected by variable name.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DebugInfo {
+    pub(crate) types: Vec<DebugType>,
+    // (start of a generated function, scope id), sorted like `function_name_table`
+    pub(crate) scopes: Vec<(BytecodeIndex, u32)>,
+    pub(crate) locals: Vec<LocalVar>,
+}
+#[derive(Debug, Clone)]
+pub(crate) struct LocalVar {
+    pub(crate) scope: u32,
+    pub(crate) name: String,
+    pub(crate) offset: i32,
+    pub(crate) ty: u32,
+    // the variable holds a value once execution has reached this instruction
+    pub(crate) live_from: BytecodeIndex,
+}
+// Types refer to each other by index so recursive types stay finite.
+#[derive(Debug, Clone)]
+pub(crate) enum DebugType {
+    Unknown,
+    Void,
+    Int,
+    Float,
+    Bool,
+    String,
+    Function,
+    Tuple(Vec<u32>),
+    Array(u32),
+    Handle,
+    Struct {
+        name: String,
+        fields: Vec<(String, u32)>,
+    },
+    Enum {
+        name: String,
+        variants: Vec<(String, Option<u32>)>,
+    },
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+    pub function: String,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Local {
+    pub name: String,
+    pub value: String,
+}
+#[derive(Debug, Clone)]
+pub struct Breakpoint {
+    pub id: u32,
+    pub file: String,
+    pub line: u32,
+    pcs: Vec<BytecodeIndex>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepMode {
+    Continue,
+    // stop at the next line, entering calls
+    In,
+    // stop at the next line of the current function
+    Over,
+    // stop after the current function returns
+    Out,
+}
+#[derive(Debug)]
+pub enum DebugEvent {
+    Breakpoint(u32),
+    Step,
+    Done,
+    Error(Box<VmError>),
+    // the embedder must handle the host function and resume
+    PendingHostFunc(u16),
+}
+const MAX_DEPTH: usize = 8;
+const MAX_ELEMENTS: usize = 32;
+impl Vm {
+    pub fn source_location(&self) -> SourceLocation {
+        self.location_at(self.pc.0)
+    }
+    // innermost frame first
+    pub fn frames(&self) -> Vec<SourceLocation> {
+        let mut frames = vec![self.source_location()];
+        for frame in self.call_stack.iter().rev() {
+            // the return address follows the call
+            frames.push(self.location_at(frame.pc.0 - 1));
+        }
+        frames
+    }
+    pub fn call_depth(&self) -> usize {
+        self.call_stack.len()
+    }
+    // the variables in scope in `frame` (0 is the innermost) with their values pretty-printed
+    pub fn locals(&self, frame: usize) -> Vec<Local> {
+        let (pc, stack_base) = if frame == 0 {
+            (self.pc.0, self.stack_base)
+        } else {
+            let Some(call_frame) = self.call_stack.iter().rev().nth(frame - 1) else {
+                return vec![];
+            };
+            (call_frame.pc.0 - 1, call_frame.stack_base)
+        };
+        let Some(scope) = self.scope_at(pc) else {
+            return vec![];
+        };
+        let mut ret: Vec<Local> = vec![];
+        for local in &self.debug_info.locals {
+            if local.scope != scope || local.live_from > pc {
+                continue;
+            }
+            let idx = stack_base.wrapping_add_signed(local.offset as isize);
+            let Some(value) = self.value_stack.get(idx) else {
+                continue;
+            };
+            let value = self.format_value(*value, local.ty, 0);
+            // a name bound twice (shadowing, separate match arms) shows the latest binding
+            match ret.iter_mut().find(|existing| existing.name == local.name) {
+                Some(existing) => existing.value = value,
+                None => ret.push(Local {
+                    name: local.name.clone(),
+                    value,
+                }),
+            }
+        }
+        ret
+    }
+    fn location_at(&self, pc: BytecodeIndex) -> SourceLocation {
+        // error locations are looked up with the pc after the failing instruction
+        let location = self.pc_to_error_location(crate::vm::ProgramCounter(pc + 1));
+        SourceLocation {
+            file: location.filename,
+            line: location.lineno,
+            function: location.function_name,
+        }
+    }
+    fn scope_at(&self, pc: BytecodeIndex) -> Option<u32> {
+        let scopes = &self.debug_info.scopes;
+        let idx = match scopes.binary_search_by_key(&pc, |pair| pair.0) {
+            Ok(idx) => idx,
+            Err(0) => return None,
+            Err(idx) => idx - 1,
+        };
+        Some(scopes[idx].1)
+    }
+    fn is_statement_start(&self, pc: BytecodeIndex) -> bool {
+        pc == 0 || self.location_at(pc) != self.location_at(pc - 1)
+    }
+    fn format_value(&self, value: PackedValue, ty: u32, depth: usize) -> String {
+        let ty = &self.debug_info.types[ty as usize];
+        match ty {
+            DebugType::Unknown => return "?".into(),
+            DebugType::Void => return "()".into(),
+            DebugType::Int => return value.get_int(self).to_string(),
+            DebugType::Float => return format!("{:?}", value.get_float(self)),
+            DebugType::Bool => return value.get_bool(self).to_string(),
+            DebugType::Function => return "<function>".into(),
+            _ => {}
+        }
+        // uninitialized slots hold nil, never a heap reference
+        if !value.is_heap_ref() {
+            return "<uninitialized>".into();
+        }
+        if depth >= MAX_DEPTH {
+            return "...".into();
+        }
+        let index = value.get_heap_index(self, ValueKind::HeapObject);
+        let Some(object) = self.heap.get(index) else {
+            return "<invalid>".into();
+        };
+        let elements = |values: &[PackedValue], tys: &dyn Fn(usize) -> Option<u32>| {
+            let mut parts: Vec<String> = values
+                .iter()
+                .take(MAX_ELEMENTS)
+                .enumerate()
+                .map(|(i, value)| match tys(i) {
+                    Some(ty) => self.format_value(*value, ty, depth + 1),
+                    None => "?".into(),
+                })
+                .collect();
+            if values.len() > MAX_ELEMENTS {
+                parts.push("...".into());
+            }
+            parts
+        };
+        match (ty, &object.kind) {
+            (DebugType::String, ManagedObjectKind::String(s)) => format!("{s:?}"),
+            (DebugType::Tuple(tys), ManagedObjectKind::Struct(values)) => {
+                let parts = elements(values, &|i| tys.get(i).copied());
+                format!("({})", parts.join(", "))
+            }
+            (DebugType::Array(ty), ManagedObjectKind::DynArray(values)) => {
+                let parts = elements(values, &|_| Some(*ty));
+                format!("[{}]", parts.join(", "))
+            }
+            (DebugType::Handle, ManagedObjectKind::Handle(_)) => "<handle>".into(),
+            (DebugType::Struct { name, fields }, ManagedObjectKind::Struct(values)) => {
+                let parts = elements(values, &|i| fields.get(i).map(|field| field.1));
+                let parts: Vec<_> = parts
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, part)| match fields.get(i) {
+                        Some((field, _)) => format!("{field}: {part}"),
+                        None => part,
+                    })
+                    .collect();
+                format!("{name} {{ {} }}", parts.join(", "))
+            }
+            (DebugType::Enum { name, variants }, ManagedObjectKind::Enum { tag, value }) => {
+                let Some((ctor, data)) = variants.get(*tag as usize) else {
+                    return "<invalid>".into();
+                };
+                match data {
+                    None => format!("{name}.{ctor}"),
+                    Some(ty) => {
+                        let data = self.format_value(*value, *ty, depth + 1);
+                        if data.starts_with('(') {
+                            format!("{name}.{ctor}{data}")
+                        } else {
+                            format!("{name}.{ctor}({data})")
+                        }
+                    }
+                }
+            }
+            _ => "<invalid>".into(),
+        }
+    }
+}
+#[derive(Debug, Default)]
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    next_id: u32,
+    // where the step interrupted by a host function started
+    interrupted: Option<(SourceLocation, usize)>,
+}
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+    // Breaks at the first line at or after `line` that has code. `file` is matched by file name.
+    pub fn add_breakpoint(&mut self, vm: &Vm, file: &str, line: u32) -> Option<&Breakpoint> {
+        let file_name = Path::new(file)
+            .file_name()
+            .map_or(file.to_string(), |name| name.to_string_lossy().into_owned());
+        let mut candidates: Vec<(u32, BytecodeIndex)> = vec![];
+        for pc in 0..vm.program.len() as BytecodeIndex {
+            if !vm.is_statement_start(pc) {
+                continue;
+            }
+            let location = vm.location_at(pc);
+            if location.file == file_name && location.line >= line {
+                candidates.push((location.line, pc));
+            }
+        }
+        let resolved = candidates.iter().map(|(line, _)| *line).min()?;
+        let pcs = candidates
+            .into_iter()
+            .filter(|(line, _)| *line == resolved)
+            .map(|(_, pc)| pc)
+            .collect();
+        self.next_id += 1;
+        self.breakpoints.push(Breakpoint {
+            id: self.next_id,
+            file: file_name,
+            line: resolved,
+            pcs,
+        });
+        self.breakpoints.last()
+    }
+    pub fn remove_breakpoint(&mut self, id: u32) -> bool {
+        let len = self.breakpoints.len();
+        self.breakpoints.retain(|breakpoint| breakpoint.id != id);
+        self.breakpoints.len() != len
+    }
+    // Runs `vm` until `mode` is satisfied, a breakpoint is hit or the program stops.
+    pub fn resume(&mut self, vm: &mut Vm, mode: StepMode) -> DebugEvent {
+        let (start_location, start_depth) = self
+            .interrupted
+            .take()
+            .unwrap_or_else(|| (vm.source_location(), vm.call_depth()));
+        loop {
+            vm.run_n_steps(1);
+            match vm.status() {
+                VmStatus::Done => return DebugEvent::Done,
+                VmStatus::Error(err) => return DebugEvent::Error(err),
+                VmStatus::PendingHostFunc(idx) => {
+                    self.interrupted = Some((start_location, start_depth));
+                    return DebugEvent::PendingHostFunc(idx);
+                }
+                VmStatus::OutOfSteps | VmStatus::Timeout | VmStatus::OutOfMemory => {
+                    // a limit set by the embedder was reached
+                    if let Some(err) = vm.limit_error() {
+                        return DebugEvent::Error(err);
+                    }
+                }
+            }
+            let pc = vm.pc.0;
+            if let Some(breakpoint) = self
+                .breakpoints
+                .iter()
+                .find(|breakpoint| breakpoint.pcs.contains(&pc))
+            {
+                return DebugEvent::Breakpoint(breakpoint.id);
+            }
+            let depth = vm.call_depth();
+            let stop = match mode {
+                StepMode::Continue => false,
+                StepMode::In => {
+                    depth < start_depth
+                        || vm.is_statement_start(pc)
+                            && (depth != start_depth || vm.source_location() != start_location)
+                }
+                StepMode::Over => {
+                    depth < start_depth
+                        || depth == start_depth
+                            && vm.is_statement_start(pc)
+                            && vm.source_location() != start_location
+                }
+                StepMode::Out => depth < start_depth,
+            };
+            if stop {
+                return DebugEvent::Step;
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::host::{VmArgs, VmType};
+use crate::translate_bytecode::{ExportedFunction, HostFunctionSignature};
+use crate::vm::{CallFrame, PackedValue, ProgramCounter, Vm, VmError, VmStatus};
+use std::fmt::{Display, Formatter};
+#[derive(Debug, Clone)]
+pub enum CallError {
+    UnknownFunction(String),
+    WrongSignature { expected: String, found: String },
+    Error(Box<VmError>),
+    // a host function or a limit stopped the call; handle it and call `finish_call`
+    Interrupted,
+}
+impl Display for CallError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallError::UnknownFunction(name) => {
+                write!(f, "no exported function named `{name}`")
+            }
+            CallError::WrongSignature { expected, found } => {
+                write!(f, "function has type `{expected}`, but was called as `{found}`")
+            }
+            CallError::Error(err) => write!(f, "{err}"),
+            CallError::Interrupted => write!(f, "the call was interrupted"),
+        }
+    }
+}
+impl std::error::Error for CallError {}
+fn signature(args: &[String], ret: &str) -> String {
+    format!("fn({}) -> {ret}", args.join(", "))
+}
+impl Vm {
+    pub fn functions(&self) -> &[ExportedFunction] {
+        &self.functions
+    }
+    // indexed by the number of `VmStatus::PendingHostFunc`
+    pub fn host_functions(&self) -> &[HostFunctionSignature] {
+        &self.host_functions
+    }
+    // Calls an exported function of the main file and runs until it returns. Whatever the vm was
+    // running before is abandoned, so a vm can be reused after an error. The shared libraries are
+    // loaded by the first call if <main> hasn't run.
+    pub fn call<A: VmArgs<PackedValue>, R: VmType<PackedValue>>(
+        &mut self,
+        name: &str,
+        args: A,
+    ) -> Result<R, CallError> {
+        self.start_call::<A, R>(name, args)?;
+        self.finish_call()
+    }
+    // Sets up a call without running it. `R` is only used to check the signature.
+    pub fn start_call<A: VmArgs<PackedValue>, R: VmType<PackedValue>>(
+        &mut self,
+        name: &str,
+        args: A,
+    ) -> Result<(), CallError> {
+        let Some(function) = self.functions.iter().find(|function| function.name == name) else {
+            return Err(CallError::UnknownFunction(name.to_string()));
+        };
+        let arg_types = A::eon_types();
+        let ret_type = R::eon_type();
+        if arg_types != function.args || ret_type != function.ret {
+            return Err(CallError::WrongSignature {
+                expected: signature(&function.args, &function.ret),
+                found: signature(&arg_types, &ret_type),
+            });
+        }
+        let entry = function.entry;
+        // <main> loads the shared libraries before anything else, but it may not have run
+        self.reset();
+        self.load_libs().map_err(CallError::Error)?;
+        self.reset();
+        args.push_args(self);
+        self.call_stack.push(CallFrame {
+            pc: ProgramCounter(self.call_return),
+            stack_base: self.stack_base,
+            nargs: arg_types.len() as u8,
+            tail_calls: 0,
+            last_tail_call: ProgramCounter(0),
+        });
+        self.pc = ProgramCounter(entry);
+        self.stack_base = self.value_stack.len();
+        if let Some(profiler) = &mut self.profiler {
+            profiler.enter(entry);
+        }
+        Ok(())
+    }
+    fn reset(&mut self) {
+        self.call_stack.clear();
+        self.value_stack.truncate(1);
+        self.stack_base = 1;
+        self.pending_host_func = None;
+        self.error = None;
+        self.limit_hit = None;
+        self.done = false;
+    }
+    // Runs a call set up by `start_call`, or resumes it after `CallError::Interrupted`.
+    pub fn finish_call<R: VmType<PackedValue>>(&mut self) -> Result<R, CallError> {
+        self.run();
+        match self.status() {
+            VmStatus::Done => {
+                let result = R::from_vm(self);
+                self.push_nil();
+                Ok(result)
+            }
+            VmStatus::Error(err) => Err(CallError::Error(err)),
+            _ => Err(CallError::Interrupted),
+        }
+    }
+}
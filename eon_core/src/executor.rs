@@ -0,0 +1,225 @@
+use crate::host::VmType;
+use crate::vm::{PackedValue, Vm, VmErrorKind, VmStatus};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, channel};
+use std::time::Instant;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaskId(pub usize);
+// What the handler did with a host function call.
+pub enum HostReply {
+    // the result was pushed and `clear_pending_host_func` called
+    Ready,
+    // the task sleeps until the instant, then the host function returns void
+    Sleep(Instant),
+    // the result comes later through the `Completer`, possibly from another thread
+    Wait,
+    // the task fails with this message
+    Error(String),
+}
+// (task, host function call, how to finish it or `None` if the completer was dropped)
+type Completion = (TaskId, u64, Option<Box<dyn FnOnce(&mut Vm) + Send>>);
+// Finishes a host function call that was answered with `HostReply::Wait`.
+pub struct Completer {
+    task: TaskId,
+    call: u64,
+    sender: Option<Sender<Completion>>,
+}
+impl Completer {
+    pub fn task(&self) -> TaskId {
+        self.task
+    }
+    pub fn complete<R: VmType<PackedValue> + Send + 'static>(mut self, result: R) {
+        if let Some(sender) = self.sender.take() {
+            // the executor may be gone, then nobody is waiting for the result
+            let _ = sender.send((
+                self.task,
+                self.call,
+                Some(Box::new(move |vm: &mut Vm| {
+                    result.to_vm(vm);
+                    vm.clear_pending_host_func();
+                })),
+            ));
+        }
+    }
+}
+impl Drop for Completer {
+    fn drop(&mut self) {
+        // a dropped completer would leave the task waiting forever
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.send((self.task, self.call, None));
+        }
+    }
+}
+type Handler = Box<dyn FnMut(&mut Vm, u16, Completer) -> HostReply>;
+enum TaskState {
+    Ready,
+    Sleeping,
+    // for the completer of this host function call
+    Waiting(u64),
+}
+// Runs many vms on one thread. Each ready task runs for at most `step_budget` instructions
+// before the next one gets a turn, and tasks waiting for a host function don't block the others.
+pub struct Executor {
+    handler: Handler,
+    step_budget: u32,
+    tasks: Vec<Option<(Vm, TaskState)>>,
+    ready: VecDeque<TaskId>,
+    timers: BinaryHeap<Reverse<(Instant, TaskId)>>,
+    sender: Sender<Completion>,
+    receiver: Receiver<Completion>,
+    calls: u64,
+    finished: Vec<(TaskId, Vm)>,
+}
+pub const DEFAULT_STEP_BUDGET: u32 = 10_000;
+impl Executor {
+    pub fn new(handler: impl FnMut(&mut Vm, u16, Completer) -> HostReply + 'static) -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            handler: Box::new(handler),
+            step_budget: DEFAULT_STEP_BUDGET,
+            tasks: vec![],
+            ready: VecDeque::new(),
+            timers: BinaryHeap::new(),
+            sender,
+            receiver,
+            calls: 0,
+            finished: vec![],
+        }
+    }
+    pub fn set_step_budget(&mut self, steps: u32) {
+        self.step_budget = steps.max(1);
+    }
+    pub fn spawn(&mut self, vm: Vm) -> TaskId {
+        let id = TaskId(self.tasks.len());
+        self.tasks.push(Some((vm, TaskState::Ready)));
+        self.ready.push_back(id);
+        id
+    }
+    // tasks that haven't finished yet
+    pub fn pending(&self) -> usize {
+        self.tasks.iter().filter(|task| task.is_some()).count()
+    }
+    // Runs until every task is done, failed or stopped by a limit, and returns the vms in the
+    // order they finished.
+    pub fn run(&mut self) -> Vec<(TaskId, Vm)> {
+        while self.pending() > 0 {
+            if !self.poll() && self.pending() > 0 {
+                self.wait();
+            }
+        }
+        self.take_finished()
+    }
+    // Gives every ready task one turn. Returns false if no task was ready.
+    pub fn poll(&mut self) -> bool {
+        self.wake(Instant::now());
+        while let Ok(completion) = self.receiver.try_recv() {
+            self.complete(completion);
+        }
+        if self.ready.is_empty() {
+            return false;
+        }
+        for _ in 0..self.ready.len() {
+            let Some(id) = self.ready.pop_front() else {
+                break;
+            };
+            self.turn(id);
+        }
+        true
+    }
+    pub fn take_finished(&mut self) -> Vec<(TaskId, Vm)> {
+        std::mem::take(&mut self.finished)
+    }
+    fn turn(&mut self, id: TaskId) {
+        let Some((vm, _)) = &mut self.tasks[id.0] else {
+            return;
+        };
+        vm.run_n_steps(self.step_budget);
+        match vm.status() {
+            VmStatus::PendingHostFunc(idx) => {
+                self.calls += 1;
+                let call = self.calls;
+                let completer = Completer {
+                    task: id,
+                    call,
+                    sender: Some(self.sender.clone()),
+                };
+                match (self.handler)(vm, idx, completer) {
+                    HostReply::Ready => self.ready.push_back(id),
+                    HostReply::Sleep(until) => {
+                        self.set_state(id, TaskState::Sleeping);
+                        self.timers.push(Reverse((until, id)));
+                    }
+                    HostReply::Wait => self.set_state(id, TaskState::Waiting(call)),
+                    HostReply::Error(msg) => self.fail(id, msg),
+                }
+            }
+            // the budget ran out, so the task goes to the back of the queue
+            VmStatus::OutOfSteps if vm.limit_error().is_none() => self.ready.push_back(id),
+            _ => self.finish(id),
+        }
+    }
+    fn set_state(&mut self, id: TaskId, state: TaskState) {
+        if let Some((_, task_state)) = &mut self.tasks[id.0] {
+            *task_state = state;
+        }
+    }
+    fn finish(&mut self, id: TaskId) {
+        if let Some((vm, _)) = self.tasks[id.0].take() {
+            self.finished.push((id, vm));
+        }
+    }
+    fn fail(&mut self, id: TaskId, msg: String) {
+        if let Some((vm, _)) = &mut self.tasks[id.0] {
+            vm.clear_pending_host_func();
+            vm.error = Some(Box::new(vm.make_error(VmErrorKind::HostFunction(msg))));
+        }
+        self.finish(id);
+    }
+    fn complete(&mut self, (id, call, apply): Completion) {
+        // completers of calls that were answered right away are dropped and ignored
+        let Some((vm, state)) = &mut self.tasks[id.0] else {
+            return;
+        };
+        if !matches!(state, TaskState::Waiting(waiting) if *waiting == call) {
+            return;
+        }
+        match apply {
+            Some(apply) => {
+                apply(vm);
+                *state = TaskState::Ready;
+                self.ready.push_back(id);
+            }
+            None => self.fail(id, "the host function was never completed".to_string()),
+        }
+    }
+    fn wake(&mut self, now: Instant) {
+        while let Some(Reverse((until, id))) = self.timers.peek().copied()
+            && until <= now
+        {
+            self.timers.pop();
+            if let Some((vm, state)) = &mut self.tasks[id.0] {
+                vm.push_nil();
+                vm.clear_pending_host_func();
+                *state = TaskState::Ready;
+                self.ready.push_back(id);
+            }
+        }
+    }
+    // blocks until a timer fires or a host function completes
+    fn wait(&mut self) {
+        let completion = match self.timers.peek() {
+            Some(Reverse((until, _))) => {
+                let timeout = until.saturating_duration_since(Instant::now());
+                match self.receiver.recv_timeout(timeout) {
+                    Ok(completion) => completion,
+                    Err(RecvTimeoutError::Timeout) => return,
+                    Err(RecvTimeoutError::Disconnected) => unreachable!(),
+                }
+            }
+            // the executor holds a sender, so this only returns with a completion
+            None => self.receiver.recv().unwrap(),
+        };
+        self.complete(completion);
+    }
+}
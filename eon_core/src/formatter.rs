@@ -0,0 +1,904 @@
+use crate::ast::{
+    BinaryOperator, Expr, ExprKind, FileAst, FileDatabase, FuncDecl, FuncDef, ImportList,
+    InterfaceMethodDecl, InterfaceOutputType, Item, ItemKind, MatchArm, Pat, PatKind, Polytype,
+    Stmt, StmtKind, StructField, Type, TypeDefKind, TypeKind, Variant,
+};
+use crate::parse::{parse_comments, parse_or_err};
+use crate::{ErrorSummary, FileData};
+use std::mem;
+use std::ops::Range;
+use std::rc::Rc;
+const INDENT: usize = 2;
+const MAX_WIDTH: usize = 100;
+pub fn format(file_data: &FileData) -> Result<String, ErrorSummary> {
+    let mut file_db = FileDatabase::new();
+    let file_id = file_db.add(file_data.clone());
+    let file_ast = match parse_or_err(file_id, file_data) {
+        Ok(file_ast) => file_ast,
+        Err(err) => {
+            return Err(ErrorSummary {
+                msg: "".into(),
+                more: Some((file_db, vec![*err])),
+            });
+        }
+    };
+    let mut formatter = Formatter {
+        source: &file_data.source,
+        comments: parse_comments(&file_data.source),
+        consumed: vec![],
+        in_head: false,
+    };
+    formatter.consumed = vec![false; formatter.comments.len()];
+    let doc = formatter.file(&file_ast);
+    let mut out = render(&doc);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    Ok(out)
+}
+enum Doc {
+    Nil,
+    Text(String),
+    // a space, or a newline when the enclosing group is broken
+    Line,
+    // nothing, or a newline when the enclosing group is broken
+    SoftLine,
+    HardLine,
+    IfBreak(&'static str),
+    Concat(Vec<Doc>),
+    Nest(Box<Doc>),
+    Group(Box<Doc>),
+    ForceBreak(Box<Doc>),
+}
+fn text(s: impl Into<String>) -> Doc {
+    Doc::Text(s.into())
+}
+fn concat(docs: Vec<Doc>) -> Doc {
+    Doc::Concat(docs)
+}
+fn nest(doc: Doc) -> Doc {
+    Doc::Nest(doc.into())
+}
+fn group(doc: Doc) -> Doc {
+    Doc::Group(doc.into())
+}
+fn join(docs: Vec<Doc>, sep: impl Fn() -> Doc) -> Vec<Doc> {
+    let mut ret = vec![];
+    for (i, doc) in docs.into_iter().enumerate() {
+        if i > 0 {
+            ret.push(sep());
+        }
+        ret.push(doc);
+    }
+    ret
+}
+// `(a, b)` that breaks into one element per line when it does not fit
+fn list(open: &str, docs: Vec<Doc>, close: &str, trailing_comma: bool) -> Doc {
+    if docs.is_empty() {
+        return text(format!("{open}{close}"));
+    }
+    group(concat(vec![
+        text(open),
+        nest(concat(vec![
+            Doc::SoftLine,
+            concat(join(docs, || concat(vec![text(","), Doc::Line]))),
+            if trailing_comma {
+                Doc::IfBreak(",")
+            } else {
+                Doc::Nil
+            },
+        ])),
+        Doc::SoftLine,
+        text(close),
+    ]))
+}
+// `{` + one entry per line + `}`
+fn braced(head: Doc, body: Doc) -> Doc {
+    if let Doc::Nil = body {
+        return concat(vec![head, text("{}")]);
+    }
+    concat(vec![
+        head,
+        text("{"),
+        Doc::ForceBreak(
+            concat(vec![nest(concat(vec![Doc::HardLine, body])), Doc::HardLine]).into(),
+        ),
+        text("}"),
+    ])
+}
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+type Cmd<'a> = (usize, Mode, &'a Doc);
+fn render(doc: &Doc) -> String {
+    let mut out = String::new();
+    let mut col = 0;
+    let mut stack: Vec<Cmd> = vec![(0, Mode::Break, doc)];
+    let newline = |out: &mut String, col: &mut usize, indent: usize| {
+        let trimmed = out.trim_end_matches(' ').len();
+        out.truncate(trimmed);
+        out.push('\n');
+        out.push_str(&" ".repeat(indent));
+        *col = indent;
+    };
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Nil => {}
+            Doc::Text(s) => {
+                out.push_str(s);
+                col = match s.rfind('\n') {
+                    Some(idx) => s[idx + 1..].chars().count(),
+                    None => col + s.chars().count(),
+                };
+            }
+            Doc::Line if mode == Mode::Flat => {
+                out.push(' ');
+                col += 1;
+            }
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine => newline(&mut out, &mut col, indent),
+            Doc::IfBreak(s) => {
+                if mode == Mode::Break {
+                    out.push_str(s);
+                    col += s.len();
+                }
+            }
+            Doc::Concat(docs) => {
+                for doc in docs.iter().rev() {
+                    stack.push((indent, mode, doc));
+                }
+            }
+            Doc::Nest(doc) => {
+                let indent = if mode == Mode::Break {
+                    indent + INDENT
+                } else {
+                    indent
+                };
+                stack.push((indent, mode, doc));
+            }
+            Doc::Group(doc) => {
+                let mode = if mode == Mode::Flat
+                    || fits(MAX_WIDTH as isize - col as isize, doc, &stack)
+                {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+                stack.push((indent, mode, doc));
+            }
+            Doc::ForceBreak(doc) => stack.push((indent, Mode::Break, doc)),
+        }
+    }
+    let trimmed = out.trim_end().len();
+    out.truncate(trimmed);
+    out
+}
+// whether `doc` printed flat, followed by the rest of the current line, stays within `width`
+fn fits(mut width: isize, doc: &Doc, rest: &[Cmd]) -> bool {
+    let mut stack = vec![(Mode::Flat, doc)];
+    let mut rest_idx = rest.len();
+    loop {
+        let (mode, doc) = match stack.pop() {
+            Some(next) => next,
+            None => {
+                if rest_idx == 0 {
+                    return true;
+                }
+                rest_idx -= 1;
+                (rest[rest_idx].1, rest[rest_idx].2)
+            }
+        };
+        match doc {
+            Doc::Nil => {}
+            Doc::Text(s) => {
+                let first_line = s.split('\n').next().unwrap();
+                width -= first_line.chars().count() as isize;
+                if width < 0 {
+                    return false;
+                }
+                if s.contains('\n') {
+                    return true;
+                }
+            }
+            Doc::Line | Doc::SoftLine if mode == Mode::Break => return true,
+            Doc::Line => width -= 1,
+            Doc::SoftLine => {}
+            Doc::HardLine => return true,
+            Doc::IfBreak(s) => {
+                if mode == Mode::Break {
+                    width -= s.len() as isize;
+                }
+            }
+            Doc::Concat(docs) => {
+                for doc in docs.iter().rev() {
+                    stack.push((mode, doc));
+                }
+            }
+            Doc::Nest(doc) | Doc::Group(doc) => stack.push((mode, doc)),
+            Doc::ForceBreak(doc) => stack.push((Mode::Break, doc)),
+        }
+        if width < 0 {
+            return false;
+        }
+    }
+}
+struct Formatter<'a> {
+    source: &'a str,
+    comments: Vec<Range<usize>>,
+    consumed: Vec<bool>,
+    // set while formatting the operands of a condition, where a struct literal needs parentheses
+    in_head: bool,
+}
+enum InterfaceMember {
+    OutputType(Rc<InterfaceOutputType>),
+    Method(Rc<InterfaceMethodDecl>),
+}
+impl Formatter<'_> {
+    fn file(&mut self, file_ast: &FileAst) -> Doc {
+        self.sequence(
+            0,
+            self.source.len(),
+            &file_ast.items,
+            |item| item.loc.range(),
+            "",
+            Self::item,
+        )
+    }
+    // Prints `nodes` one per line, keeping the comments between them and at most one blank line
+    // wherever the source had any.
+    fn sequence<T>(
+        &mut self,
+        lo: usize,
+        hi: usize,
+        nodes: &[T],
+        range: fn(&T) -> Range<usize>,
+        sep: &str,
+        print: fn(&mut Self, &T) -> Doc,
+    ) -> Doc {
+        let mut docs = vec![];
+        let mut last_end = None;
+        for (i, node) in nodes.iter().enumerate() {
+            let range = range(node);
+            let node_hi = self.trimmed_end(range.clone());
+            for comment in self.take_comments(lo, range.start) {
+                self.separate(&mut docs, last_end, comment.start);
+                docs.push(text(&self.source[comment.clone()]));
+                last_end = Some(comment.end);
+            }
+            self.separate(&mut docs, last_end, range.start);
+            docs.push(print(self, node));
+            if i + 1 < nodes.len() {
+                docs.push(text(sep));
+            }
+            last_end = Some(node_hi);
+            let mut trailing = self.take_comments(range.start, node_hi);
+            if let Some(idx) = self.next_comment(node_hi)
+                && self.source[node_hi..self.comments[idx].start]
+                    .chars()
+                    .all(|c| c == ' ' || c == '\t' || c == ',')
+            {
+                self.consumed[idx] = true;
+                trailing.push(self.comments[idx].clone());
+            }
+            for comment in trailing {
+                docs.push(text(" "));
+                docs.push(text(&self.source[comment.clone()]));
+                last_end = Some(comment.end);
+            }
+        }
+        for comment in self.take_comments(lo, hi) {
+            self.separate(&mut docs, last_end, comment.start);
+            docs.push(text(&self.source[comment.clone()]));
+            last_end = Some(comment.end);
+        }
+        if docs.is_empty() {
+            Doc::Nil
+        } else {
+            concat(docs)
+        }
+    }
+    fn separate(&self, docs: &mut Vec<Doc>, last_end: Option<usize>, next_start: usize) {
+        let Some(last_end) = last_end else {
+            return;
+        };
+        docs.push(Doc::HardLine);
+        if last_end < next_start && self.source[last_end..next_start].matches('\n').count() > 1 {
+            docs.push(Doc::HardLine);
+        }
+    }
+    fn take_comments(&mut self, lo: usize, hi: usize) -> Vec<Range<usize>> {
+        let mut ret = vec![];
+        for (idx, comment) in self.comments.iter().enumerate() {
+            if !self.consumed[idx] && comment.start >= lo && comment.start < hi {
+                self.consumed[idx] = true;
+                ret.push(comment.clone());
+            }
+        }
+        ret
+    }
+    fn next_comment(&self, pos: usize) -> Option<usize> {
+        (0..self.comments.len()).find(|&idx| !self.consumed[idx] && self.comments[idx].start >= pos)
+    }
+    // pest spans can run over trailing whitespace and comments
+    fn trimmed_end(&self, range: Range<usize>) -> usize {
+        let mut hi = range.end;
+        loop {
+            hi = range.start + self.source[range.start..hi].trim_end().len();
+            match self
+                .comments
+                .iter()
+                .find(|comment| comment.end == hi && comment.start >= range.start)
+            {
+                Some(comment) => hi = comment.start,
+                None => return hi,
+            }
+        }
+    }
+    fn item(&mut self, item: &Rc<Item>) -> Doc {
+        let doc = match &*item.kind {
+            ItemKind::FuncDef(f) if f.test => concat(vec![text("test "), self.func_def(f)]),
+            ItemKind::FuncDef(f) => self.func_def(f),
+            ItemKind::ForeignFuncDecl(f) => text(format!("foreign fn {}", func_decl(f))),
+            ItemKind::HostFuncDecl(f) => text(format!("host fn {}", func_decl(f))),
+            ItemKind::TypeDef(tydef) => match &**tydef {
+                TypeDefKind::Struct(struct_def) => {
+                    let head = text(format!(
+                        "type {}{} = ",
+                        struct_def.name.v,
+                        ty_params(&struct_def.ty_args)
+                    ));
+                    let body = self.sequence(
+                        item.loc.lo as usize,
+                        item.loc.hi as usize,
+                        &struct_def.fields,
+                        |field| field.loc.range(),
+                        "",
+                        Self::struct_field,
+                    );
+                    braced(head, body)
+                }
+                TypeDefKind::Enum(enum_def) => {
+                    let body = self.sequence(
+                        item.loc.lo as usize,
+                        item.loc.hi as usize,
+                        &enum_def.variants,
+                        |variant| variant.loc.range(),
+                        "",
+                        Self::variant,
+                    );
+                    concat(vec![
+                        text(format!(
+                            "type {}{} =",
+                            enum_def.name.v,
+                            ty_params(&enum_def.ty_args)
+                        )),
+                        Doc::ForceBreak(nest(concat(vec![Doc::HardLine, body])).into()),
+                    ])
+                }
+            },
+            ItemKind::InterfaceDef(iface_def) => {
+                let mut members: Vec<_> = iface_def
+                    .output_types
+                    .iter()
+                    .map(|ty| InterfaceMember::OutputType(ty.clone()))
+                    .chain(
+                        iface_def
+                            .methods
+                            .iter()
+                            .map(|method| InterfaceMember::Method(method.clone())),
+                    )
+                    .collect();
+                members.sort_by_key(|member| self.interface_member_range(member).start);
+                let ranges: Vec<_> = members
+                    .iter()
+                    .map(|member| (self.interface_member_range(member), member))
+                    .collect();
+                let body = self.sequence(
+                    item.loc.lo as usize,
+                    item.loc.hi as usize,
+                    &ranges,
+                    |(range, _)| range.clone(),
+                    "",
+                    |this, (_, member)| this.interface_member(member),
+                );
+                braced(text(format!("interface {} ", iface_def.name.v)), body)
+            }
+            ItemKind::InterfaceImpl(imp) => {
+                let body = self.methods(item, &imp.methods);
+                braced(
+                    text(format!("implement {} for {} ", imp.iface.v, ty(&imp.typ))),
+                    body,
+                )
+            }
+            ItemKind::Extension(ext) => {
+                let body = self.methods(item, &ext.methods);
+                braced(text(format!("extend {} ", ty(&ext.typ))), body)
+            }
+            ItemKind::Import(path, list) => {
+                let names = |list: &Vec<_>| {
+                    let names: Vec<_> = list
+                        .iter()
+                        .map(|ident: &crate::ast::Identifier| ident.v.clone())
+                        .collect();
+                    match names.len() {
+                        1 => names[0].clone(),
+                        _ => format!("({})", names.join(", ")),
+                    }
+                };
+                text(match list {
+                    None => format!("use {}", path.v),
+                    Some(ImportList::Inclusion(list)) => format!("use {}.{}", path.v, names(list)),
+                    Some(ImportList::Exclusion(list)) => {
+                        format!("use {} except {}", path.v, names(list))
+                    }
+                })
+            }
+            ItemKind::Stmt(stmt) => self.stmt(stmt),
+        };
+        if item.public {
+            concat(vec![text("pub "), doc])
+        } else {
+            doc
+        }
+    }
+    fn methods(&mut self, item: &Item, methods: &[Rc<FuncDef>]) -> Doc {
+        self.sequence(
+            item.loc.lo as usize,
+            item.loc.hi as usize,
+            methods,
+            |f| f.name.loc.lo as usize..f.body.loc.hi as usize,
+            "",
+            |this, f| this.func_def(f),
+        )
+    }
+    fn interface_member_range(&self, member: &InterfaceMember) -> Range<usize> {
+        match member {
+            InterfaceMember::OutputType(output_type) => {
+                let lo = output_type.name.loc.lo as usize;
+                let hi = self.source[lo..]
+                    .find('\n')
+                    .map_or(self.source.len(), |idx| lo + idx);
+                lo..hi
+            }
+            InterfaceMember::Method(method) => method.loc.range(),
+        }
+    }
+    fn interface_member(&mut self, member: &InterfaceMember) -> Doc {
+        match member {
+            InterfaceMember::OutputType(output_type) => {
+                let mut s = format!("outputtype {}", output_type.name.v);
+                if !output_type.interfaces.is_empty() {
+                    s.push_str(" impl");
+                    for iface in &output_type.interfaces {
+                        s.push(' ');
+                        s.push_str(&interface(iface));
+                    }
+                }
+                text(s)
+            }
+            InterfaceMember::Method(method) => {
+                text(format!("fn {}: {}", method.name.v, ty(&method.ty)))
+            }
+        }
+    }
+    fn struct_field(&mut self, field: &Rc<StructField>) -> Doc {
+        text(format!("{}: {}", field.name.v, ty(&field.ty)))
+    }
+    fn variant(&mut self, variant: &Rc<Variant>) -> Doc {
+        text(match &variant.data {
+            None => format!("| {}", variant.ctor.v),
+            Some(data) => match &*data.kind {
+                TypeKind::Tuple(_) => format!("| {}{}", variant.ctor.v, ty(data)),
+                _ => format!("| {}({})", variant.ctor.v, ty(data)),
+            },
+        })
+    }
+    fn func_def(&mut self, f: &FuncDef) -> Doc {
+        let args = f
+            .args
+            .iter()
+            .map(|(name, annot)| text(arg(name, annot)))
+            .collect();
+        let mut docs = vec![
+            text(format!("fn {}", f.name.v)),
+            list("(", args, ")", true),
+        ];
+        if let Some(ret_type) = &f.ret_type {
+            docs.push(text(format!(" -> {}", ty(ret_type))));
+        }
+        match &*f.body.kind {
+            ExprKind::Block(_) => {
+                docs.push(text(" "));
+                docs.push(self.expr(&f.body));
+            }
+            _ => {
+                docs.push(text(" = "));
+                docs.push(self.expr(&f.body));
+            }
+        }
+        concat(docs)
+    }
+    fn stmt(&mut self, stmt: &Rc<Stmt>) -> Doc {
+        match &*stmt.kind {
+            StmtKind::Let(mutable, (pat, annot), expr) => {
+                let keyword = if *mutable { "var" } else { "let" };
+                let annot = match annot {
+                    Some(annot) => format!(": {}", ty(annot)),
+                    None => "".into(),
+                };
+                concat(vec![
+                    text(format!("{keyword} {}{annot} = ", self.pat(pat))),
+                    self.expr(expr),
+                ])
+            }
+            StmtKind::Set(lhs, rhs) => concat(vec![self.expr(lhs), text(" = "), self.expr(rhs)]),
+            StmtKind::Expr(expr) => self.expr(expr),
+            StmtKind::Continue => text("continue"),
+            StmtKind::Break => text("break"),
+            StmtKind::Return(expr) => concat(vec![text("return "), self.expr(expr)]),
+            StmtKind::If(cond, body) => concat(vec![
+                text("if "),
+                self.head_expr(cond),
+                text(" "),
+                self.expr(body),
+            ]),
+            StmtKind::WhileLoop(cond, body) => concat(vec![
+                text("while "),
+                self.head_expr(cond),
+                text(" "),
+                self.expr(body),
+            ]),
+            StmtKind::ForLoop(pat, iterable, body) => concat(vec![
+                text(format!("for {} in ", self.pat(pat))),
+                self.head_expr(iterable),
+                text(" "),
+                self.expr(body),
+            ]),
+        }
+    }
+    // an expression followed by `{`, which must not be read as the start of a struct literal
+    fn head_expr(&mut self, expr: &Rc<Expr>) -> Doc {
+        self.in_head = true;
+        let doc = self.expr(expr);
+        if ends_with_type_name(expr) {
+            concat(vec![text("("), doc, text(")")])
+        } else {
+            doc
+        }
+    }
+    fn expr(&mut self, expr: &Rc<Expr>) -> Doc {
+        // only the operands of a condition are in it, not nested expressions like arguments
+        let in_head = mem::take(&mut self.in_head);
+        match &*expr.kind {
+            ExprKind::Variable(name) => text(name),
+            ExprKind::Void => text("()"),
+            ExprKind::Int(i) => text(i.to_string()),
+            ExprKind::Float(f) => text(f),
+            ExprKind::Bool(b) => text(b.to_string()),
+            ExprKind::Str(s) => text(format!("\"{s}\"")),
+            ExprKind::Array(elems) => {
+                let elems = elems.iter().map(|elem| self.expr(elem)).collect();
+                list("[", elems, "]", true)
+            }
+            ExprKind::Tuple(elems) => {
+                let elems = elems.iter().map(|elem| self.expr(elem)).collect();
+                list("(", elems, ")", false)
+            }
+            ExprKind::AnonymousFunction(args, ret_type, body) => {
+                let args = match &args[..] {
+                    [(name, None)] => name.v.clone(),
+                    _ => {
+                        let args: Vec<_> =
+                            args.iter().map(|(name, annot)| arg(name, annot)).collect();
+                        format!("({})", args.join(", "))
+                    }
+                };
+                let ret_type = match ret_type {
+                    Some(ret_type) => format!("-> {} ", ty(ret_type)),
+                    None => "".into(),
+                };
+                concat(vec![text(format!("{args} -> {ret_type}")), self.expr(body)])
+            }
+            ExprKind::IfElse(cond, then_expr, else_expr) => concat(vec![
+                text("if "),
+                self.head_expr(cond),
+                text(" "),
+                self.expr(then_expr),
+                text(" else "),
+                self.expr(else_expr),
+            ]),
+            ExprKind::Match(scrutinee, arms) => {
+                let head = concat(vec![text("match "), self.head_expr(scrutinee), text(" ")]);
+                let body = self.sequence(
+                    expr.loc.lo as usize,
+                    expr.loc.hi as usize,
+                    arms,
+                    |arm| arm.loc.range(),
+                    ",",
+                    Self::match_arm,
+                );
+                braced(head, body)
+            }
+            ExprKind::Block(stmts) => {
+                let body = self.sequence(
+                    expr.loc.lo as usize,
+                    expr.loc.hi as usize,
+                    stmts,
+                    |stmt| stmt.loc.range(),
+                    "",
+                    Self::stmt,
+                );
+                braced(Doc::Nil, body)
+            }
+            ExprKind::BinOp(lhs, op, rhs) => {
+                let prec = op_precedence(*op);
+                let (lhs_prec, rhs_prec) = match op {
+                    BinaryOperator::Format => (prec + 1, prec),
+                    _ => (prec, prec + 1),
+                };
+                self.in_head = in_head;
+                let lhs = self.operand(lhs, lhs_prec);
+                self.in_head = in_head;
+                let rhs = self.operand(rhs, rhs_prec);
+                concat(vec![lhs, text(format!(" {} ", op_str(*op))), rhs])
+            }
+            ExprKind::FuncAp(f, args) => {
+                self.in_head = in_head;
+                let f = self.operand(f, POSTFIX);
+                let args = args.iter().map(|arg| self.expr(arg)).collect();
+                concat(vec![f, list("(", args, ")", true)])
+            }
+            ExprKind::MemberFuncAp(receiver, fname, args) => {
+                let receiver = match receiver {
+                    Some(receiver) => {
+                        self.in_head = in_head;
+                        self.operand(receiver, POSTFIX)
+                    }
+                    None => Doc::Nil,
+                };
+                let args = args.iter().map(|arg| self.expr(arg)).collect();
+                concat(vec![
+                    receiver,
+                    text(format!(".{}", fname.v)),
+                    list("(", args, ")", true),
+                ])
+            }
+            ExprKind::StructLiteral(name, base, fields) => {
+                let mut members = vec![];
+                if let Some(base) = base {
+                    members.push(concat(vec![text(".."), self.expr(base)]));
+                }
+                // `name { field }` is only a struct literal for capitalized names
+                let lowercase_name = name
+                    .as_ref()
+                    .is_some_and(|name| !name.v.starts_with(|c: char| c.is_ascii_uppercase()));
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    let shorthand = matches!(&*value.kind, ExprKind::Variable(v) if *v == field.v)
+                        && !(i == 0 && base.is_none() && lowercase_name);
+                    if shorthand {
+                        members.push(text(&field.v));
+                    } else {
+                        members.push(concat(vec![
+                            text(format!("{}: ", field.v)),
+                            self.expr(value),
+                        ]));
+                    }
+                }
+                let head = match name {
+                    Some(name) => text(format!("{} {{", name.v)),
+                    None => text("{"),
+                };
+                let doc = group(concat(vec![
+                    head,
+                    nest(concat(vec![
+                        Doc::Line,
+                        concat(join(members, || concat(vec![text(","), Doc::Line]))),
+                        Doc::IfBreak(","),
+                    ])),
+                    Doc::Line,
+                    text("}"),
+                ]));
+                // `if Point { x, y }.y == 4 {` would read `Point` as the condition
+                if in_head {
+                    concat(vec![text("("), doc, text(")")])
+                } else {
+                    doc
+                }
+            }
+            ExprKind::MemberAccess(accessed, field) => {
+                self.in_head = in_head;
+                concat(vec![
+                    self.operand(accessed, POSTFIX),
+                    text(format!(".{}", field.v)),
+                ])
+            }
+            ExprKind::MemberAccessLeadingDot(ident) => text(format!(".{}", ident.v)),
+            ExprKind::IndexAccess(accessed, index) => {
+                self.in_head = in_head;
+                let accessed = self.operand(accessed, POSTFIX);
+                concat(vec![accessed, text("["), self.expr(index), text("]")])
+            }
+            ExprKind::Unwrap(expr) => {
+                self.in_head = in_head;
+                concat(vec![self.operand(expr, POSTFIX), text("!")])
+            }
+        }
+    }
+    fn operand(&mut self, expr: &Rc<Expr>, min_precedence: u8) -> Doc {
+        if expr_precedence(expr) < min_precedence {
+            // parenthesized, so it's no longer an operand of a condition
+            self.in_head = false;
+            concat(vec![text("("), self.expr(expr), text(")")])
+        } else {
+            self.expr(expr)
+        }
+    }
+    fn match_arm(&mut self, arm: &Rc<MatchArm>) -> Doc {
+        concat(vec![
+            text(format!("{} -> ", self.pat(&arm.pat))),
+            self.stmt(&arm.stmt),
+        ])
+    }
+    fn pat(&self, pat: &Rc<Pat>) -> String {
+        match &*pat.kind {
+            PatKind::Wildcard => "_".into(),
+            PatKind::Binding(name) => name.clone(),
+            PatKind::Variant(prefixes, ctor, data) => {
+                let mut s = String::new();
+                for prefix in prefixes {
+                    s.push_str(&prefix.v);
+                    s.push('.');
+                }
+                if prefixes.is_empty() {
+                    s.push('.');
+                }
+                s.push_str(&ctor.v);
+                if let Some(data) = data {
+                    match &*data.kind {
+                        PatKind::Tuple(_) => s.push_str(&self.pat(data)),
+                        _ => s.push_str(&format!("({})", self.pat(data))),
+                    }
+                }
+                s
+            }
+            PatKind::Void => "()".into(),
+            PatKind::Int(i) => i.to_string(),
+            PatKind::Float(f) => f.clone(),
+            PatKind::Bool(b) => b.to_string(),
+            PatKind::Str(s) => format!("\"{s}\""),
+            PatKind::Tuple(pats) => {
+                let pats: Vec<_> = pats.iter().map(|pat| self.pat(pat)).collect();
+                format!("({})", pats.join(", "))
+            }
+        }
+    }
+}
+const POSTFIX: u8 = 8;
+fn op_precedence(op: BinaryOperator) -> u8 {
+    match op {
+        BinaryOperator::Equal => 1,
+        BinaryOperator::Format => 2,
+        BinaryOperator::And | BinaryOperator::Or => 3,
+        BinaryOperator::LessThan
+        | BinaryOperator::LessThanOrEqual
+        | BinaryOperator::GreaterThan
+        | BinaryOperator::GreaterThanOrEqual => 4,
+        BinaryOperator::Add | BinaryOperator::Subtract => 5,
+        BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Mod => 6,
+        BinaryOperator::Pow => 7,
+    }
+}
+fn expr_precedence(expr: &Expr) -> u8 {
+    match &*expr.kind {
+        ExprKind::BinOp(_, op, _) => op_precedence(*op),
+        ExprKind::FuncAp(..)
+        | ExprKind::MemberFuncAp(Some(_), ..)
+        | ExprKind::MemberAccess(..)
+        | ExprKind::IndexAccess(..)
+        | ExprKind::Unwrap(_) => POSTFIX,
+        // these extend as far right as they can
+        ExprKind::IfElse(..) | ExprKind::AnonymousFunction(..) => 0,
+        _ => POSTFIX + 1,
+    }
+}
+fn op_str(op: BinaryOperator) -> &'static str {
+    match op {
+        BinaryOperator::Equal => "==",
+        BinaryOperator::LessThan => "<",
+        BinaryOperator::LessThanOrEqual => "<=",
+        BinaryOperator::GreaterThan => ">",
+        BinaryOperator::GreaterThanOrEqual => ">=",
+        BinaryOperator::Add => "+",
+        BinaryOperator::Subtract => "-",
+        BinaryOperator::Multiply => "*",
+        BinaryOperator::Divide => "/",
+        BinaryOperator::Mod => "mod",
+        BinaryOperator::Pow => "^",
+        BinaryOperator::And => "and",
+        BinaryOperator::Or => "or",
+        BinaryOperator::Format => "&",
+    }
+}
+fn ends_with_type_name(expr: &Expr) -> bool {
+    match &*expr.kind {
+        ExprKind::Variable(name) => name.starts_with(|c: char| c.is_ascii_uppercase()),
+        ExprKind::BinOp(_, _, rhs) => ends_with_type_name(rhs),
+        _ => false,
+    }
+}
+fn arg(name: &crate::ast::Identifier, annot: &Option<Rc<Type>>) -> String {
+    match annot {
+        Some(annot) => format!("{}: {}", name.v, ty(annot)),
+        None => name.v.clone(),
+    }
+}
+fn func_decl(f: &FuncDecl) -> String {
+    let args: Vec<_> = f.args.iter().map(|(name, annot)| arg(name, annot)).collect();
+    format!("{}({}) -> {}", f.name.v, args.join(", "), ty(&f.ret_type))
+}
+fn ty_params(ty_args: &[Rc<Polytype>]) -> String {
+    if ty_args.is_empty() {
+        return "".into();
+    }
+    let ty_args: Vec<_> = ty_args.iter().map(|polyty| poly(polyty)).collect();
+    format!("<{}>", ty_args.join(", "))
+}
+fn ty(typ: &Type) -> String {
+    match &*typ.kind {
+        TypeKind::Poly(polyty) => poly(polyty),
+        TypeKind::NamedWithParams(name, params) => {
+            if params.is_empty() {
+                return name.v.clone();
+            }
+            let params: Vec<_> = params.iter().map(|param| ty(param)).collect();
+            format!("{}<{}>", name.v, params.join(", "))
+        }
+        TypeKind::Void => "void".into(),
+        TypeKind::Int => "int".into(),
+        TypeKind::Float => "float".into(),
+        TypeKind::Bool => "bool".into(),
+        TypeKind::Str => "string".into(),
+        TypeKind::Function(args, ret) => match &args[..] {
+            [arg] if !matches!(&*arg.kind, TypeKind::Function(..) | TypeKind::Tuple(_)) => {
+                format!("{} -> {}", ty(arg), ty(ret))
+            }
+            _ => {
+                let args: Vec<_> = args.iter().map(|arg| ty(arg)).collect();
+                format!("({}) -> {}", args.join(", "), ty(ret))
+            }
+        },
+        TypeKind::Tuple(elems) => {
+            let elems: Vec<_> = elems.iter().map(|elem| ty(elem)).collect();
+            format!("({})", elems.join(", "))
+        }
+    }
+}
+fn poly(polyty: &Polytype) -> String {
+    let name = &polyty.name.v;
+    let mut chars = name.chars();
+    let short = chars.next().is_some_and(|c| c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_digit());
+    let mut s = if short {
+        name.clone()
+    } else {
+        format!("'{name}")
+    };
+    for iface in &polyty.interfaces {
+        s.push(' ');
+        s.push_str(&interface(iface));
+    }
+    s
+}
+fn interface(iface: &crate::ast::Interface) -> String {
+    if iface.arguments.is_empty() {
+        return iface.name.v.clone();
+    }
+    let args: Vec<_> = iface
+        .arguments
+        .iter()
+        .map(|(name, typ)| format!("{}={}", name.v, ty(typ)))
+        .collect();
+    format!("{}<{}>", iface.name.v, args.join(", "))
+}
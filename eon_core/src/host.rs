@@ -1,6 +1,8 @@
 use crate::addons::name_of_ty;
 use crate::ast::{FileAst, ItemKind, Type, TypeDefKind, TypeKind};
-use crate::vm::{ValueTrait, Vm};
+use crate::vm::{PackedValue, ValueTrait, Vm, VmErrorKind, VmStatus};
+use std::fmt::{self, Display, Formatter};
+use std::io::Write;
 use crate::{ErrorSummary, FileProvider, get_files, statics};
 use std::path::Path;
 use std::process::Command;
@@ -11,8 +13,7 @@ pub fn generate_host_function_enum(
     file_provider: Box<dyn FileProvider>,
     destination: &Path,
 ) -> Result<(), ErrorSummary> {
-    let (file_asts, file_db) =
-        get_files(&[main_host_func_file_name], &*file_provider).map_err(ErrorSummary::msg)?;
+    let (file_asts, file_db) = get_files(&[main_host_func_file_name], &*file_provider)?;
     let inference_ctx = statics::analyze(&file_asts, &file_db, file_provider)?;
     let output = &mut String::new();
     output.push_str(
@@ -235,7 +236,9 @@ fn add_items_from_ast(ast: &Rc<FileAst>, output: &mut String) {
                     swrite!(
                         output,
                         r#"impl<Value: ValueTrait> VmType<Value> for {} {{
+fn eon_type() -> String {{ "{}".to_string() }}
 "#,
+                        s.name.v,
                         s.name.v
                     );
                     output.push_str(
@@ -321,7 +324,9 @@ fn add_items_from_ast(ast: &Rc<FileAst>, output: &mut String) {
                     swrite!(
                         output,
                         r#"impl<Value: ValueTrait> VmType<Value> for {} {{
+fn eon_type() -> String {{ "{}".to_string() }}
 "#,
+                        e.name.v,
                         e.name.v
                     );
                     output.push_str(
@@ -380,10 +385,15 @@ fn add_items_from_ast(ast: &Rc<FileAst>, output: &mut String) {
     }
 }
 pub trait VmType<Value: ValueTrait> {
+    // the type as written in Eon, checked against the signature of a function called from Rust
+    fn eon_type() -> String;
     fn from_vm(vm: &mut Vm<Value>) -> Self;
     fn to_vm(self, vm: &mut Vm<Value>);
 }
 impl<Value: ValueTrait> VmType<Value> for i64 {
+    fn eon_type() -> String {
+        "int".to_string()
+    }
     fn from_vm(vm: &mut Vm<Value>) -> Self {
         vm.pop_int()
     }
@@ -392,6 +402,9 @@ impl<Value: ValueTrait> VmType<Value> for i64 {
     }
 }
 impl<Value: ValueTrait> VmType<Value> for f64 {
+    fn eon_type() -> String {
+        "float".to_string()
+    }
     fn from_vm(vm: &mut Vm<Value>) -> Self {
         vm.pop_float()
     }
@@ -400,6 +413,9 @@ impl<Value: ValueTrait> VmType<Value> for f64 {
     }
 }
 impl<Value: ValueTrait> VmType<Value> for () {
+    fn eon_type() -> String {
+        "void".to_string()
+    }
     fn from_vm(vm: &mut Vm<Value>) -> Self {
         vm.pop();
     }
@@ -408,6 +424,9 @@ impl<Value: ValueTrait> VmType<Value> for () {
     }
 }
 impl<Value: ValueTrait> VmType<Value> for bool {
+    fn eon_type() -> String {
+        "bool".to_string()
+    }
     fn from_vm(vm: &mut Vm<Value>) -> Self {
         vm.pop_bool()
     }
@@ -416,6 +435,9 @@ impl<Value: ValueTrait> VmType<Value> for bool {
     }
 }
 impl<Value: ValueTrait> VmType<Value> for String {
+    fn eon_type() -> String {
+        "string".to_string()
+    }
     fn from_vm(vm: &mut Vm<Value>) -> Self {
         vm.pop().view_string(vm).to_string()
     }
@@ -427,6 +449,9 @@ impl<T, Value: ValueTrait> VmType<Value> for Option<T>
 where
     T: VmType<Value>,
 {
+    fn eon_type() -> String {
+        format!("option<{}>", T::eon_type())
+    }
     fn from_vm(vm: &mut Vm<Value>) -> Self {
         vm.deconstruct_variant();
         let tag = vm.pop_int();
@@ -459,6 +484,9 @@ where
     T: VmType<Value>,
     E: VmType<Value>,
 {
+    fn eon_type() -> String {
+        format!("result<{}, {}>", T::eon_type(), E::eon_type())
+    }
     fn from_vm(vm: &mut Vm<Value>) -> Self {
         vm.deconstruct_variant();
         let tag = vm.pop_int();
@@ -493,6 +521,9 @@ impl<T, Value: ValueTrait> VmType<Value> for Vec<T>
 where
     T: VmType<Value>,
 {
+    fn eon_type() -> String {
+        format!("array<{}>", T::eon_type())
+    }
     fn from_vm(vm: &mut Vm<Value>) -> Self {
         {
             let len = vm.array_len();
@@ -523,6 +554,10 @@ macro_rules! replace_expr {
 macro_rules! tuple_impls {
     ( $( $name:ident ),+ $(,)? ) => {
         impl< Value: ValueTrait, $($name: VmType<Value>),+ > VmType<Value> for ( $($name,)+ ) {
+            fn eon_type() -> String {
+                let elems: Vec<String> = vec![$( $name::eon_type() ),+];
+                format!("({})", elems.join(", "))
+            }
             fn from_vm(vm: &mut Vm<Value>) -> Self {
                 vm.deconstruct_struct();
                 #[allow(non_snake_case)]
@@ -537,8 +572,230 @@ macro_rules! tuple_impls {
                 vm.construct_struct(count as u16);
             }
         }
+        impl< Value: ValueTrait, $($name: VmType<Value>),+ > VmArgs<Value> for ( $($name,)+ ) {
+            fn eon_types() -> Vec<String> {
+                vec![$( $name::eon_type() ),+]
+            }
+            fn push_args(self, vm: &mut Vm<Value>) {
+                #[allow(non_snake_case)]
+                let ($($name,)+) = self;
+                $( $name.to_vm(vm); )+
+            }
+            fn pop_args(vm: &mut Vm<Value>) -> Self {
+                // the last argument is on top, so they are packed into a tuple first
+                let count: usize = [$( replace_expr!($name, 1) ),+].len();
+                vm.construct_struct(count as u16);
+                <Self as VmType<Value>>::from_vm(vm)
+            }
+        }
     };
 }
+// The arguments of a function called from Rust or of a host function, as a tuple.
+pub trait VmArgs<Value: ValueTrait>: Sized {
+    fn eon_types() -> Vec<String>;
+    fn push_args(self, vm: &mut Vm<Value>);
+    fn pop_args(vm: &mut Vm<Value>) -> Self;
+}
+impl<Value: ValueTrait> VmArgs<Value> for () {
+    fn eon_types() -> Vec<String> {
+        vec![]
+    }
+    fn push_args(self, _vm: &mut Vm<Value>) {}
+    fn pop_args(_vm: &mut Vm<Value>) -> Self {}
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostError {
+    Unimplemented(String),
+    WrongSignature {
+        name: String,
+        expected: String,
+        found: String,
+    },
+}
+impl Display for HostError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HostError::Unimplemented(name) => {
+                write!(f, "host function `{name}` is not registered")
+            }
+            HostError::WrongSignature {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "host function `{name}` has type `{expected}`, but was registered as `{found}`"
+            ),
+        }
+    }
+}
+impl std::error::Error for HostError {}
+type HostCall = Box<dyn FnMut(&mut Vm) -> Result<(), String>>;
+struct RegisteredFunction {
+    name: String,
+    // the `host fn` declaration, or `None` for functions declared in Eon source like the prelude
+    declaration: Option<String>,
+    args: Vec<String>,
+    ret: String,
+    call: HostCall,
+}
+// Host functions implemented by closures, an alternative to `generate_host_function_enum` that
+// doesn't need a build script. Compile with `compile_bytecode_with_registry` so the program can
+// call the registered functions.
+#[derive(Default)]
+pub struct HostRegistry {
+    functions: Vec<RegisteredFunction>,
+}
+impl HostRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    // `print_string` and `readline` of the prelude on stdout and stdin. An I/O error fails the
+    // program.
+    pub fn with_stdio() -> Self {
+        let mut registry = Self::new();
+        registry.try_bind("print_string", |(s,): (String,)| {
+            let mut stdout = std::io::stdout();
+            stdout
+                .write_all(s.as_bytes())
+                .and_then(|()| stdout.flush())
+                .map_err(|err| format!("can't write to stdout: {err}"))
+        });
+        registry.try_bind("readline", |()| {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_line(&mut input)
+                .map_err(|err| format!("can't read from stdin: {err}"))?;
+            if input.ends_with('\n') {
+                input.pop();
+                if input.ends_with('\r') {
+                    input.pop();
+                }
+            }
+            Ok(input)
+        });
+        registry
+    }
+    // Declares `host fn name<signature>`, e.g. `signature` is `(a: int, b: int) -> int`, and
+    // implements it with `f`. The arguments are passed to `f` as a tuple.
+    pub fn register<A, R, F>(&mut self, name: &str, signature: &str, mut f: F) -> &mut Self
+    where
+        A: VmArgs<PackedValue>,
+        R: VmType<PackedValue>,
+        F: FnMut(A) -> R + 'static,
+    {
+        self.try_register(name, signature, move |args| Ok(f(args)))
+    }
+    // Like `register`, but an error returned by `f` fails the program.
+    pub fn try_register<A, R, F>(&mut self, name: &str, signature: &str, f: F) -> &mut Self
+    where
+        A: VmArgs<PackedValue>,
+        R: VmType<PackedValue>,
+        F: FnMut(A) -> Result<R, String> + 'static,
+    {
+        self.add(name, Some(format!("host fn {name}{signature}")), f)
+    }
+    // Implements a host function that is declared in Eon source.
+    pub fn bind<A, R, F>(&mut self, name: &str, mut f: F) -> &mut Self
+    where
+        A: VmArgs<PackedValue>,
+        R: VmType<PackedValue>,
+        F: FnMut(A) -> R + 'static,
+    {
+        self.try_bind(name, move |args| Ok(f(args)))
+    }
+    // Like `bind`, but an error returned by `f` fails the program.
+    pub fn try_bind<A, R, F>(&mut self, name: &str, f: F) -> &mut Self
+    where
+        A: VmArgs<PackedValue>,
+        R: VmType<PackedValue>,
+        F: FnMut(A) -> Result<R, String> + 'static,
+    {
+        self.add(name, None, f)
+    }
+    fn add<A, R, F>(&mut self, name: &str, declaration: Option<String>, mut f: F) -> &mut Self
+    where
+        A: VmArgs<PackedValue>,
+        R: VmType<PackedValue>,
+        F: FnMut(A) -> Result<R, String> + 'static,
+    {
+        self.functions.retain(|function| function.name != name);
+        self.functions.push(RegisteredFunction {
+            name: name.to_string(),
+            declaration,
+            args: A::eon_types(),
+            ret: R::eon_type(),
+            call: Box::new(move |vm| {
+                let args = A::pop_args(vm);
+                f(args).map(|ret| ret.to_vm(vm))
+            }),
+        });
+        self
+    }
+    pub(crate) fn declarations(&self) -> String {
+        let mut out = String::new();
+        for function in &self.functions {
+            if let Some(declaration) = &function.declaration {
+                out.push_str(declaration);
+                out.push('\n');
+            }
+        }
+        out
+    }
+    // Checks that every host function of the program is registered with the right types.
+    pub fn check(&self, vm: &Vm) -> Result<(), HostError> {
+        for idx in 0..vm.host_functions.len() {
+            self.lookup(vm, idx as u16)?;
+        }
+        Ok(())
+    }
+    fn lookup(&self, vm: &Vm, idx: u16) -> Result<usize, HostError> {
+        let signature = &vm.host_functions[idx as usize];
+        let Some(i) = self
+            .functions
+            .iter()
+            .position(|function| function.name == signature.name)
+        else {
+            return Err(HostError::Unimplemented(signature.name.clone()));
+        };
+        let function = &self.functions[i];
+        if function.args != signature.args || function.ret != signature.ret {
+            let show = |args: &[String], ret: &str| format!("fn({}) -> {ret}", args.join(", "));
+            return Err(HostError::WrongSignature {
+                name: signature.name.clone(),
+                expected: show(&signature.args, &signature.ret),
+                found: show(&function.args, &function.ret),
+            });
+        }
+        Ok(i)
+    }
+    // Runs the host function `vm` is waiting for and lets it continue, or fails the program if
+    // the function returned an error.
+    pub fn dispatch(&mut self, vm: &mut Vm, idx: u16) -> Result<(), HostError> {
+        let i = self.lookup(vm, idx)?;
+        let result = (self.functions[i].call)(vm);
+        vm.clear_pending_host_func();
+        if let Err(msg) = result {
+            vm.error = Some(Box::new(vm.make_error(VmErrorKind::HostFunction(msg))));
+        }
+        Ok(())
+    }
+    // Runs `vm` until it stops for a reason other than a host function.
+    pub fn run(&mut self, vm: &mut Vm) -> Result<VmStatus, HostError> {
+        loop {
+            vm.run();
+            match vm.status() {
+                VmStatus::PendingHostFunc(idx) => {
+                    self.dispatch(vm, idx)?;
+                    if let status @ VmStatus::Error(_) = vm.status() {
+                        return Ok(status);
+                    }
+                }
+                status => return Ok(status),
+            }
+        }
+    }
+}
 tuple_impls!(A);
 tuple_impls!(A, B);
 tuple_impls!(A, B, C);
@@ -0,0 +1,639 @@
+use crate::ast::{
+    AstNode, Expr, ExprKind, FileAst, FileDatabase, FileId, FuncDecl, FuncDef, ItemKind,
+    Location, Pat, PatKind, Stmt, StmtKind, Type as AstType, TypeDefKind, TypeKind,
+};
+use crate::statics::typecheck::Nominal;
+use crate::statics::{self, Declaration, Report, StaticsContext, Type};
+use crate::{ErrorSummary, FileProvider, get_files};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+pub struct Analysis {
+    ctx: StaticsContext,
+    file_asts: Vec<Rc<FileAst>>,
+    file_db: FileDatabase,
+    reports: Vec<Report>,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hover {
+    pub contents: String,
+    pub range: Range<usize>,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub file: PathBuf,
+    pub range: Range<usize>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Field,
+    Method,
+    Function,
+    Variant,
+    Type,
+    Module,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    pub detail: Option<String>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Struct,
+    Enum,
+    Interface,
+    Implementation,
+    Field,
+    Variant,
+    Method,
+    Variable,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub range: Range<usize>,
+    pub selection_range: Range<usize>,
+    pub children: Vec<Symbol>,
+}
+impl Analysis {
+    pub fn new(
+        main_file_name: &str,
+        file_provider: Box<dyn FileProvider>,
+    ) -> Result<Analysis, ErrorSummary> {
+        let (file_asts, file_db) = get_files(&[main_file_name], &*file_provider)?;
+        let ctx = statics::analyze_keep_errors(&file_asts, &file_db, file_provider);
+        let reports = ctx
+            .errors()
+            .iter()
+            .map(|error| error.report(&file_db))
+            .collect();
+        Ok(Analysis {
+            ctx,
+            file_asts,
+            file_db,
+            reports,
+        })
+    }
+    pub fn reports(&self) -> &[Report] {
+        &self.reports
+    }
+    pub fn hover(&self, file: &Path, offset: usize) -> Option<Hover> {
+        let file_id = self.file_id(file)?;
+        for node in self.nodes_at(file_id, offset) {
+            if !matches!(
+                node,
+                AstNode::Expr(_) | AstNode::Pat(_) | AstNode::Identifier(_)
+            ) {
+                continue;
+            }
+            let Some(ty) = self.ctx.solution_of_node(node.clone()) else {
+                continue;
+            };
+            let name = match &node {
+                AstNode::Identifier(ident) => Some(ident.v.clone()),
+                AstNode::Expr(expr) => match &*expr.kind {
+                    ExprKind::Variable(name) => Some(name.clone()),
+                    _ => None,
+                },
+                AstNode::Pat(pat) => match &*pat.kind {
+                    PatKind::Binding(name) => Some(name.clone()),
+                    _ => None,
+                },
+                _ => None,
+            };
+            let contents = match name {
+                Some(name) => format!("{name}: {ty}"),
+                None => ty.to_string(),
+            };
+            return Some(Hover {
+                contents,
+                range: node.location().range(),
+            });
+        }
+        None
+    }
+    pub fn definition(&self, file: &Path, offset: usize) -> Option<Definition> {
+        let file_id = self.file_id(file)?;
+        for node in self.nodes_at(file_id, offset) {
+            if let Some(decl) = self.ctx.resolution_map.get(&node.id()) {
+                return self.definition_of_decl(decl);
+            }
+        }
+        None
+    }
+    pub fn member_completions(&self, file: &Path, receiver_end: usize) -> Vec<Completion> {
+        let mut completions = vec![];
+        let Some(file_id) = self.file_id(file) else {
+            return completions;
+        };
+        let Some(receiver) = self.receiver_ending_at(file_id, receiver_end) else {
+            return completions;
+        };
+        match self.ctx.resolution_map.get(&receiver.id()) {
+            Some(Declaration::Module(path)) => {
+                if let Some(namespace) = self.ctx.root_namespace.namespace(path) {
+                    for (name, decl) in namespace.public_declarations() {
+                        completions.push(Completion {
+                            label: name.clone(),
+                            kind: completion_kind(decl),
+                            detail: self.detail_of_decl(decl),
+                        });
+                    }
+                }
+            }
+            Some(Declaration::Enum(enum_def)) => {
+                for variant in &enum_def.variants {
+                    completions.push(Completion {
+                        label: variant.ctor.v.clone(),
+                        kind: CompletionKind::Variant,
+                        detail: None,
+                    });
+                }
+            }
+            _ => {
+                if let Some(ty) = self.ctx.solution_of_node(receiver) {
+                    if let Type::Nominal(Nominal::Struct(struct_def), _) = &ty {
+                        for field in &struct_def.fields {
+                            completions.push(Completion {
+                                label: field.name.v.clone(),
+                                kind: CompletionKind::Field,
+                                detail: Some(self.source_text(&field.ty.loc)),
+                            });
+                        }
+                    }
+                    let key = ty.key();
+                    for ((ty_key, name), decl) in &self.ctx.member_functions {
+                        if *ty_key == key {
+                            completions.push(Completion {
+                                label: name.clone(),
+                                kind: CompletionKind::Method,
+                                detail: self.detail_of_decl(decl),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        completions.sort_by(|a, b| a.label.cmp(&b.label));
+        completions.dedup_by(|a, b| a.label == b.label);
+        completions
+    }
+    pub fn document_symbols(&self, file: &Path) -> Vec<Symbol> {
+        let mut symbols = vec![];
+        let Some(file_id) = self.file_id(file) else {
+            return symbols;
+        };
+        let Some(file_ast) = self.file_asts.iter().find(|f| f.loc.file_id == file_id) else {
+            return symbols;
+        };
+        for item in &file_ast.items {
+            let range = item.loc.range();
+            match &*item.kind {
+                ItemKind::FuncDef(f) => {
+                    symbols.push(symbol(&f.name.v, SymbolKind::Function, range, &f.name.loc.range()))
+                }
+                ItemKind::ForeignFuncDecl(f) | ItemKind::HostFuncDecl(f) => {
+                    symbols.push(symbol(&f.name.v, SymbolKind::Function, range, &f.name.loc.range()))
+                }
+                ItemKind::TypeDef(tydef) => match &**tydef {
+                    TypeDefKind::Struct(struct_def) => {
+                        let mut sym = symbol(
+                            &struct_def.name.v,
+                            SymbolKind::Struct,
+                            range,
+                            &struct_def.name.loc.range(),
+                        );
+                        for field in &struct_def.fields {
+                            sym.children.push(symbol(
+                                &field.name.v,
+                                SymbolKind::Field,
+                                field.loc.range(),
+                                &field.name.loc.range(),
+                            ));
+                        }
+                        symbols.push(sym);
+                    }
+                    TypeDefKind::Enum(enum_def) => {
+                        let mut sym = symbol(
+                            &enum_def.name.v,
+                            SymbolKind::Enum,
+                            range,
+                            &enum_def.name.loc.range(),
+                        );
+                        for variant in &enum_def.variants {
+                            sym.children.push(symbol(
+                                &variant.ctor.v,
+                                SymbolKind::Variant,
+                                variant.loc.range(),
+                                &variant.ctor.loc.range(),
+                            ));
+                        }
+                        symbols.push(sym);
+                    }
+                },
+                ItemKind::InterfaceDef(iface_def) => {
+                    let mut sym = symbol(
+                        &iface_def.name.v,
+                        SymbolKind::Interface,
+                        range,
+                        &iface_def.name.loc.range(),
+                    );
+                    for method in &iface_def.methods {
+                        sym.children.push(symbol(
+                            &method.name.v,
+                            SymbolKind::Method,
+                            method.loc.range(),
+                            &method.name.loc.range(),
+                        ));
+                    }
+                    symbols.push(sym);
+                }
+                ItemKind::InterfaceImpl(imp) => {
+                    let name = format!(
+                        "implement {} for {}",
+                        imp.iface.v,
+                        self.source_text(&imp.typ.loc)
+                    );
+                    let mut sym =
+                        symbol(&name, SymbolKind::Implementation, range, &imp.iface.loc.range());
+                    sym.children = methods_symbols(&imp.methods);
+                    symbols.push(sym);
+                }
+                ItemKind::Extension(ext) => {
+                    let name = format!("extend {}", self.source_text(&ext.typ.loc));
+                    let mut sym =
+                        symbol(&name, SymbolKind::Implementation, range, &ext.typ.loc.range());
+                    sym.children = methods_symbols(&ext.methods);
+                    symbols.push(sym);
+                }
+                ItemKind::Stmt(stmt) => {
+                    if let StmtKind::Let(_, (pat, _), _) = &*stmt.kind
+                        && let PatKind::Binding(name) = &*pat.kind
+                    {
+                        symbols.push(symbol(name, SymbolKind::Variable, range, &pat.loc.range()));
+                    }
+                }
+                ItemKind::Import(..) => {}
+            }
+        }
+        symbols
+    }
+    fn file_id(&self, file: &Path) -> Option<FileId> {
+        self.file_db
+            .files
+            .iter()
+            .position(|f| f.full_path == file)
+            .map(|idx| idx as FileId)
+    }
+    fn source_text(&self, loc: &Location) -> String {
+        self.file_db.files[loc.file_id as usize].source[loc.range()]
+            .trim()
+            .to_string()
+    }
+    fn nodes_at(&self, file_id: FileId, offset: usize) -> Vec<AstNode> {
+        let mut nodes = vec![];
+        for file_ast in self.file_asts.iter().filter(|f| f.loc.file_id == file_id) {
+            visit_file(file_ast, &mut |node| {
+                let range = node.location().range();
+                if range.start <= offset && offset <= range.end {
+                    nodes.push(node);
+                }
+            });
+        }
+        nodes.sort_by_key(|node| node.location().range().len());
+        nodes
+    }
+    fn receiver_ending_at(&self, file_id: FileId, end: usize) -> Option<AstNode> {
+        let mut best: Option<AstNode> = None;
+        for file_ast in self.file_asts.iter().filter(|f| f.loc.file_id == file_id) {
+            visit_file(file_ast, &mut |node| {
+                if let AstNode::Expr(_) = node {
+                    let range = node.location().range();
+                    if range.end == end
+                        && best
+                            .as_ref()
+                            .is_none_or(|b| b.location().range().start < range.start)
+                    {
+                        best = Some(node);
+                    }
+                }
+            });
+        }
+        best
+    }
+    fn definition_of_decl(&self, decl: &Declaration) -> Option<Definition> {
+        let loc = match decl {
+            Declaration::FreeFunction(f) | Declaration::MemberFunction { f } => f.name.loc.clone(),
+            Declaration::HostFunction(f) | Declaration::_ForeignFunction { f, .. } => {
+                f.name.loc.clone()
+            }
+            Declaration::InterfaceDef(iface_def) => iface_def.name.loc.clone(),
+            Declaration::InterfaceMethod { iface, method } => {
+                iface.methods[*method].name.loc.clone()
+            }
+            Declaration::InterfaceOutputType { iface: _, ty } => ty.name.loc.clone(),
+            Declaration::Enum(enum_def) => enum_def.name.loc.clone(),
+            Declaration::EnumVariant { e, variant } => {
+                e.variants[*variant].ctor.loc.clone()
+            }
+            Declaration::Struct(struct_def) => struct_def.name.loc.clone(),
+            Declaration::Var(node) => node.location().clone(),
+            Declaration::Module(path) => {
+                let file_ast = self.file_asts.iter().find(|f| f.name == *path)?;
+                let file = self.file_db.files[file_ast.loc.file_id as usize].full_path.clone();
+                return Some(Definition { file, range: 0..0 });
+            }
+            Declaration::Polytype(_)
+            | Declaration::Builtin(_)
+            | Declaration::BuiltinType(_)
+            | Declaration::Array
+            | Declaration::Handle => return None,
+        };
+        Some(Definition {
+            file: self.file_db.files[loc.file_id as usize].full_path.clone(),
+            range: loc.range(),
+        })
+    }
+    fn detail_of_decl(&self, decl: &Declaration) -> Option<String> {
+        let name = match decl {
+            Declaration::FreeFunction(f) | Declaration::MemberFunction { f } => &f.name,
+            Declaration::HostFunction(f) | Declaration::_ForeignFunction { f, .. } => &f.name,
+            _ => return None,
+        };
+        self.ctx
+            .solution_of_node(name.node())
+            .map(|ty| ty.to_string())
+    }
+}
+fn symbol(name: &str, kind: SymbolKind, range: Range<usize>, selection: &Range<usize>) -> Symbol {
+    Symbol {
+        name: name.to_string(),
+        kind,
+        range,
+        selection_range: selection.clone(),
+        children: vec![],
+    }
+}
+fn methods_symbols(methods: &[Rc<FuncDef>]) -> Vec<Symbol> {
+    methods
+        .iter()
+        .map(|f| {
+            let range = f.name.loc.lo as usize..f.body.loc.hi as usize;
+            symbol(&f.name.v, SymbolKind::Method, range, &f.name.loc.range())
+        })
+        .collect()
+}
+fn completion_kind(decl: &Declaration) -> CompletionKind {
+    match decl {
+        Declaration::Struct(_) | Declaration::Enum(_) | Declaration::InterfaceDef(_) => {
+            CompletionKind::Type
+        }
+        Declaration::Module(_) => CompletionKind::Module,
+        Declaration::EnumVariant { .. } => CompletionKind::Variant,
+        _ => CompletionKind::Function,
+    }
+}
+fn visit_file(file_ast: &FileAst, f: &mut impl FnMut(AstNode)) {
+    for item in &file_ast.items {
+        f(item.node());
+        match &*item.kind {
+            ItemKind::FuncDef(func) => visit_func_def(func, f),
+            ItemKind::ForeignFuncDecl(decl) | ItemKind::HostFuncDecl(decl) => {
+                visit_func_decl(decl, f)
+            }
+            ItemKind::TypeDef(tydef) => match &**tydef {
+                TypeDefKind::Struct(struct_def) => {
+                    f(struct_def.name.node());
+                    for field in &struct_def.fields {
+                        f(field.name.node());
+                        visit_type(&field.ty, f);
+                    }
+                }
+                TypeDefKind::Enum(enum_def) => {
+                    f(enum_def.name.node());
+                    for variant in &enum_def.variants {
+                        f(variant.node());
+                        f(variant.ctor.node());
+                        if let Some(data) = &variant.data {
+                            visit_type(data, f);
+                        }
+                    }
+                }
+            },
+            ItemKind::InterfaceDef(iface_def) => {
+                f(iface_def.name.node());
+                for method in &iface_def.methods {
+                    f(method.node());
+                    f(method.name.node());
+                    visit_type(&method.ty, f);
+                }
+            }
+            ItemKind::InterfaceImpl(imp) => {
+                f(imp.iface.node());
+                visit_type(&imp.typ, f);
+                for method in &imp.methods {
+                    visit_func_def(method, f);
+                }
+            }
+            ItemKind::Extension(ext) => {
+                visit_type(&ext.typ, f);
+                for method in &ext.methods {
+                    visit_func_def(method, f);
+                }
+            }
+            ItemKind::Import(path, _) => f(path.node()),
+            ItemKind::Stmt(stmt) => visit_stmt(stmt, f),
+        }
+    }
+}
+fn visit_func_def(func: &FuncDef, f: &mut impl FnMut(AstNode)) {
+    f(func.name.node());
+    for (arg, annot) in &func.args {
+        f(arg.node());
+        if let Some(annot) = annot {
+            visit_type(annot, f);
+        }
+    }
+    if let Some(ret_type) = &func.ret_type {
+        visit_type(ret_type, f);
+    }
+    visit_expr(&func.body, f);
+}
+fn visit_func_decl(decl: &FuncDecl, f: &mut impl FnMut(AstNode)) {
+    f(decl.name.node());
+    for (arg, annot) in &decl.args {
+        f(arg.node());
+        if let Some(annot) = annot {
+            visit_type(annot, f);
+        }
+    }
+    visit_type(&decl.ret_type, f);
+}
+fn visit_stmt(stmt: &Rc<Stmt>, f: &mut impl FnMut(AstNode)) {
+    f(stmt.node());
+    match &*stmt.kind {
+        StmtKind::Let(_, (pat, annot), expr) => {
+            visit_pat(pat, f);
+            if let Some(annot) = annot {
+                visit_type(annot, f);
+            }
+            visit_expr(expr, f);
+        }
+        StmtKind::Set(lhs, rhs) => {
+            visit_expr(lhs, f);
+            visit_expr(rhs, f);
+        }
+        StmtKind::Expr(expr) | StmtKind::Return(expr) => visit_expr(expr, f),
+        StmtKind::If(cond, body) | StmtKind::WhileLoop(cond, body) => {
+            visit_expr(cond, f);
+            visit_expr(body, f);
+        }
+        StmtKind::ForLoop(pat, iterable, body) => {
+            visit_pat(pat, f);
+            visit_expr(iterable, f);
+            visit_expr(body, f);
+        }
+        StmtKind::Continue | StmtKind::Break => {}
+    }
+}
+fn visit_expr(expr: &Rc<Expr>, f: &mut impl FnMut(AstNode)) {
+    f(expr.node());
+    match &*expr.kind {
+        ExprKind::Variable(_)
+        | ExprKind::Void
+        | ExprKind::Int(_)
+        | ExprKind::Float(_)
+        | ExprKind::Bool(_)
+        | ExprKind::Str(_) => {}
+        ExprKind::Array(exprs) | ExprKind::Tuple(exprs) => {
+            for expr in exprs {
+                visit_expr(expr, f);
+            }
+        }
+        ExprKind::AnonymousFunction(args, ret_type, body) => {
+            for (arg, annot) in args {
+                f(arg.node());
+                if let Some(annot) = annot {
+                    visit_type(annot, f);
+                }
+            }
+            if let Some(ret_type) = ret_type {
+                visit_type(ret_type, f);
+            }
+            visit_expr(body, f);
+        }
+        ExprKind::IfElse(cond, then_block, else_block) => {
+            visit_expr(cond, f);
+            visit_expr(then_block, f);
+            visit_expr(else_block, f);
+        }
+        ExprKind::Match(scrutinee, arms) => {
+            visit_expr(scrutinee, f);
+            for arm in arms {
+                f(arm.node());
+                visit_pat(&arm.pat, f);
+                visit_stmt(&arm.stmt, f);
+            }
+        }
+        ExprKind::Block(stmts) => {
+            for stmt in stmts {
+                visit_stmt(stmt, f);
+            }
+        }
+        ExprKind::BinOp(left, _, right) => {
+            visit_expr(left, f);
+            visit_expr(right, f);
+        }
+        ExprKind::FuncAp(func, args) => {
+            visit_expr(func, f);
+            for arg in args {
+                visit_expr(arg, f);
+            }
+        }
+        ExprKind::MemberFuncAp(receiver, fname, args) => {
+            if let Some(receiver) = receiver {
+                visit_expr(receiver, f);
+            }
+            f(fname.node());
+            for arg in args {
+                visit_expr(arg, f);
+            }
+        }
+        ExprKind::StructLiteral(name, base, fields) => {
+            if let Some(name) = name {
+                f(name.node());
+            }
+            if let Some(base) = base {
+                visit_expr(base, f);
+            }
+            for (field, value) in fields {
+                f(field.node());
+                visit_expr(value, f);
+            }
+        }
+        ExprKind::MemberAccess(accessed, field) => {
+            visit_expr(accessed, f);
+            f(field.node());
+        }
+        ExprKind::MemberAccessLeadingDot(ident) => f(ident.node()),
+        ExprKind::IndexAccess(accessed, index) => {
+            visit_expr(accessed, f);
+            visit_expr(index, f);
+        }
+        ExprKind::Unwrap(expr) => visit_expr(expr, f),
+    }
+}
+fn visit_pat(pat: &Rc<Pat>, f: &mut impl FnMut(AstNode)) {
+    f(pat.node());
+    match &*pat.kind {
+        PatKind::Variant(prefixes, ctor, data) => {
+            for prefix in prefixes {
+                f(prefix.node());
+            }
+            f(ctor.node());
+            if let Some(data) = data {
+                visit_pat(data, f);
+            }
+        }
+        PatKind::Tuple(pats) => {
+            for pat in pats {
+                visit_pat(pat, f);
+            }
+        }
+        PatKind::Wildcard
+        | PatKind::Binding(_)
+        | PatKind::Void
+        | PatKind::Int(_)
+        | PatKind::Float(_)
+        | PatKind::Bool(_)
+        | PatKind::Str(_) => {}
+    }
+}
+fn visit_type(ty: &Rc<AstType>, f: &mut impl FnMut(AstNode)) {
+    f(ty.node());
+    match &*ty.kind {
+        TypeKind::Poly(polyty) => f(polyty.name.node()),
+        TypeKind::NamedWithParams(name, params) => {
+            f(name.node());
+            for param in params {
+                visit_type(param, f);
+            }
+        }
+        TypeKind::Function(args, out) => {
+            for arg in args {
+                visit_type(arg, f);
+            }
+            visit_type(out, f);
+        }
+        TypeKind::Tuple(elems) => {
+            for elem in elems {
+                visit_type(elem, f);
+            }
+        }
+        TypeKind::Void | TypeKind::Int | TypeKind::Float | TypeKind::Bool | TypeKind::Str => {}
+    }
+}
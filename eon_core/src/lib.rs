@@ -2,6 +2,7 @@ extern crate core;
 use ast::FileAst;
 use ast::FileDatabase;
 use ast::FileId;
+use ast::Identifier;
 use ast::ItemKind;
 use core::fmt;
 use std::collections::HashMap;
@@ -15,19 +16,28 @@ pub mod addons;
 mod assembly;
 pub mod ast;
 mod builtin;
+pub mod coverage;
+pub mod debugger;
+pub mod embed;
 pub mod environment;
+pub mod executor;
+pub mod formatter;
 pub mod host;
+pub mod ide;
 mod optimize_bytecode;
 mod parse;
 pub mod prelude;
+pub mod profiler;
 pub mod statics;
 mod translate_bytecode;
 pub mod vm;
+use addons::StaticModule;
 pub use ast::FileData;
-pub use host::{generate_host_function_enum, VmType};
+pub use host::{generate_host_function_enum, HostRegistry, VmType};
 pub use prelude::PRELUDE;
 use statics::Error;
-use translate_bytecode::CompiledProgram;
+use statics::Report;
+pub use translate_bytecode::{CompiledProgram, ExportedFunction, HostFunctionSignature, TestFunction};
 use translate_bytecode::Translator;
 pub fn eon_hello_world() {
     println!("Hello, world!");
@@ -59,6 +69,36 @@ pub fn compile_bytecode_with_host_funcs(
         file_provider,
     )
 }
+pub fn check(
+    main_file_name: &str,
+    file_provider: Box<dyn FileProvider>,
+) -> Result<(), ErrorSummary> {
+    let (file_asts, file_db) = get_files(&[main_file_name], &*file_provider)?;
+    statics::analyze(&file_asts, &file_db, file_provider)?;
+    Ok(())
+}
+pub fn compile_tests(
+    main_file_name: &str,
+    file_provider: Box<dyn FileProvider>,
+) -> Result<CompiledProgram, ErrorSummary> {
+    let (file_asts, file_db) = get_files(&[main_file_name], &*file_provider)?;
+    let inference_ctx = statics::analyze(&file_asts, &file_db, file_provider)?;
+    let translator = Translator::new(inference_ctx, file_db, file_asts);
+    translator.translate_with_tests()
+}
+// The functions registered with `registry` are declared as if they were part of the prelude.
+pub fn compile_bytecode_with_registry(
+    main_file_name: &str,
+    registry: &HostRegistry,
+    file_provider: Box<dyn FileProvider>,
+) -> Result<CompiledProgram, ErrorSummary> {
+    let prelude = format!("{PRELUDE}\n{}", registry.declarations());
+    let (file_asts, file_db) =
+        get_files_with_prelude(&[main_file_name], &*file_provider, &prelude)?;
+    let inference_ctx = statics::analyze(&file_asts, &file_db, file_provider)?;
+    let translator = Translator::new(inference_ctx, file_db, file_asts);
+    translator.translate()
+}
 fn compile_bytecode_(
     main_file_name: &str,
     main_host_func_file_name: Option<&str>,
@@ -68,10 +108,10 @@ fn compile_bytecode_(
     if let Some(host) = main_host_func_file_name {
         roots.push(host);
     }
-    let (file_asts, file_db) = get_files(&roots, &*file_provider).map_err(ErrorSummary::msg)?;
+    let (file_asts, file_db) = get_files(&roots, &*file_provider)?;
     let inference_ctx = statics::analyze(&file_asts, &file_db, file_provider)?;
     let translator = Translator::new(inference_ctx, file_db, file_asts);
-    Ok(translator.translate())
+    translator.translate()
 }
 #[derive(Debug)]
 pub struct ErrorSummary {
@@ -90,6 +130,23 @@ impl ErrorSummary {
             }
         }
     }
+    pub fn reports(&self) -> Vec<Report> {
+        let mut reports = vec![];
+        if !self.msg.is_empty() {
+            reports.push(Report {
+                severity: "error",
+                message: self.msg.clone(),
+                labels: vec![],
+                notes: vec![],
+            });
+        }
+        if let Some((file_db, errors)) = &self.more {
+            for error in errors {
+                reports.push(error.report(file_db));
+            }
+        }
+        reports
+    }
     pub fn to_string_ansi(&self) -> String {
         let mut s = String::new();
         s.push_str(&self.msg);
@@ -116,28 +173,42 @@ impl std::error::Error for ErrorSummary {}
 fn get_files(
     roots: &[&str],
     file_provider: &dyn FileProvider,
-) -> Result<(Vec<Rc<FileAst>>, FileDatabase), String> {
+) -> Result<(Vec<Rc<FileAst>>, FileDatabase), ErrorSummary> {
+    get_files_with_prelude(roots, file_provider, PRELUDE)
+}
+fn get_files_with_prelude(
+    roots: &[&str],
+    file_provider: &dyn FileProvider,
+    prelude: &str,
+) -> Result<(Vec<Rc<FileAst>>, FileDatabase), ErrorSummary> {
     let mut errors: Vec<Error> = vec![];
     let mut file_db = FileDatabase::new();
     let mut file_asts: Vec<Rc<FileAst>> = vec![];
     let mut stack: VecDeque<FileId> = VecDeque::new();
     let mut visited = HashSet::<PathBuf>::default();
+    let mut imports = HashMap::<String, Vec<Rc<Identifier>>>::default();
     for root in roots {
         let main_file_data = file_provider.search_for_file(Path::new(root)).unwrap();
-        visited.insert(main_file_data.full_path.clone());
+        visited.insert(main_file_data.nominal_path.clone());
         let id = file_db.add(main_file_data);
         stack.push_back(id);
     }
     {
         let prelude_file_data =
-            FileData::new("prelude.en".into(), "prelude.en".into(), PRELUDE.into());
-        visited.insert(prelude_file_data.full_path.clone());
+            FileData::new("prelude.en".into(), "prelude.en".into(), prelude.into());
+        visited.insert(prelude_file_data.nominal_path.clone());
         let id = file_db.add(prelude_file_data);
         stack.push_back(id);
     }
     while let Some(file_id) = stack.pop_front() {
         let file_data = file_db.get(file_id).unwrap();
-        let file_ast = parse::parse_or_err(file_id, file_data)?;
+        let file_ast = match parse::parse_or_err(file_id, file_data) {
+            Ok(file_ast) => file_ast,
+            Err(err) => {
+                errors.push(*err);
+                continue;
+            }
+        };
         file_asts.push(file_ast.clone());
         add_imports(
             file_ast,
@@ -145,9 +216,17 @@ fn get_files(
             file_provider,
             &mut stack,
             &mut visited,
+            &mut imports,
             &mut errors,
         );
     }
+    check_import_cycles(&file_asts, &imports, &mut errors);
+    if !errors.is_empty() {
+        return Err(ErrorSummary {
+            msg: "".into(),
+            more: Some((file_db, errors)),
+        });
+    }
     Ok((file_asts, file_db))
 }
 #[derive(Debug)]
@@ -164,10 +243,15 @@ fn add_imports(
     file_provider: &dyn FileProvider,
     stack: &mut VecDeque<FileId>,
     visited: &mut HashSet<PathBuf>,
+    imports: &mut HashMap<String, Vec<Rc<Identifier>>>,
     errors: &mut Vec<Error>,
 ) {
     for item in file_ast.items.iter() {
         if let ItemKind::Import(ident, _) = &*item.kind {
+            imports
+                .entry(file_ast.name.clone())
+                .or_default()
+                .push(ident.clone());
             let path = PathBuf::from(format!("{}.en", ident.v));
             if !visited.contains(&path) {
                 visited.insert(path.clone());
@@ -177,12 +261,51 @@ fn add_imports(
                         let file_id = file_db.add(file_data);
                         stack.push_back(file_id);
                     }
-                    Err(_) => errors.push(Error::UnresolvedIdentifier { node: item.node() }),
+                    Err(_) => errors.push(Error::ModuleNotFound {
+                        node: ident.node(),
+                        path: ident.v.clone(),
+                    }),
                 }
             }
         }
     }
 }
+fn check_import_cycles(
+    file_asts: &[Rc<FileAst>],
+    imports: &HashMap<String, Vec<Rc<Identifier>>>,
+    errors: &mut Vec<Error>,
+) {
+    fn visit(
+        module: &str,
+        imports: &HashMap<String, Vec<Rc<Identifier>>>,
+        path: &mut Vec<String>,
+        done: &mut HashSet<String>,
+        errors: &mut Vec<Error>,
+    ) {
+        if done.contains(module) {
+            return;
+        }
+        path.push(module.to_string());
+        for ident in imports.get(module).into_iter().flatten() {
+            if let Some(start) = path.iter().position(|m| *m == ident.v) {
+                let mut cycle = path[start..].to_vec();
+                cycle.push(ident.v.clone());
+                errors.push(Error::ImportCycle {
+                    node: ident.node(),
+                    cycle,
+                });
+            } else {
+                visit(&ident.v, imports, path, done, errors);
+            }
+        }
+        path.pop();
+        done.insert(module.to_string());
+    }
+    let mut done = HashSet::default();
+    for file_ast in file_asts {
+        visit(&file_ast.name, imports, &mut vec![], &mut done, errors);
+    }
+}
 pub trait FileProvider {
     fn search_for_file(&self, path: &Path) -> Result<FileData, Box<dyn std::error::Error>>;
     #[cfg(feature = "ffi")]
@@ -190,8 +313,9 @@ pub trait FileProvider {
 }
 #[derive(Default, Debug)]
 pub struct OsFileProvider {
-    main_file_dir: PathBuf,
-    modules: PathBuf,
+    roots: Vec<PathBuf>,
+    // sources of modules linked into the binary, used when no file in `roots` has the same path
+    static_files: HashMap<PathBuf, &'static str>,
     #[cfg(feature = "ffi")]
     shared_objects_dir: PathBuf,
 }
@@ -199,29 +323,37 @@ impl OsFileProvider {
     pub fn new(
         main_file_dir: PathBuf,
         modules: PathBuf,
-        _shared_objects_dir: PathBuf,
+        shared_objects_dir: PathBuf,
     ) -> Box<Self> {
+        Self::with_roots(vec![modules, main_file_dir], shared_objects_dir)
+    }
+    pub fn with_roots(roots: Vec<PathBuf>, _shared_objects_dir: PathBuf) -> Box<Self> {
         Box::new(Self {
-            main_file_dir,
-            modules,
+            roots,
+            static_files: HashMap::new(),
             #[cfg(feature = "ffi")]
             shared_objects_dir: _shared_objects_dir,
         })
     }
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+    pub fn add_static_module(&mut self, module: &StaticModule) {
+        for (path, source) in module.files {
+            self.static_files.insert(PathBuf::from(path), source);
+        }
+    }
 }
 impl FileProvider for OsFileProvider {
     fn search_for_file(&self, path: &Path) -> Result<FileData, Box<dyn std::error::Error>> {
-        {
-            let desired = self.modules.join(path);
+        for root in &self.roots {
+            let desired = root.join(path);
             if let Ok(contents) = std::fs::read_to_string(&desired) {
                 return Ok(FileData::new(path.to_owned(), desired.clone(), contents));
             }
         }
-        {
-            let desired = self.main_file_dir.join(path);
-            if let Ok(contents) = std::fs::read_to_string(&desired) {
-                return Ok(FileData::new(path.to_owned(), desired.clone(), contents));
-            }
+        if let Some(source) = self.static_files.get(path) {
+            return Ok(FileData::new(path.to_owned(), path.to_owned(), source.to_string()));
         }
         Err(Box::new(MyError(format!(
             "Could not find desired file: {}",
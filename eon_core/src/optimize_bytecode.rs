@@ -1,22 +1,48 @@
-use crate::assembly::{Instr, Line};
-pub(crate) fn optimize(lines: Vec<Line>) -> Vec<Line> {
+use crate::assembly::{Instr, Label, Line};
+use utils::hash::{HashMap, HashSet};
+// An operation on constants that is certain to fail whenever it runs.
+#[derive(Debug)]
+pub(crate) struct ConstantError {
+    pub(crate) file_id: u32,
+    pub(crate) span: (u32, u32),
+    pub(crate) msg: &'static str,
+}
+// `entry_points` are labels that are jumped to from outside the program, like tests. Constant
+// errors are only reported for code that runs from the start of the program or from one of
+// `checked_entry_points`, so a function that is only exported to the embedder may still fail.
+pub(crate) fn optimize(
+    lines: Vec<Line>,
+    entry_points: &[Label],
+    checked_entry_points: &[Label],
+) -> Result<Vec<Line>, Vec<ConstantError>> {
     let mut len = lines.len();
     let mut ret = lines;
     loop {
         ret = peephole2(ret);
+        ret = inline_small_functions(ret);
+        ret = fold_constants(ret);
+        ret = eliminate_dead_code(ret, entry_points);
         if ret.len() < len {
             len = ret.len();
         } else {
             break;
         }
-        ret = peephole3(ret);
-        if ret.len() < len {
-            len = ret.len();
+    }
+    let mut checked = ret.clone();
+    loop {
+        checked = eliminate_dead_code(checked, checked_entry_points);
+        if checked.len() < len {
+            len = checked.len();
         } else {
             break;
         }
     }
-    ret
+    let errors = constant_errors(&checked);
+    if errors.is_empty() {
+        Ok(ret)
+    } else {
+        Err(errors)
+    }
 }
 pub(crate) fn peephole2(lines: Vec<Line>) -> Vec<Line> {
     let mut ret: Vec<Line> = vec![];
@@ -33,6 +59,7 @@ pub(crate) fn peephole2(lines: Vec<Line>) -> Vec<Line> {
                 lineno,
                 file_id,
                 func_id,
+                span,
             } => {
                 let mut noop = |index: &mut usize| {
                     ret.push(curr.clone());
@@ -51,6 +78,7 @@ pub(crate) fn peephole2(lines: Vec<Line>) -> Vec<Line> {
                                 lineno,
                                 file_id,
                                 func_id,
+                                span,
                             });
                             index += 2;
                         }
@@ -72,9 +100,23 @@ pub(crate) fn peephole2(lines: Vec<Line>) -> Vec<Line> {
                                 lineno,
                                 file_id,
                                 func_id,
+                                span,
+                            });
+                            index += 2;
+                        }
+                        (Instr::PushBool(true), Instr::JumpIf(label)) => {
+                            ret.push(Line::Instr {
+                                instr: Instr::Jump(label.clone()),
+                                lineno,
+                                file_id,
+                                func_id,
+                                span,
                             });
                             index += 2;
                         }
+                        (Instr::PushBool(false), Instr::JumpIf(_)) => {
+                            index += 2;
+                        }
                         _ => {
                             noop(&mut index);
                         }
@@ -87,52 +129,305 @@ pub(crate) fn peephole2(lines: Vec<Line>) -> Vec<Line> {
     }
     ret
 }
-pub(crate) fn peephole3(lines: Vec<Line>) -> Vec<Line> {
+#[derive(Debug, Clone)]
+enum Constant {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+}
+fn as_constant(line: &Line) -> Option<Constant> {
+    let Line::Instr { instr, .. } = line else {
+        return None;
+    };
+    match instr {
+        Instr::PushInt(n) => Some(Constant::Int(*n)),
+        Instr::PushFloat(f) => Some(Constant::Float(f.parse().unwrap())),
+        Instr::PushBool(b) => Some(Constant::Bool(*b)),
+        Instr::PushString(s) => Some(Constant::String(s.clone())),
+        _ => None,
+    }
+}
+// Computes `instr` like the VM does. Operations that would fail at runtime aren't folded.
+fn eval_unary(instr: &Instr, a: &Constant) -> Option<Constant> {
+    use Constant::*;
+    Some(match (instr, a) {
+        (Instr::Not, Bool(a)) => Bool(!a),
+        (Instr::SquareRoot, Float(a)) => Float(a.sqrt()),
+        (Instr::IntToString, Int(a)) => String(a.to_string()),
+        (Instr::FloatToString, Float(a)) => String(a.to_string()),
+        _ => return None,
+    })
+}
+fn eval_binary(instr: &Instr, a: &Constant, b: &Constant) -> Option<Constant> {
+    use Constant::*;
+    Some(match (instr, a, b) {
+        (Instr::AddInt, Int(a), Int(b)) => Int(a.wrapping_add(*b)),
+        (Instr::SubtractInt, Int(a), Int(b)) => Int(a.wrapping_sub(*b)),
+        (Instr::MultiplyInt, Int(a), Int(b)) => Int(a.wrapping_mul(*b)),
+        (Instr::DivideInt, Int(a), Int(b)) if *b != 0 => Int(a.wrapping_div(*b)),
+        (Instr::PowerInt, Int(a), Int(b)) => Int(a.wrapping_pow(*b as u32)),
+        (Instr::Modulo, Int(a), Int(b)) => Int(a.checked_rem(*b)?),
+        (Instr::AddFloat, Float(a), Float(b)) => Float(a + b),
+        (Instr::SubtractFloat, Float(a), Float(b)) => Float(a - b),
+        (Instr::MultiplyFloat, Float(a), Float(b)) => Float(a * b),
+        (Instr::DivideFloat, Float(a), Float(b)) if *b != 0.0 => Float(a / b),
+        (Instr::PowerFloat, Float(a), Float(b)) => Float(a.powf(*b)),
+        (Instr::And, Bool(a), Bool(b)) => Bool(*a && *b),
+        (Instr::Or, Bool(a), Bool(b)) => Bool(*a || *b),
+        (Instr::LessThanInt, Int(a), Int(b)) => Bool(a < b),
+        (Instr::LessThanOrEqualInt, Int(a), Int(b)) => Bool(a <= b),
+        (Instr::GreaterThanInt, Int(a), Int(b)) => Bool(a > b),
+        (Instr::GreaterThanOrEqualInt, Int(a), Int(b)) => Bool(a >= b),
+        (Instr::LessThanFloat, Float(a), Float(b)) => Bool(a < b),
+        (Instr::LessThanOrEqualFloat, Float(a), Float(b)) => Bool(a <= b),
+        (Instr::GreaterThanFloat, Float(a), Float(b)) => Bool(a > b),
+        (Instr::GreaterThanOrEqualFloat, Float(a), Float(b)) => Bool(a >= b),
+        (Instr::EqualInt, Int(a), Int(b)) => Bool(a == b),
+        (Instr::EqualFloat, Float(a), Float(b)) => Bool(a == b),
+        (Instr::EqualBool, Bool(a), Bool(b)) => Bool(a == b),
+        (Instr::EqualString, String(a), String(b)) => Bool(a == b),
+        (Instr::ConcatStrings, String(a), String(b)) => String(format!("{a}{b}")),
+        _ => return None,
+    })
+}
+// Replaces operations on constants pushed right before them by their result. Operands are only
+// taken from the same basic block, since no label can come between them.
+pub(crate) fn fold_constants(lines: Vec<Line>) -> Vec<Line> {
+    let mut ret: Vec<Line> = vec![];
+    for line in lines {
+        let Line::Instr {
+            instr,
+            lineno,
+            file_id,
+            func_id,
+            span,
+        } = &line
+        else {
+            ret.push(line);
+            continue;
+        };
+        let n = ret.len();
+        let b = ret.last().and_then(as_constant);
+        let a = if n >= 2 { as_constant(&ret[n - 2]) } else { None };
+        let folded = match (a, b) {
+            (Some(a), Some(b)) => eval_binary(instr, &a, &b).map(|c| (2, c)),
+            _ => None,
+        }
+        .or_else(|| {
+            let b = ret.last().and_then(as_constant)?;
+            eval_unary(instr, &b).map(|c| (1, c))
+        });
+        let Some((operands, constant)) = folded else {
+            ret.push(line);
+            continue;
+        };
+        ret.truncate(n - operands);
+        let instr = match constant {
+            Constant::Int(n) => Instr::PushInt(n),
+            Constant::Float(f) => Instr::PushFloat(format!("{f:?}")),
+            Constant::Bool(b) => Instr::PushBool(b),
+            Constant::String(s) => Instr::PushString(s),
+        };
+        ret.push(Line::Instr {
+            instr,
+            lineno: *lineno,
+            file_id: *file_id,
+            func_id: *func_id,
+            span: *span,
+        });
+    }
+    ret
+}
+// Drops instructions that can't be reached: those after an unconditional jump, up to a label
+// something still jumps to. Labels are kept, since debug info may refer to them.
+pub(crate) fn eliminate_dead_code(lines: Vec<Line>, entry_points: &[Label]) -> Vec<Line> {
+    let mut targets: HashSet<&Label> = entry_points.iter().collect();
+    for line in &lines {
+        if let Line::Instr {
+            instr:
+                Instr::Jump(label)
+                | Instr::JumpIf(label)
+                | Instr::Call(_, label)
+                | Instr::TailCall(_, label)
+                | Instr::MakeClosure { func_addr: label },
+            ..
+        } = line
+        {
+            targets.insert(label);
+        }
+    }
     let mut ret: Vec<Line> = vec![];
-    let mut index = 0;
-    while index < lines.len() {
-        let curr = &lines[index];
-        match lines[index].clone() {
-            Line::Label(_) => {
-                ret.push(curr.clone());
-                index += 1;
+    let mut reachable = true;
+    for (i, line) in lines.iter().enumerate() {
+        match line {
+            Line::Label(label) => {
+                reachable |= targets.contains(label);
+                ret.push(line.clone());
             }
+            Line::Instr { .. } if !reachable => {}
+            Line::Instr { instr, .. } => {
+                // a jump to the next instruction does nothing
+                if let Instr::Jump(label) = instr
+                    && jumps_to_next(&lines[i + 1..], label)
+                {
+                    continue;
+                }
+                reachable = !matches!(
+                    instr,
+                    Instr::Jump(_) | Instr::TailCall(..) | Instr::Return(_) | Instr::Stop | Instr::Panic
+                );
+                ret.push(line.clone());
+            }
+        }
+    }
+    ret
+}
+fn jumps_to_next(rest: &[Line], label: &Label) -> bool {
+    for line in rest {
+        match line {
+            Line::Label(l) if l == label => return true,
+            Line::Label(_) => {}
+            Line::Instr { .. } => return false,
+        }
+    }
+    false
+}
+fn constant_errors(lines: &[Line]) -> Vec<ConstantError> {
+    let mut errors = vec![];
+    for window in lines.windows(3) {
+        let [a, b, Line::Instr { instr, file_id, span, .. }] = window else {
+            continue;
+        };
+        let (Some(a), Some(b)) = (as_constant(a), as_constant(b)) else {
+            continue;
+        };
+        let msg = match (instr, a, b) {
+            (Instr::DivideInt | Instr::Modulo, _, Constant::Int(0)) => "Division by zero",
+            (Instr::DivideFloat, _, Constant::Float(0.0)) => "Division by zero",
+            (Instr::Modulo, Constant::Int(i64::MIN), Constant::Int(-1)) => "Integer overflow",
+            _ => continue,
+        };
+        errors.push(ConstantError {
+            file_id: *file_id,
+            span: *span,
+            msg,
+        });
+    }
+    errors
+}
+// Longest function body, in instructions, that gets inlined.
+const MAX_INLINE_LEN: usize = 8;
+// How many values `instr` pops and pushes, for instructions that only work on the top of the
+// stack and can't fail.
+fn stack_effect(instr: &Instr) -> Option<(usize, usize)> {
+    Some(match instr {
+        Instr::PushBool(_) | Instr::PushInt(_) | Instr::PushFloat(_) | Instr::PushString(_) => {
+            (0, 1)
+        }
+        Instr::AddInt
+        | Instr::SubtractInt
+        | Instr::MultiplyInt
+        | Instr::PowerInt
+        | Instr::AddFloat
+        | Instr::SubtractFloat
+        | Instr::MultiplyFloat
+        | Instr::PowerFloat
+        | Instr::And
+        | Instr::Or
+        | Instr::LessThanInt
+        | Instr::LessThanOrEqualInt
+        | Instr::GreaterThanInt
+        | Instr::GreaterThanOrEqualInt
+        | Instr::LessThanFloat
+        | Instr::LessThanOrEqualFloat
+        | Instr::GreaterThanFloat
+        | Instr::GreaterThanOrEqualFloat
+        | Instr::EqualInt
+        | Instr::EqualFloat
+        | Instr::EqualBool
+        | Instr::EqualString
+        | Instr::ConcatStrings => (2, 1),
+        Instr::Not
+        | Instr::SquareRoot
+        | Instr::IntToString
+        | Instr::FloatToString
+        | Instr::ArrayLength => (1, 1),
+        Instr::ConstructStruct(n) => (*n as usize, 1),
+        _ => return None,
+    })
+}
+// The instructions that replace a call to the function starting at `body`, if it's a small
+// wrapper: it loads its arguments in order, then only operates on them, e.g. `fn f(a, b) = a < b`.
+// Such a function calls nothing and can't fail, so inlining it never hides a stack frame.
+fn inlined_body(body: &[Line]) -> Option<Vec<Instr>> {
+    let mut instrs = vec![];
+    let mut lines = body.iter();
+    let nargs = loop {
+        match lines.next()? {
             Line::Instr {
-                instr: instr1,
+                instr: Instr::Return(nargs),
+                ..
+            } => break *nargs as usize,
+            Line::Instr { instr, .. } => instrs.push(instr.clone()),
+            Line::Label(_) => {}
+        }
+        // the argument loads plus the body; calls take at most 31 arguments
+        if instrs.len() > 31 + MAX_INLINE_LEN {
+            return None;
+        }
+    };
+    if instrs.len() < nargs {
+        return None;
+    }
+    for (i, instr) in instrs[..nargs].iter().enumerate() {
+        let Instr::LoadOffset(offset) = instr else {
+            return None;
+        };
+        if *offset != i as i32 - nargs as i32 {
+            return None;
+        }
+    }
+    let rest = instrs.split_off(nargs);
+    let mut depth = nargs;
+    for instr in &rest {
+        let (pops, pushes) = stack_effect(instr)?;
+        depth = depth.checked_sub(pops)? + pushes;
+    }
+    (depth == 1 && rest.len() <= MAX_INLINE_LEN).then_some(rest)
+}
+pub(crate) fn inline_small_functions(lines: Vec<Line>) -> Vec<Line> {
+    let mut bodies: HashMap<&Label, Vec<Instr>> = HashMap::default();
+    for (i, line) in lines.iter().enumerate() {
+        if let Line::Label(label) = line
+            && let Some(body) = inlined_body(&lines[i + 1..])
+        {
+            bodies.insert(label, body);
+        }
+    }
+    let mut ret = vec![];
+    for line in &lines {
+        match line {
+            Line::Instr {
+                instr: Instr::Call(_, label),
                 lineno,
                 file_id,
                 func_id,
-            } => {
-                let mut noop = |index: &mut usize| {
-                    ret.push(curr.clone());
-                    *index += 1;
-                };
-                if index + 2 < lines.len()
-                    && let Line::Instr { instr: instr2, .. } = &lines[index + 1]
-                    && let Line::Instr { instr: instr3, .. } = &lines[index + 2]
-                {
-                    match (instr1, instr2, instr3) {
-                        (Instr::PushFloat(a), Instr::PushFloat(b), Instr::DivideFloat) => {
-                            let a = a.parse::<f64>().unwrap();
-                            let b = b.parse::<f64>().unwrap();
-                            let c = a / b;
-                            ret.push(Line::Instr {
-                                instr: Instr::PushFloat(c.to_string()),
-                                lineno,
-                                file_id,
-                                func_id,
-                            });
-                            index += 3;
-                        }
-                        _ => {
-                            noop(&mut index);
-                        }
-                    }
-                } else {
-                    noop(&mut index);
+                span,
+            } if bodies.contains_key(label) => {
+                // the inlined code belongs to the call site in stack traces and the debugger
+                for instr in &bodies[label] {
+                    ret.push(Line::Instr {
+                        instr: instr.clone(),
+                        lineno: *lineno,
+                        file_id: *file_id,
+                        func_id: *func_id,
+                        span: *span,
+                    });
                 }
             }
+            _ => ret.push(line.clone()),
         }
     }
     ret
-}
\ No newline at end of file
+}
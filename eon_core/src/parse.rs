@@ -1,17 +1,42 @@
 use std::rc::Rc;
 use pest::Parser;
+use pest::error::InputLocation;
 use pest::iterators::{Pair, Pairs};
 use pest::pratt_parser::{Assoc, Op, PrattParser};
 use pest_derive::Parser;
 use crate::ast::*;
+use crate::statics::Error;
 #[derive(Parser)]
 #[grammar = "grammar.pest"]
 struct MyParser;
-pub(crate) fn parse_or_err(file_id: FileId, file_data: &FileData) -> Result<Rc<FileAst>, String> {
-    let pairs = get_pairs(&file_data.source)?;
+pub(crate) fn parse_or_err(file_id: FileId, file_data: &FileData) -> Result<Rc<FileAst>, Box<Error>> {
+    let pairs = get_pairs(&file_data.source).map_err(|e| {
+        let range = match e.location {
+            InputLocation::Pos(pos) => pos..pos,
+            InputLocation::Span((lo, hi)) => lo..hi,
+        };
+        Box::new(Error::Parse {
+            file_id,
+            range,
+            msg: e.variant.message().to_string(),
+        })
+    })?;
     let file_ast = parse_file(pairs, file_data, file_id);
     Ok(file_ast)
 }
+pub(crate) fn parse_comments(source: &str) -> Vec<std::ops::Range<usize>> {
+    let Ok(pairs) = MyParser::parse(Rule::comment_scan, source) else {
+        return vec![];
+    };
+    pairs
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::comment)
+        .map(|pair| {
+            let start = pair.as_span().start();
+            start..start + pair.as_str().trim_end().len()
+        })
+        .collect()
+}
 pub(crate) fn parse_file(pairs: Pairs<Rule>, file_data: &FileData, file_id: FileId) -> Rc<FileAst> {
     let mut items = Vec::new();
     let pairs: Vec<_> = pairs.into_iter().collect();
@@ -160,8 +185,8 @@ pub(crate) fn parse_expr_pratt(pairs: Pairs<Rule>, file_id: FileId) -> Rc<Expr>
         })
         .parse(pairs)
 }
-pub(crate) fn get_pairs(source: &str) -> Result<Pairs<'_, Rule>, String> {
-    MyParser::parse(Rule::file, source).map_err(|e| e.to_string())
+pub(crate) fn get_pairs(source: &str) -> Result<Pairs<'_, Rule>, Box<pest::error::Error<Rule>>> {
+    MyParser::parse(Rule::file, source).map_err(Box::new)
 }
 pub(crate) fn parse_func_arg_annotation(pair: Pair<Rule>, file_id: FileId) -> ArgMaybeAnnotated {
     let rule = pair.as_rule();
@@ -533,12 +558,34 @@ pub(crate) fn parse_item(pair: Pair<Rule>, file_id: FileId) -> Rc<Item> {
     let rule = pair.as_rule();
     let inner: Vec<_> = pair.clone().into_inner().collect();
     match rule {
+        Rule::pub_item => {
+            let item = parse_item(inner[0].clone(), file_id);
+            Rc::new(Item {
+                kind: item.kind.clone(),
+                loc: span,
+                id: item.id,
+                public: true,
+            })
+        }
+        Rule::test_func_def => {
+            let func_def = FuncDef {
+                test: true,
+                ..parse_func_def(inner[0].clone().into_inner().collect(), file_id)
+            };
+            Rc::new(Item {
+                kind: Rc::new(ItemKind::FuncDef(func_def.into())),
+                loc: span,
+                id: NodeId::new(),
+                public: false,
+            })
+        }
         Rule::func_def => {
             let func_def = parse_func_def(inner, file_id);
             Rc::new(Item {
                 kind: Rc::new(ItemKind::FuncDef(func_def.into())),
                 loc: span,
                 id: NodeId::new(),
+                public: false,
             })
         }
         Rule::foreign_func_decl => {
@@ -569,6 +616,7 @@ pub(crate) fn parse_item(pair: Pair<Rule>, file_id: FileId) -> Rc<Item> {
                 )),
                 loc: span,
                 id: NodeId::new(),
+                public: false,
             })
         }
         Rule::host_func_decl => {
@@ -599,6 +647,7 @@ pub(crate) fn parse_item(pair: Pair<Rule>, file_id: FileId) -> Rc<Item> {
                 )),
                 loc: span,
                 id: NodeId::new(),
+                public: false,
             })
         }
         Rule::enum_declaration => {
@@ -633,6 +682,7 @@ pub(crate) fn parse_item(pair: Pair<Rule>, file_id: FileId) -> Rc<Item> {
                 )))),
                 loc: span,
                 id: NodeId::new(),
+                public: false,
             })
         }
         Rule::struct_declaration => {
@@ -667,6 +717,7 @@ pub(crate) fn parse_item(pair: Pair<Rule>, file_id: FileId) -> Rc<Item> {
                 )))),
                 loc: span,
                 id,
+                public: false,
             })
         }
         Rule::interface_declaration => {
@@ -763,6 +814,7 @@ pub(crate) fn parse_item(pair: Pair<Rule>, file_id: FileId) -> Rc<Item> {
                 .into(),
                 loc: span,
                 id: NodeId::new(),
+                public: false,
             })
         }
         Rule::interface_implementation => {
@@ -796,6 +848,7 @@ pub(crate) fn parse_item(pair: Pair<Rule>, file_id: FileId) -> Rc<Item> {
                 .into(),
                 loc: span,
                 id: impl_id,
+                public: false,
             })
         }
         Rule::extension => {
@@ -821,6 +874,7 @@ pub(crate) fn parse_item(pair: Pair<Rule>, file_id: FileId) -> Rc<Item> {
                 .into(),
                 loc: span,
                 id: impl_id,
+                public: false,
             })
         }
         Rule::import => {
@@ -833,7 +887,7 @@ pub(crate) fn parse_item(pair: Pair<Rule>, file_id: FileId) -> Rc<Item> {
                 for pair in inner {
                     list.push(Identifier {
                         v: pair.as_str().to_string(),
-                        loc: span.clone(),
+                        loc: Location::new(file_id, pair.as_span()),
                         id: NodeId::new(),
                     });
                 }
@@ -851,7 +905,7 @@ pub(crate) fn parse_item(pair: Pair<Rule>, file_id: FileId) -> Rc<Item> {
                 kind: ItemKind::Import(
                     Identifier {
                         v: name,
-                        loc: span.clone(),
+                        loc: Location::new(file_id, inner[0].as_span()),
                         id: NodeId::new(),
                     }
                     .into(),
@@ -860,6 +914,7 @@ pub(crate) fn parse_item(pair: Pair<Rule>, file_id: FileId) -> Rc<Item> {
                 .into(),
                 loc: span,
                 id: NodeId::new(),
+                public: false,
             })
         }
         Rule::let_statement
@@ -874,6 +929,7 @@ pub(crate) fn parse_item(pair: Pair<Rule>, file_id: FileId) -> Rc<Item> {
                 kind: ItemKind::Stmt(stmt).into(),
                 loc: span,
                 id: NodeId::new(),
+                public: false,
             })
         }
         _ => panic!("unreachable rule {rule:#?}"),
@@ -907,6 +963,7 @@ fn parse_func_def(inner: Vec<Pair<'_, Rule>>, file_id: FileId) -> FuncDef {
         args,
         ret_type,
         body,
+        test: false,
     }
 }
 pub(crate) fn parse_stmt(pair: Pair<Rule>, file_id: FileId) -> Rc<Stmt> {
@@ -1058,7 +1115,7 @@ pub(crate) fn parse_expr_term(pair: Pair<Rule>, file_id: FileId) -> Rc<Expr> {
     let span = Location::new(file_id, pair.as_span());
     let rule = pair.as_rule();
     match rule {
-        Rule::expression => parse_expr_pratt(pair.into_inner(), file_id),
+        Rule::expression | Rule::condition => parse_expr_pratt(pair.into_inner(), file_id),
         Rule::block_expression => {
             let inner = pair.into_inner();
             let mut statements: Vec<Rc<Stmt>> = Vec::new();
@@ -1143,6 +1200,52 @@ pub(crate) fn parse_expr_term(pair: Pair<Rule>, file_id: FileId) -> Rc<Expr> {
                 id: NodeId::new(),
             })
         }
+        Rule::struct_literal => {
+            let mut name = None;
+            let mut base = None;
+            let mut fields = vec![];
+            for p in pair.into_inner() {
+                match p.as_rule() {
+                    Rule::struct_literal_name => {
+                        name = Some(Rc::new(Identifier {
+                            v: p.as_str().to_string(),
+                            loc: Location::new(file_id, p.as_span()),
+                            id: NodeId::new(),
+                        }));
+                    }
+                    Rule::struct_literal_base => {
+                        let inner = p.into_inner().next().unwrap();
+                        base = Some(parse_expr_pratt(Pairs::single(inner), file_id));
+                    }
+                    Rule::struct_literal_field => {
+                        let inner: Vec<_> = p.into_inner().collect();
+                        let field_loc = Location::new(file_id, inner[0].as_span());
+                        let field_name = inner[0].as_str().to_string();
+                        let value = match inner.get(1) {
+                            Some(value) => parse_expr_pratt(Pairs::single(value.clone()), file_id),
+                            None => Rc::new(Expr {
+                                kind: Rc::new(ExprKind::Variable(field_name.clone())),
+                                loc: field_loc.clone(),
+                                id: NodeId::new(),
+                            }),
+                        };
+                        let ident = Identifier {
+                            v: field_name,
+                            loc: field_loc,
+                            id: NodeId::new(),
+                        }
+                        .into();
+                        fields.push((ident, value));
+                    }
+                    _ => panic!("unreachable rule {:#?}", p.as_rule()),
+                }
+            }
+            Rc::new(Expr {
+                kind: Rc::new(ExprKind::StructLiteral(name, base, fields)),
+                loc: span,
+                id: NodeId::new(),
+            })
+        }
         Rule::literal_void => Rc::new(Expr {
             kind: Rc::new(ExprKind::Void),
             loc: span,
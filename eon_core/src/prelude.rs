@@ -20,9 +20,9 @@ implement Num for int {
     fn divide(a, b) = divide_int(a, b)
     fn power(a, b) = power_int(a, b)
     fn less_than(a, b) = less_than_int(a, b)
-    fn less_than_or_equal(a, b) = (a < b) or (a == b)
-    fn greater_than(a, b) = not(a < b) and not(a == b)
-    fn greater_than_or_equal(a, b) = not(a < b)
+    fn less_than_or_equal(a, b) = a <= b
+    fn greater_than(a, b) = a > b
+    fn greater_than_or_equal(a, b) = a >= b
 }
 implement Num for float {
     fn add(a, b) = add_float(a, b)
@@ -31,9 +31,9 @@ implement Num for float {
     fn divide(a, b) = divide_float(a, b)
     fn power(a, b) = power_float(a, b)
     fn less_than(a, b) = less_than_float(a, b)
-    fn less_than_or_equal(a, b) = a < b
-    fn greater_than(a, b) = b < a
-    fn greater_than_or_equal(a, b) = b < a
+    fn less_than_or_equal(a, b) = a <= b
+    fn greater_than(a, b) = a > b
+    fn greater_than_or_equal(a, b) = a >= b
 }
 type option<T> = some(T) | none
 fn unwrap(m: option<T>) -> T {
@@ -42,6 +42,7 @@ fn unwrap(m: option<T>) -> T {
         .none -> panic("cannot unwrap option.none")
     }
 }
+type result<T, E> = ok(T) | err(E)
 interface Equal {
     fn equal: (Self, Self) -> bool
 }
@@ -189,23 +190,32 @@ implement ToString for option<T ToString> {
 }
 implement ToString for array<T ToString> {
     fn str(arr) {
-        "[ " & array_to_string_helper(arr, 0) & " ]"
+        "[ " & array_to_string_helper(arr, 0, "") & " ]"
     }
 }
-fn array_to_string_helper(arr: array<T ToString>, idx: int) {
-    let l = array_length(arr)
-    if idx == l {
-        ""
-    } else if idx == l - 1 {
-        ToString.str(arr[idx])
+fn array_to_string_helper(arr: array<T ToString>, idx: int, acc: string) -> string {
+    if idx == array_length(arr) {
+        acc
+    } else if idx == 0 {
+        array_to_string_helper(arr, 1, ToString.str(arr[0]))
     } else {
-        ToString.str(arr[idx]) & ", " & array_to_string_helper(arr, idx + 1)
+        array_to_string_helper(arr, idx + 1, acc & ", " & ToString.str(arr[idx]))
     }
 }
 fn print(x: T ToString) { print_string(ToString.str(x)) }
 fn println(x: T ToString) {
     print_string(ToString.str(x) & newline)
 }
+fn assert(cond: bool) -> void {
+    if not(cond) {
+        panic("assertion failed")
+    }
+}
+fn assert_eq(left: T Equal ToString, right: T Equal ToString) -> void {
+    if not(left == right) {
+        panic("assertion failed: left == right" & newline & "  left: " & left & newline & " right: " & right)
+    }
+}
 fn format_append(s1: T1 ToString, s2: T2 ToString) {
     let s3 = ToString.str(s1)
     let s4 = ToString.str(s2)
@@ -0,0 +1,268 @@
+use crate::translate_bytecode::BytecodeIndex;
+use crate::vm::{ProgramCounter, Vm};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+// Collected by the VM while profiling is enabled. Functions are identified by their entry point
+// and instructions are attributed to nodes of a call tree, which become the folded stacks.
+#[derive(Debug, Clone)]
+pub(crate) struct Profiler {
+    instructions: Vec<u64>,
+    // (parent node, entry point of the function)
+    nodes: Vec<(u32, BytecodeIndex)>,
+    node_instructions: Vec<u64>,
+    children: HashMap<(u32, BytecodeIndex), u32>,
+    calls: Vec<ActiveCall>,
+    // how many calls of a function are active, so recursion doesn't count inclusive time twice
+    active: HashMap<BytecodeIndex, u32>,
+    functions: HashMap<BytecodeIndex, CallStats>,
+    end: Option<Instant>,
+}
+#[derive(Debug, Clone)]
+struct ActiveCall {
+    entry: BytecodeIndex,
+    node: u32,
+    start: Instant,
+    children_time: Duration,
+}
+#[derive(Debug, Default, Clone, Copy)]
+struct CallStats {
+    calls: u64,
+    inclusive: Duration,
+    exclusive: Duration,
+}
+impl Profiler {
+    pub(crate) fn new(program_len: usize, entry: BytecodeIndex) -> Self {
+        let mut profiler = Self {
+            instructions: vec![0; program_len],
+            nodes: vec![],
+            node_instructions: vec![],
+            children: HashMap::new(),
+            calls: vec![],
+            active: HashMap::new(),
+            functions: HashMap::new(),
+            end: None,
+        };
+        profiler.enter(entry);
+        profiler
+    }
+    #[inline]
+    pub(crate) fn count(&mut self, pc: ProgramCounter) {
+        self.instructions[pc.get()] += 1;
+        if let Some(call) = self.calls.last() {
+            self.node_instructions[call.node as usize] += 1;
+        }
+    }
+    pub(crate) fn enter(&mut self, entry: BytecodeIndex) {
+        let parent = self.calls.last().map_or(u32::MAX, |call| call.node);
+        let node = *self.children.entry((parent, entry)).or_insert_with(|| {
+            self.nodes.push((parent, entry));
+            self.node_instructions.push(0);
+            self.nodes.len() as u32 - 1
+        });
+        *self.active.entry(entry).or_default() += 1;
+        self.functions.entry(entry).or_default().calls += 1;
+        self.calls.push(ActiveCall {
+            entry,
+            node,
+            start: Instant::now(),
+            children_time: Duration::ZERO,
+        });
+    }
+    pub(crate) fn exit(&mut self) {
+        self.exit_at(Instant::now());
+    }
+    pub(crate) fn stop(&mut self) {
+        self.end = Some(Instant::now());
+    }
+    fn exit_at(&mut self, now: Instant) {
+        let Some(call) = self.calls.pop() else {
+            return;
+        };
+        let elapsed = now - call.start;
+        let active = self.active.get_mut(&call.entry).unwrap();
+        *active -= 1;
+        let stats = self.functions.get_mut(&call.entry).unwrap();
+        if *active == 0 {
+            stats.inclusive += elapsed;
+        }
+        stats.exclusive += elapsed.saturating_sub(call.children_time);
+        if let Some(parent) = self.calls.last_mut() {
+            parent.children_time += elapsed;
+        }
+    }
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionProfile {
+    pub name: String,
+    pub file: String,
+    pub calls: u64,
+    // instructions executed in the function itself
+    pub instructions: u64,
+    pub inclusive_time: Duration,
+    pub exclusive_time: Duration,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineProfile {
+    pub file: String,
+    pub line: u32,
+    pub instructions: u64,
+}
+// A snapshot of the data collected by a profiling `Vm`. Functions and lines are sorted by
+// instructions executed, most first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub functions: Vec<FunctionProfile>,
+    pub lines: Vec<LineProfile>,
+    // `caller;callee` stacks with the instructions executed in them
+    pub stacks: Vec<(String, u64)>,
+}
+impl Profile {
+    // the input format of flamegraph.pl and inferno
+    pub fn folded_stacks(&self) -> String {
+        let mut out = String::new();
+        for (stack, count) in &self.stacks {
+            writeln!(out, "{stack} {count}").unwrap();
+        }
+        out
+    }
+    pub fn summary(&self, top: usize) -> String {
+        let total: u64 = self.lines.iter().map(|line| line.instructions).sum();
+        let percent = |n: u64| {
+            if total == 0 {
+                0.0
+            } else {
+                n as f64 * 100.0 / total as f64
+            }
+        };
+        let mut out = String::new();
+        writeln!(out, "{total} instructions executed").unwrap();
+        writeln!(out, "\nhottest functions:").unwrap();
+        writeln!(
+            out,
+            "{:>12} {:>7} {:>10} {:>12} {:>12}  function",
+            "instructions", "%", "calls", "inclusive", "exclusive"
+        )
+        .unwrap();
+        for function in self.functions.iter().take(top) {
+            writeln!(
+                out,
+                "{:>12} {:>6.2}% {:>10} {:>12} {:>12}  {} ({})",
+                function.instructions,
+                percent(function.instructions),
+                function.calls,
+                format!("{:.3?}", function.inclusive_time),
+                format!("{:.3?}", function.exclusive_time),
+                function.name,
+                function.file
+            )
+            .unwrap();
+        }
+        writeln!(out, "\nhottest lines:").unwrap();
+        writeln!(out, "{:>12} {:>7}  line", "instructions", "%").unwrap();
+        for line in self.lines.iter().take(top) {
+            writeln!(
+                out,
+                "{:>12} {:>6.2}%  {}:{}",
+                line.instructions,
+                percent(line.instructions),
+                line.file,
+                line.line
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+impl Vm {
+    // Counts instructions per function and line and times every call from now on.
+    pub fn enable_profiling(&mut self) {
+        self.profiler = Some(Box::new(Profiler::new(self.program.len(), self.pc.0)));
+    }
+    pub fn profile(&self) -> Option<Profile> {
+        let mut profiler = (**self.profiler.as_ref()?).clone();
+        // calls still running (after an error, or when the program hasn't finished) end now
+        let now = profiler.end.unwrap_or_else(Instant::now);
+        while !profiler.calls.is_empty() {
+            profiler.exit_at(now);
+        }
+        let mut by_function: HashMap<(String, String), FunctionProfile> = HashMap::new();
+        for (entry, stats) in &profiler.functions {
+            let location = self.pc_to_error_location(ProgramCounter(*entry + 1));
+            let function = by_function
+                .entry((location.function_name.clone(), location.filename.clone()))
+                .or_insert_with(|| FunctionProfile {
+                    name: location.function_name,
+                    file: location.filename,
+                    calls: 0,
+                    instructions: 0,
+                    inclusive_time: Duration::ZERO,
+                    exclusive_time: Duration::ZERO,
+                });
+            function.calls += stats.calls;
+            function.inclusive_time += stats.inclusive;
+            function.exclusive_time += stats.exclusive;
+        }
+        let mut by_line: HashMap<(String, u32), u64> = HashMap::new();
+        for (pc, count) in profiler.instructions.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+            // error locations are looked up with the pc after the failing instruction
+            let location = self.pc_to_error_location(ProgramCounter::new(pc + 1));
+            *by_line
+                .entry((location.filename.clone(), location.lineno))
+                .or_default() += count;
+            if let Some(function) =
+                by_function.get_mut(&(location.function_name, location.filename))
+            {
+                function.instructions += count;
+            }
+        }
+        let mut functions: Vec<_> = by_function.into_values().collect();
+        functions.sort_by(|a, b| {
+            (b.instructions, &a.name, &a.file).cmp(&(a.instructions, &b.name, &b.file))
+        });
+        let mut lines: Vec<_> = by_line
+            .into_iter()
+            .map(|((file, line), instructions)| LineProfile {
+                file,
+                line,
+                instructions,
+            })
+            .collect();
+        lines.sort_by(|a, b| {
+            (b.instructions, &a.file, a.line).cmp(&(a.instructions, &b.file, b.line))
+        });
+
+        let names: Vec<String> = profiler
+            .nodes
+            .iter()
+            .map(|(_, entry)| {
+                self.pc_to_error_location(ProgramCounter(*entry + 1))
+                    .function_name
+            })
+            .collect();
+        let mut stacks: HashMap<String, u64> = HashMap::new();
+        for (node, count) in profiler.node_instructions.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+            let mut frames = vec![];
+            let mut node = node as u32;
+            while node != u32::MAX {
+                frames.push(names[node as usize].as_str());
+                node = profiler.nodes[node as usize].0;
+            }
+            frames.reverse();
+            *stacks.entry(frames.join(";")).or_default() += count;
+        }
+        let mut stacks: Vec<_> = stacks.into_iter().collect();
+        stacks.sort();
+        Some(Profile {
+            functions,
+            lines,
+            stacks,
+        })
+    }
+}
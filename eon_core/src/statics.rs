@@ -13,6 +13,7 @@ use typecheck::{ConstraintReason, PotentialType, SolvedType, TypeKey, TypeVar, s
 use utils::hash::{HashMap, HashSet};
 use utils::id_set::IdSet;
 mod error;
+pub use error::{Report, ReportLabel};
 mod pat_exhaustiveness;
 mod resolve;
 pub(crate) mod typecheck;
@@ -67,6 +68,9 @@ impl StaticsContext {
             errors: Default::default(),
         }
     }
+    pub(crate) fn errors(&self) -> &[Error] {
+        &self.errors
+    }
     pub(crate) fn solution_of_node(&self, node: AstNode) -> Option<SolvedType> {
         let prov = TypeProv::Node(node);
         match self.unifvars.get(&prov) {
@@ -89,11 +93,20 @@ impl StaticsContext {
 pub(crate) struct Namespace {
     declarations: HashMap<String, Declaration>,
     namespaces: HashMap<String, Rc<Namespace>>,
+    private: HashSet<String>,
 }
 impl Namespace {
     pub fn new() -> Self {
         Self::default()
     }
+    pub(crate) fn namespace(&self, name: &str) -> Option<&Rc<Namespace>> {
+        self.namespaces.get(name)
+    }
+    pub(crate) fn public_declarations(&self) -> impl Iterator<Item = (&String, &Declaration)> {
+        self.declarations
+            .iter()
+            .filter(|(name, _)| !self.private.contains(*name))
+    }
     pub fn get_declaration(&self, path: &str) -> Option<Declaration> {
         let segments: Vec<_> = path.split('.').collect();
         let mut current_namespace: &Namespace = self;
@@ -153,10 +166,12 @@ pub(crate) enum Declaration {
     },
     Struct(Rc<StructDef>),
     Array,
+    Handle,
     Builtin(BuiltinOperation),
     BuiltinType(BuiltinType),
     Var(AstNode),
     Polytype(PolytypeDeclaration),
+    Module(String),
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub(crate) enum PolytypeDeclaration {
@@ -176,11 +191,13 @@ impl Declaration {
             | Declaration::Builtin(_)
             | Declaration::Var(_)
             | Declaration::Polytype(_)
+            | Declaration::Module(_)
             | Declaration::EnumVariant { .. } => None,
             Declaration::InterfaceOutputType { .. } => unimplemented!(),
             Declaration::Enum(enum_def) => Some(TypeKey::TyApp(Nominal::Enum(enum_def))),
             Declaration::Struct(struct_def) => Some(TypeKey::TyApp(Nominal::Struct(struct_def))),
             Declaration::Array => Some(TypeKey::TyApp(Nominal::Array)),
+            Declaration::Handle => Some(TypeKey::TyApp(Nominal::Handle)),
             Declaration::BuiltinType(builtin_type) => Some(builtin_type.to_type_key()),
         }
     }
@@ -227,6 +244,50 @@ pub(crate) enum Error {
     UnqualifiedEnumNeedsAnnotation {
         node: AstNode,
     },
+    NotAStruct {
+        node: AstNode,
+    },
+    Parse {
+        file_id: FileId,
+        range: Range<usize>,
+        msg: String,
+    },
+    // found while folding constants
+    ConstantEvaluation {
+        file_id: FileId,
+        range: Range<usize>,
+        msg: String,
+    },
+    PrivateItem {
+        node: AstNode,
+        module: String,
+    },
+    ModuleNotFound {
+        node: AstNode,
+        path: String,
+    },
+    ImportCycle {
+        node: AstNode,
+        cycle: Vec<String>,
+    },
+    // two imported modules with the same name, like `a/util` and `b/util`
+    ModuleNameClash {
+        node: AstNode,
+        name: String,
+        original: String,
+    },
+    UnknownStructField {
+        node: AstNode,
+        struct_def: Rc<StructDef>,
+    },
+    DuplicateStructField {
+        original: AstNode,
+        duplicate: AstNode,
+    },
+    MissingStructFields {
+        node: AstNode,
+        missing: Vec<String>,
+    },
     InterfaceNotImplemented {
         ty: SolvedType,
         iface: Rc<InterfaceDef>,
@@ -257,13 +318,21 @@ pub(crate) fn analyze(
     files: &FileDatabase,
     file_provider: Box<dyn FileProvider>,
 ) -> Result<StaticsContext, ErrorSummary> {
+    let ctx = analyze_keep_errors(file_asts, files, file_provider);
+    check_errors(&ctx, files)?;
+    Ok(ctx)
+}
+pub(crate) fn analyze_keep_errors(
+    file_asts: &Vec<Rc<FileAst>>,
+    files: &FileDatabase,
+    file_provider: Box<dyn FileProvider>,
+) -> StaticsContext {
     let mut ctx = StaticsContext::new(files.clone(), file_provider);
     scan_declarations(&mut ctx, file_asts);
     resolve(&mut ctx, file_asts);
     solve_types(&mut ctx, file_asts);
     check_pattern_exhaustiveness_and_usefulness(&mut ctx, file_asts);
-    check_errors(&ctx, files)?;
-    Ok(ctx)
+    ctx
 }
 pub(crate) fn check_errors(ctx: &StaticsContext, files: &FileDatabase) -> Result<(), ErrorSummary> {
     if ctx.errors.is_empty() {
@@ -288,4 +357,4 @@ pub(crate) fn _print_node(ctx: &StaticsContext, node: AstNode) {
     let writer = StandardStream::stderr(ColorChoice::Always);
     let config = codespan_reporting::term::Config::default();
     term::emit_to_io_write(&mut writer.lock(), &config, &ctx._files, &diagnostic).unwrap();
-}
\ No newline at end of file
+}
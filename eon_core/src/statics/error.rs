@@ -3,9 +3,12 @@ use super::Error;
 use super::PolytypeDeclaration;
 use crate::ast::{AstNode, FileDatabase, FileId};
 use crate::statics::typecheck::{ConstraintReason, PotentialType, Reason, fmt_conflicting_types};
-use codespan_reporting::diagnostic::{Diagnostic, Label};
+use codespan_reporting::diagnostic::{Diagnostic, Label, LabelStyle, Severity};
+use codespan_reporting::files::Files;
 use codespan_reporting::term;
 use codespan_reporting::term::termcolor::{Buffer, ColorChoice, StandardStream};
+use std::ops::Range;
+use std::path::PathBuf;
 impl Error {
     fn make_diagnostic(&self) -> Diagnostic<FileId> {
         let mut diagnostic = Diagnostic::error();
@@ -178,6 +181,84 @@ impl Error {
                 let (file, range) = node.get_file_and_range();
                 labels.push(Label::secondary(file, range));
             }
+            Error::Parse {
+                file_id,
+                range,
+                msg,
+            } => {
+                diagnostic = diagnostic.with_message(format!("Syntax error: {msg}"));
+                labels.push(Label::secondary(*file_id, range.clone()));
+            }
+            Error::ConstantEvaluation {
+                file_id,
+                range,
+                msg,
+            } => {
+                diagnostic = diagnostic.with_message(msg);
+                labels.push(Label::secondary(*file_id, range.clone()));
+                notes.push("This operation would fail every time it runs".to_string());
+            }
+            Error::PrivateItem { node, module } => {
+                diagnostic = diagnostic.with_message(format!("This item is private to `{module}`"));
+                let (file, range) = node.get_file_and_range();
+                labels.push(Label::secondary(file, range));
+                notes.push("Mark it `pub` to use it from other modules".to_string());
+            }
+            Error::ModuleNotFound { node, path } => {
+                diagnostic = diagnostic.with_message(format!("Could not find module `{path}`"));
+                let (file, range) = node.get_file_and_range();
+                labels.push(Label::secondary(file, range));
+            }
+            Error::ImportCycle { node, cycle } => {
+                diagnostic = diagnostic.with_message("Modules import each other in a cycle");
+                let (file, range) = node.get_file_and_range();
+                labels.push(Label::secondary(file, range));
+                notes.push(format!("cycle: {}", cycle.join(" -> ")));
+            }
+            Error::ModuleNameClash {
+                node,
+                name,
+                original,
+            } => {
+                diagnostic = diagnostic.with_message(format!("`{name}` was declared more than once"));
+                let (file, range) = node.get_file_and_range();
+                labels.push(Label::secondary(file, range));
+                notes.push(format!("`{name}` already refers to the imported module `{original}`"));
+            }
+            Error::NotAStruct { node } => {
+                diagnostic = diagnostic.with_message("Expected a struct");
+                let (file, range) = node.get_file_and_range();
+                labels.push(Label::secondary(file, range));
+            }
+            Error::UnknownStructField { node, struct_def } => {
+                diagnostic = diagnostic.with_message(format!(
+                    "Struct `{}` has no field with this name",
+                    struct_def.name.v
+                ));
+                let (file, range) = node.get_file_and_range();
+                labels.push(Label::secondary(file, range));
+                let (file, range) = struct_def.name.node().get_file_and_range();
+                labels.push(Label::secondary(file, range).with_message("struct defined here"));
+            }
+            Error::DuplicateStructField {
+                original,
+                duplicate,
+            } => {
+                diagnostic = diagnostic.with_message("Field was specified more than once");
+                let (file, range) = original.get_file_and_range();
+                labels.push(Label::secondary(file, range).with_message("first specified here"));
+                let (file, range) = duplicate.get_file_and_range();
+                labels.push(Label::secondary(file, range).with_message("then specified here"));
+            }
+            Error::MissingStructFields { node, missing } => {
+                diagnostic = diagnostic.with_message("Struct is missing fields");
+                let (file, range) = node.get_file_and_range();
+                labels.push(Label::secondary(file, range));
+                notes.push("The following fields are missing:".to_string());
+                for field in missing {
+                    notes.push(format!("\t`{field}`\n"));
+                }
+            }
             Error::InterfaceNotImplemented { ty, iface, node } => {
                 diagnostic = diagnostic.with_message(format!(
                     "Interface `{}` is not implemented for type `{}`",
@@ -243,6 +324,39 @@ impl Error {
         let config = term::Config::default();
         term::emit_to_io_write(&mut writer.lock(), &config, files, &diagnostic).unwrap();
     }
+    pub fn report(&self, files: &FileDatabase) -> Report {
+        let diagnostic = self.make_diagnostic();
+        Report {
+            severity: match diagnostic.severity {
+                Severity::Bug => "bug",
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Note => "note",
+                Severity::Help => "help",
+            },
+            message: diagnostic.message,
+            labels: diagnostic
+                .labels
+                .iter()
+                .map(|label| {
+                    let file = files.get(label.file_id).unwrap();
+                    let start = files.location(label.file_id, label.range.start).unwrap();
+                    let end = files.location(label.file_id, label.range.end).unwrap();
+                    ReportLabel {
+                        primary: label.style == LabelStyle::Primary,
+                        file: file.full_path.clone(),
+                        range: label.range.clone(),
+                        start_line: start.line_number,
+                        start_column: start.column_number,
+                        end_line: end.line_number,
+                        end_column: end.column_number,
+                        message: label.message.clone(),
+                    }
+                })
+                .collect(),
+            notes: diagnostic.notes,
+        }
+    }
     pub fn to_string(&self, files: &FileDatabase, ansi: bool) -> String {
         let diagnostic = self.make_diagnostic();
         let mut buffer = if ansi {
@@ -255,6 +369,24 @@ impl Error {
         String::from_utf8(buffer.into_inner()).unwrap()
     }
 }
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub severity: &'static str,
+    pub message: String,
+    pub labels: Vec<ReportLabel>,
+    pub notes: Vec<String>,
+}
+#[derive(Debug, Clone)]
+pub struct ReportLabel {
+    pub primary: bool,
+    pub file: PathBuf,
+    pub range: Range<usize>,
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub message: String,
+}
 fn handle_reason(
     ty: &PotentialType,
     reason: Reason,
@@ -324,9 +456,10 @@ fn add_detail_for_decl(
             "`{}` is a builtin operation and cannot be re-declared",
             builtin.name()
         )),
-        Declaration::Array | Declaration::BuiltinType(_) => {
+        Declaration::Array | Declaration::Handle | Declaration::BuiltinType(_) => {
             notes.push("cannot redeclare a builtin type".to_string())
         }
+        Declaration::Module(path) => notes.push(format!("`{path}` is an imported module")),
     };
 }
 fn add_detail_for_decl_node(
@@ -355,9 +488,13 @@ fn add_detail_for_decl_node(
         },
         Declaration::Var(ast_node) => ast_node.clone(),
         Declaration::InterfaceOutputType { .. } => unimplemented!(),
-        Declaration::Builtin(_) | Declaration::BuiltinType(_) | Declaration::Array => return false,
+        Declaration::Builtin(_)
+        | Declaration::BuiltinType(_)
+        | Declaration::Array
+        | Declaration::Handle
+        | Declaration::Module(_) => return false,
     };
     let (file, range) = node.get_file_and_range();
     labels.push(Label::secondary(file, range).with_message(message));
     true
-}
\ No newline at end of file
+}
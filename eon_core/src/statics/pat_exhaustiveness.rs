@@ -134,6 +134,14 @@ fn check_pattern_exhaustiveness_expr(statics: &mut StaticsContext, expr: &Rc<Exp
                 check_pattern_exhaustiveness_expr(statics, expr);
             }
         }
+        ExprKind::StructLiteral(_, base, fields) => {
+            if let Some(base) = base {
+                check_pattern_exhaustiveness_expr(statics, base);
+            }
+            for (_, expr) in fields {
+                check_pattern_exhaustiveness_expr(statics, expr);
+            }
+        }
         ExprKind::MemberAccess(expr, _) => {
             check_pattern_exhaustiveness_expr(statics, expr);
         }
@@ -10,7 +10,7 @@ use crate::builtin::{BuiltinOperation, BuiltinType};
 use crate::statics::typecheck::{Nominal, TypeKey};
 use std::cell::RefCell;
 use std::rc::Rc;
-use utils::hash::HashMap;
+use utils::hash::{HashMap, HashSet};
 pub(crate) fn scan_declarations(ctx: &mut StaticsContext, file_asts: &Vec<Rc<FileAst>>) {
     for file in file_asts {
         let name = file.name.clone();
@@ -23,9 +23,29 @@ fn gather_declarations_file(ctx: &mut StaticsContext, file: &Rc<FileAst>) -> Nam
     let qualifiers = vec![file.name.clone()];
     for item in file.items.iter() {
         gather_declarations_item(ctx, &mut namespace, qualifiers.clone(), file, item);
+        if !item.public
+            && let Some(name) = declared_name(item)
+        {
+            namespace.private.insert(name);
+        }
     }
     namespace
 }
+fn declared_name(item: &Rc<Item>) -> Option<String> {
+    match &*item.kind {
+        ItemKind::FuncDef(f) => Some(f.name.v.clone()),
+        ItemKind::HostFuncDecl(f) | ItemKind::ForeignFuncDecl(f) => Some(f.name.v.clone()),
+        ItemKind::InterfaceDef(iface) => Some(iface.name.v.clone()),
+        ItemKind::TypeDef(tydef) => match &**tydef {
+            TypeDefKind::Enum(e) => Some(e.name.v.clone()),
+            TypeDefKind::Struct(s) => Some(s.name.v.clone()),
+        },
+        ItemKind::InterfaceImpl(..)
+        | ItemKind::Extension(..)
+        | ItemKind::Import(..)
+        | ItemKind::Stmt(..) => None,
+    }
+}
 fn fullname(qualifiers: &[String], unqualified_name: &str) -> String {
     let mut fullname = String::new();
     for qualifier in qualifiers {
@@ -45,7 +65,7 @@ fn gather_declarations_item(
     match &*stmt.kind {
         ItemKind::Stmt(..) => {}
         ItemKind::InterfaceDef(iface) => {
-            namespace.add_declaration(
+            let added = namespace.add_declaration(
                 ctx,
                 iface.name.v.clone(),
                 Declaration::InterfaceDef(iface.clone()),
@@ -79,7 +99,9 @@ fn gather_declarations_item(
                 );
             }
             let iface_namespace = Rc::new(iface_namespace);
-            namespace.add_namespace(iface.name.v.clone(), iface_namespace.clone());
+            if added {
+                namespace.add_namespace(iface.name.v.clone(), iface_namespace.clone());
+            }
             ctx.interface_namespaces
                 .insert(iface.clone(), iface_namespace);
         }
@@ -88,7 +110,8 @@ fn gather_declarations_item(
         }
         ItemKind::TypeDef(typdefkind) => match &**typdefkind {
             TypeDefKind::Enum(e) => {
-                namespace.add_declaration(ctx, e.name.v.clone(), Declaration::Enum(e.clone()));
+                let added =
+                    namespace.add_declaration(ctx, e.name.v.clone(), Declaration::Enum(e.clone()));
                 let mut enum_namespace = Namespace::new();
                 for (i, v) in e.variants.iter().enumerate() {
                     let variant_name = v.ctor.v.clone();
@@ -102,7 +125,9 @@ fn gather_declarations_item(
                         },
                     );
                 }
-                namespace.add_namespace(e.name.v.clone(), enum_namespace.into());
+                if added {
+                    namespace.add_namespace(e.name.v.clone(), enum_namespace.into());
+                }
                 let fully_qualified_name = fullname(&qualifiers, &e.name.v);
                 ctx.fully_qualified_names
                     .insert(e.name.id, fully_qualified_name);
@@ -282,6 +307,9 @@ fn resolve_imports_file(ctx: &mut StaticsContext, file: &Rc<FileAst>) -> SymbolT
     effective_namespace
         .declarations
         .insert("array".to_string(), Declaration::Array);
+    effective_namespace
+        .declarations
+        .insert("handle".to_string(), Declaration::Handle);
     for builtin in BuiltinOperation::enumerate().iter() {
         effective_namespace
             .declarations
@@ -306,17 +334,54 @@ fn resolve_imports_file(ctx: &mut StaticsContext, file: &Rc<FileAst>) -> SymbolT
                     .push(Error::UnresolvedIdentifier { node: item.node() });
                 continue;
             };
+            let public_ns = Rc::new(import_src.public_view());
             let import_list = import_list.clone();
             let pred: Box<dyn Fn(&String) -> bool> = match import_list {
                 None => Box::new(|_s: &String| true),
                 Some(ImportList::Inclusion(list)) => {
+                    for ident in &list {
+                        if import_src.private.contains(&ident.v) {
+                            ctx.errors.push(Error::PrivateItem {
+                                node: Rc::new(ident.clone()).node(),
+                                module: path.v.clone(),
+                            });
+                        }
+                    }
                     Box::new(move |s: &String| list.iter().any(|ident| ident.v == *s))
                 }
                 Some(ImportList::Exclusion(list)) => {
                     Box::new(move |s: &String| !list.iter().any(|ident| ident.v == *s))
                 }
             };
-            effective_namespace.add_other_pred(ctx, &import_src, pred);
+            // importing an item of a module that was already imported isn't a clash
+            let already_imported: HashSet<String> = public_ns
+                .declarations
+                .iter()
+                .filter(|(name, decl)| effective_namespace.declarations.get(*name) == Some(decl))
+                .map(|(name, _)| name.clone())
+                .collect();
+            let pred = Box::new(move |s: &String| pred(s) && !already_imported.contains(s));
+            effective_namespace.add_other_pred(ctx, &public_ns, pred);
+            let module_name = path.v.rsplit('/').next().unwrap().to_string();
+            match effective_namespace.declarations.get(&module_name) {
+                Some(Declaration::Module(original)) if *original == path.v => {}
+                Some(Declaration::Module(original)) => {
+                    ctx.errors.push(Error::ModuleNameClash {
+                        node: path.node(),
+                        name: module_name,
+                        original: original.clone(),
+                    });
+                }
+                _ => {
+                    if effective_namespace.add_declaration(
+                        ctx,
+                        module_name.clone(),
+                        Declaration::Module(path.v.clone()),
+                    ) {
+                        effective_namespace.add_namespace(module_name, public_ns);
+                    }
+                }
+            }
         }
     }
     SymbolTable::from_namespace(effective_namespace)
@@ -332,17 +397,35 @@ impl Namespace {
         pred: Box<dyn Fn(&String) -> bool>,
     ) {
         for (name, decl) in other.declarations.iter() {
-            if pred(name) {
-                self.add_declaration(ctx, name.clone(), decl.clone());
+            if pred(name)
+                && self.add_declaration(ctx, name.clone(), decl.clone())
+                && let Some(namespace) = other.namespaces.get(name)
+            {
+                self.add_namespace(name.clone(), namespace.clone());
             }
         }
-        for (name, namespace) in other.namespaces.iter() {
-            if pred(name) {
-                self.add_namespace(name.clone(), namespace.clone());
+    }
+    fn public_view(&self) -> Self {
+        let mut ret = Namespace::new();
+        for (name, decl) in self.declarations.iter() {
+            if !self.private.contains(name) {
+                ret.declarations.insert(name.clone(), decl.clone());
+            }
+        }
+        for (name, namespace) in self.namespaces.iter() {
+            if !self.private.contains(name) {
+                ret.namespaces.insert(name.clone(), namespace.clone());
             }
         }
+        ret
     }
-    pub fn add_declaration(&mut self, ctx: &mut StaticsContext, name: String, decl: Declaration) {
+    // returns false and reports a name clash if `name` is already declared
+    pub fn add_declaration(
+        &mut self,
+        ctx: &mut StaticsContext,
+        name: String,
+        decl: Declaration,
+    ) -> bool {
         use std::collections::hash_map::*;
         match self.declarations.entry(name.clone()) {
             Entry::Occupied(occ) => {
@@ -351,12 +434,16 @@ impl Namespace {
                     original: occ.get().clone(),
                     new: decl,
                 });
+                false
             }
             Entry::Vacant(vac) => {
                 vac.insert(decl);
+                true
             }
         }
     }
+    // a namespace always belongs to the declaration of the same name, so callers only add it once
+    // `add_declaration` succeeded
     pub fn add_namespace(&mut self, name: String, namespace: Rc<Namespace>) {
         use std::collections::hash_map::*;
         match self.namespaces.entry(name.clone()) {
@@ -649,9 +736,25 @@ fn resolve_names_expr(ctx: &mut StaticsContext, symbol_table: &SymbolTable, expr
                 resolve_names_expr(ctx, symbol_table, arg);
             }
         }
-        ExprKind::MemberAccess(expr, field) => {
-            resolve_names_expr(ctx, symbol_table, expr);
-            resolve_names_member_helper(ctx, expr, field);
+        ExprKind::StructLiteral(name, base, fields) => {
+            if let Some(name) = name {
+                resolve_identifier(ctx, symbol_table, name);
+            }
+            if let Some(base) = base {
+                resolve_names_expr(ctx, symbol_table, base);
+            }
+            for (_, expr) in fields {
+                resolve_names_expr(ctx, symbol_table, expr);
+            }
+        }
+        ExprKind::MemberAccess(accessed, field) => {
+            resolve_names_expr(ctx, symbol_table, accessed);
+            resolve_names_member_helper(ctx, accessed, field);
+            if let Some(Declaration::Module(_)) = ctx.resolution_map.get(&accessed.id)
+                && let Some(decl) = ctx.resolution_map.get(&field.id).cloned()
+            {
+                ctx.resolution_map.insert(expr.id, decl);
+            }
         }
         ExprKind::MemberAccessLeadingDot(_ident) => {
         }
@@ -679,9 +782,24 @@ fn resolve_names_member_helper(ctx: &mut StaticsContext, expr: &Rc<Expr>, field:
                 ctx.errors
                     .push(Error::UnresolvedIdentifier { node: field.node() });
             }
-            Declaration::BuiltinType(_) | Declaration::Array => {
+            Declaration::BuiltinType(_) | Declaration::Array | Declaration::Handle => {
                 todo!()
             }
+            Declaration::Module(path) => {
+                let module = ctx.root_namespace.namespaces[&path].clone();
+                if let Some(decl) = module.declarations.get(&field.v) {
+                    if module.private.contains(&field.v) {
+                        ctx.errors.push(Error::PrivateItem {
+                            node: field.node(),
+                            module: path,
+                        });
+                    }
+                    ctx.resolution_map.insert(field.id, decl.clone());
+                } else {
+                    ctx.errors
+                        .push(Error::UnresolvedIdentifier { node: field.node() });
+                }
+            }
             Declaration::InterfaceDef(iface_def) => {
                 let mut found = false;
                 for (idx, method) in iface_def.methods.iter().enumerate() {
@@ -794,7 +912,7 @@ fn resolve_names_pat(ctx: &mut StaticsContext, symbol_table: &SymbolTable, pat:
                     ctx.errors
                         .push(Error::UnresolvedIdentifier { node: tag.node() });
                 }
-            } 
+            }
             if let Some(data) = data {
                 resolve_names_pat(ctx, symbol_table, data)
             };
@@ -920,9 +1038,11 @@ fn fqn_of_type(ctx: &StaticsContext, lookup_id: NodeId) -> Option<String> {
         Declaration::Struct(s) => ctx.fully_qualified_names.get(&s.name.id).cloned(),
         Declaration::EnumVariant { .. } => None,
         Declaration::Array => Some("array".into()),
+        Declaration::Handle => Some("handle".into()),
         Declaration::BuiltinType(builtin_type) => Some(builtin_type.name().to_string()),
         Declaration::Polytype(_) => None,
         Declaration::Builtin(_) => None,
         Declaration::Var(_) => None,
+        Declaration::Module(_) => None,
     }
-}
\ No newline at end of file
+}
@@ -208,6 +208,8 @@ pub(crate) enum Nominal {
     Struct(Rc<StructDef>),
     Enum(Rc<EnumDef>),
     Array,
+    // an opaque pointer owned by a native module
+    Handle,
 }
 impl Nominal {
     pub(crate) fn name(&self) -> &str {
@@ -215,10 +217,26 @@ impl Nominal {
             Self::Struct(struct_def) => &struct_def.name.v,
             Self::Enum(enum_def) => &enum_def.name.v,
             Self::Array => "array",
+            Self::Handle => "handle",
         }
     }
 }
 impl SolvedType {
+    pub(crate) fn key(&self) -> TypeKey {
+        match self {
+            Self::Poly(decl) => TypeKey::Poly(decl.clone()),
+            Self::InterfaceOutput(output_type) => TypeKey::InterfaceOutput(output_type.clone()),
+            Self::Void => TypeKey::Void,
+            Self::Never => TypeKey::Never,
+            Self::Int => TypeKey::Int,
+            Self::Float => TypeKey::Float,
+            Self::Bool => TypeKey::Bool,
+            Self::String => TypeKey::String,
+            Self::Function(args, _) => TypeKey::Function(args.len() as u8),
+            Self::Tuple(elems) => TypeKey::Tuple(elems.len() as u8),
+            Self::Nominal(nominal, _) => TypeKey::TyApp(nominal.clone()),
+        }
+    }
     pub(crate) fn monotype(&self) -> Option<Monotype> {
         match self {
             Self::Poly(..) => None,
@@ -822,7 +840,7 @@ impl TypeVar {
         match &nominal {
             Nominal::Struct(struct_def) => helper(&struct_def.ty_args),
             Nominal::Enum(enum_def) => helper(&enum_def.ty_args),
-            Nominal::Array => {
+            Nominal::Array | Nominal::Handle => {
                 params.push(TypeVar::fresh(
                     ctx,
                     Prov::InstantiateUdtParam(node.clone(), 0),
@@ -920,6 +938,7 @@ impl AstType {
                 let lookup = ctx.resolution_map.get(&identifier.id)?;
                 match lookup {
                     Declaration::Array => Some(SolvedType::Nominal(Nominal::Array, sargs)),
+                    Declaration::Handle => Some(SolvedType::Nominal(Nominal::Handle, sargs)),
                     Declaration::Struct(struct_def) => Some(SolvedType::Nominal(
                         Nominal::Struct(struct_def.clone()),
                         sargs,
@@ -988,6 +1007,11 @@ impl AstType {
                         Nominal::Array,
                         params.iter().map(|param| param.to_typevar(ctx)).collect(),
                     ),
+                    Some(Declaration::Handle) => TypeVar::make_nominal(
+                        reason,
+                        Nominal::Handle,
+                        params.iter().map(|param| param.to_typevar(ctx)).collect(),
+                    ),
                     Some(Declaration::Polytype(poly_decl)) => {
                         TypeVar::make_poly(reason, poly_decl.clone())
                     }
@@ -1526,6 +1550,12 @@ fn generate_constraints_item_stmts(ctx: &mut StaticsContext, mode: Mode, item: &
         }
         ItemKind::TypeDef(_) => {}
         ItemKind::FuncDef(f) => {
+            if f.test && !f.args.is_empty() {
+                ctx.errors.push(Error::Generic {
+                    msg: "Test functions can't take arguments.".to_string(),
+                    node: f.name.node(),
+                });
+            }
             generate_constraints_func_def(ctx, &PolyvarScope::empty(), f, f.name.node());
         }
         ItemKind::HostFuncDecl(_) | ItemKind::ForeignFuncDecl(_) => {}
@@ -1762,56 +1792,8 @@ fn generate_constraints_expr(
         ExprKind::Variable(_) => {
             let lookup = ctx.resolution_map.get(&expr.id).cloned();
             if let Some(decl) = lookup
-                && let Some(typ) = match decl {
-                    Declaration::Var(node) => {
-                        let tyvar = TypeVar::from_node(ctx, node.clone());
-                        Some(tyvar)
-                    }
-                    Declaration::FreeFunction(f) => Some(TypeVar::from_node(ctx, f.name.node())),
-                    Declaration::HostFunction(f) => Some(TypeVar::from_node(ctx, f.name.node())),
-                    Declaration::_ForeignFunction { f: decl, .. } => {
-                        Some(TypeVar::from_node(ctx, decl.name.node()))
-                    }
-                    Declaration::Builtin(builtin) => {
-                        let ty_signature = builtin.type_signature();
-                        Some(ty_signature)
-                    }
-                    Declaration::Struct(struct_def) => {
-                        let (def_type, substitution) = TypeVar::make_nominal_with_substitution(
-                            ctx,
-                            Reason::Node(expr.node()),
-                            Nominal::Struct(struct_def.clone()),
-                            expr.node(),
-                        );
-                        let fields = struct_def
-                            .fields
-                            .iter()
-                            .map(|f| {
-                                let ty = f.ty.to_typevar(ctx);
-                                ty.subst(&substitution)
-                            })
-                            .collect();
-                        Some(TypeVar::make_func(
-                            fields,
-                            def_type,
-                            Reason::Node(expr.node()),
-                        ))
-                    }
-                    Declaration::InterfaceDef(..)
-                    | Declaration::InterfaceOutputType { .. }
-                    | Declaration::Enum(_)
-                    | Declaration::Array
-                    | Declaration::BuiltinType(_)
-                    | Declaration::Polytype(_)
-                    | Declaration::EnumVariant { .. }
-                    | Declaration::InterfaceMethod { .. }
-                    | Declaration::MemberFunction { .. } => {
-                        ctx.errors
-                            .push(Error::UnresolvedIdentifier { node: expr.node() });
-                        None
-                    }
-                }
-                .map(|tyvar| tyvar.instantiate(ctx, polyvar_scope, expr.node()))
+                && let Some(typ) = tyvar_of_declaration(ctx, &decl, expr.node())
+                    .map(|tyvar| tyvar.instantiate(ctx, polyvar_scope, expr.node()))
             {
                 constrain(ctx, &typ, &node_ty);
             }
@@ -2100,6 +2082,26 @@ fn generate_constraints_expr(
                             node_ty.clone(),
                         );
                     }
+                    Some(decl)
+                        if matches!(
+                            ctx.resolution_map.get(&receiver_expr.id),
+                            Some(Declaration::Module(_))
+                        ) =>
+                    {
+                        let fn_node_ty = TypeVar::from_node(ctx, fname.node());
+                        if let Some(decl_ty) = tyvar_of_declaration(ctx, &decl, fname.node()) {
+                            let decl_ty = decl_ty.instantiate(ctx, polyvar_scope, fname.node());
+                            constrain(ctx, &fn_node_ty, &decl_ty);
+                        }
+                        generate_constraints_expr_funcap_helper(
+                            ctx,
+                            polyvar_scope,
+                            args,
+                            fname.node(),
+                            expr.node(),
+                            node_ty.clone(),
+                        );
+                    }
                     Some(Declaration::MemberFunction { f: func }) if receiver_is_namespace => {
                         let fn_node_ty = TypeVar::from_node(ctx, fname.node());
                         let memfn_ty = TypeVar::from_node(ctx, func.name.node()).instantiate(
@@ -2202,8 +2204,107 @@ fn generate_constraints_expr(
                 node_ty.set_flag_missing_info();
             }
         }
+        ExprKind::StructLiteral(name, base, fields) => {
+            let struct_def = match (name, base) {
+                (Some(name), _) => match ctx.resolution_map.get(&name.id).cloned() {
+                    Some(Declaration::Struct(struct_def)) => Some(struct_def),
+                    Some(_) => {
+                        ctx.errors.push(Error::NotAStruct { node: name.node() });
+                        None
+                    }
+                    None => None,
+                },
+                (None, Some(base)) => {
+                    generate_constraints_expr(ctx, polyvar_scope, Mode::Syn, base);
+                    let ty_base = TypeVar::from_node(ctx, base.node());
+                    if ty_base.underdetermined() {
+                        ctx.errors
+                            .push(Error::MemberAccessNeedsAnnotation { node: base.node() });
+                        None
+                    } else if let Some(PotentialType::Nominal(_, Nominal::Struct(struct_def), _)) =
+                        ty_base.single()
+                    {
+                        Some(struct_def)
+                    } else {
+                        ctx.errors.push(Error::NotAStruct { node: base.node() });
+                        None
+                    }
+                }
+                (None, None) => unreachable!(),
+            };
+            let Some(struct_def) = struct_def else {
+                if let (Some(_), Some(base)) = (name, base) {
+                    generate_constraints_expr(ctx, polyvar_scope, Mode::Syn, base);
+                }
+                for (_, value) in fields {
+                    generate_constraints_expr(ctx, polyvar_scope, Mode::Syn, value);
+                }
+                node_ty.set_flag_missing_info();
+                return;
+            };
+            let (def_type, substitution) = TypeVar::make_nominal_with_substitution(
+                ctx,
+                Reason::Node(expr.node()),
+                Nominal::Struct(struct_def.clone()),
+                expr.node(),
+            );
+            constrain(ctx, &node_ty, &def_type);
+            if let Some(base) = base {
+                if name.is_some() {
+                    generate_constraints_expr(ctx, polyvar_scope, Mode::ana(&def_type), base);
+                } else {
+                    let ty_base = TypeVar::from_node(ctx, base.node());
+                    constrain(ctx, &ty_base, &def_type);
+                }
+            }
+            let mut specified: HashMap<String, Rc<Identifier>> = HashMap::default();
+            for (field_name, value) in fields {
+                if let Some(original) = specified.get(&field_name.v) {
+                    ctx.errors.push(Error::DuplicateStructField {
+                        original: original.node(),
+                        duplicate: field_name.node(),
+                    });
+                } else {
+                    specified.insert(field_name.v.clone(), field_name.clone());
+                }
+                match struct_def.fields.iter().find(|f| f.name.v == field_name.v) {
+                    Some(field) => {
+                        let ty_field = field.ty.to_typevar(ctx).subst(&substitution);
+                        generate_constraints_expr(ctx, polyvar_scope, Mode::ana(ty_field), value);
+                    }
+                    None => {
+                        ctx.errors.push(Error::UnknownStructField {
+                            node: field_name.node(),
+                            struct_def: struct_def.clone(),
+                        });
+                        generate_constraints_expr(ctx, polyvar_scope, Mode::Syn, value);
+                    }
+                }
+            }
+            if base.is_none() {
+                let missing: Vec<String> = struct_def
+                    .fields
+                    .iter()
+                    .filter(|f| !specified.contains_key(&f.name.v))
+                    .map(|f| f.name.v.clone())
+                    .collect();
+                if !missing.is_empty() {
+                    ctx.errors.push(Error::MissingStructFields {
+                        node: expr.node(),
+                        missing,
+                    });
+                }
+            }
+        }
         ExprKind::MemberAccess(expr, member_ident) => {
-            if let Some(Declaration::EnumVariant {
+            if let Some(Declaration::Module(_)) = ctx.resolution_map.get(&expr.id) {
+                if let Some(decl) = ctx.resolution_map.get(&member_ident.id).cloned()
+                    && let Some(typ) = tyvar_of_declaration(ctx, &decl, member_ident.node())
+                {
+                    let typ = typ.instantiate(ctx, polyvar_scope, member_ident.node());
+                    constrain(ctx, &node_ty, &typ);
+                }
+            } else if let Some(Declaration::EnumVariant {
                 e: enum_def,
                 variant: _,
             }) = ctx.resolution_map.get(&member_ident.id).cloned()
@@ -2442,6 +2543,58 @@ fn generate_constraints_func_args(
         })
         .collect()
 }
+fn tyvar_of_declaration(
+    ctx: &mut StaticsContext,
+    decl: &Declaration,
+    node: AstNode,
+) -> Option<TypeVar> {
+    match decl {
+        Declaration::Var(node) => {
+            let tyvar = TypeVar::from_node(ctx, node.clone());
+            Some(tyvar)
+        }
+        Declaration::FreeFunction(f) => Some(TypeVar::from_node(ctx, f.name.node())),
+        Declaration::HostFunction(f) => Some(TypeVar::from_node(ctx, f.name.node())),
+        Declaration::_ForeignFunction { f: decl, .. } => {
+            Some(TypeVar::from_node(ctx, decl.name.node()))
+        }
+        Declaration::Builtin(builtin) => {
+            let ty_signature = builtin.type_signature();
+            Some(ty_signature)
+        }
+        Declaration::Struct(struct_def) => {
+            let (def_type, substitution) = TypeVar::make_nominal_with_substitution(
+                ctx,
+                Reason::Node(node.clone()),
+                Nominal::Struct(struct_def.clone()),
+                node.clone(),
+            );
+            let fields = struct_def
+                .fields
+                .iter()
+                .map(|f| {
+                    let ty = f.ty.to_typevar(ctx);
+                    ty.subst(&substitution)
+                })
+                .collect();
+            Some(TypeVar::make_func(fields, def_type, Reason::Node(node)))
+        }
+        Declaration::InterfaceDef(..)
+        | Declaration::InterfaceOutputType { .. }
+        | Declaration::Enum(_)
+        | Declaration::Array
+        | Declaration::Handle
+        | Declaration::BuiltinType(_)
+        | Declaration::Polytype(_)
+        | Declaration::EnumVariant { .. }
+        | Declaration::InterfaceMethod { .. }
+        | Declaration::MemberFunction { .. }
+        | Declaration::Module(_) => {
+            ctx.errors.push(Error::UnresolvedIdentifier { node });
+            None
+        }
+    }
+}
 fn generate_constraints_expr_funcap_helper(
     ctx: &mut StaticsContext,
     polyvar_scope: &PolyvarScope,
@@ -2824,7 +2977,6 @@ impl Display for SolvedType {
             SolvedType::Poly(polyty) => {
                 match polyty {
                     PolytypeDeclaration::Ordinary(polyty) => {
-                        write!(f, "{}", polyty.name.id)?;
                         write!(f, "{}", polyty.name.v)?;
                         if !polyty.interfaces.is_empty() {
                             write!(f, " ")?;
@@ -2935,4 +3087,4 @@ impl Display for Monotype {
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -2,18 +2,21 @@ use crate::assembly::{Instr, Label, Line, LineVariant, remove_labels};
 use crate::ast::{AstNode, BinaryOperator, FuncDef, InterfaceDef, ItemKind};
 use crate::ast::{FileAst, FileDatabase, NodeId};
 use crate::builtin::BuiltinOperation;
+use crate::debugger::{DebugInfo, DebugType, LocalVar};
 use crate::environment::Environment;
 use crate::optimize_bytecode::optimize;
-use crate::statics::Type;
-use crate::statics::typecheck::Nominal;
+use crate::statics::{Error, Type};
+use crate::statics::typecheck::{Monotype, Nominal};
 use crate::statics::typecheck::SolvedType;
 use crate::statics::{Declaration, PolytypeDeclaration, TypeProv};
 use crate::vm::{EonInt, Instr as VmInstr};
 use crate::{
+    ErrorSummary,
     ast::{Expr, ExprKind, Pat, PatKind, Stmt, StmtKind},
     statics::StaticsContext,
 };
 use std::mem;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use utils::hash::HashMap;
@@ -52,6 +55,12 @@ pub(crate) struct TranslatorState {
     pub(crate) curr_file: u32,
     pub(crate) curr_func: u32,
     pub(crate) curr_lineno: usize,
+    pub(crate) curr_span: (u32, u32),
+    debug_info: DebugInfo,
+    debug_types: HashMap<Type, u32>,
+    scope_labels: Vec<(Label, u32)>,
+    local_labels: Vec<(Label, LocalVar)>,
+    curr_scope: u32,
 }
 #[derive(Debug, Default)]
 struct EnclosingLoop {
@@ -84,6 +93,56 @@ fn gather_constants(lines: &Vec<Line>) -> ConstantsHolder {
     }
     constants
 }
+// Turns calls whose result is returned right away into tail calls: after the call there are only
+// labels and jumps before a `Return`.
+fn mark_tail_calls(mut lines: Vec<Line>) -> Vec<Line> {
+    let mut label_idx: HashMap<&Label, usize> = HashMap::default();
+    for (i, line) in lines.iter().enumerate() {
+        if let Line::Label(label) = line {
+            label_idx.insert(label, i);
+        }
+    }
+    let in_tail_position = |mut i: usize| {
+        // jumps can't loop without passing an instruction, so this only guards against bugs
+        for _ in 0..lines.len() {
+            match lines.get(i) {
+                Some(Line::Label(_)) => i += 1,
+                Some(Line::Instr {
+                    instr: Instr::Jump(label),
+                    ..
+                }) => i = label_idx[label],
+                Some(Line::Instr {
+                    instr: Instr::Return(_),
+                    ..
+                }) => return true,
+                _ => return false,
+            }
+        }
+        false
+    };
+    let tail_calls: Vec<usize> = (0..lines.len())
+        .filter(|i| {
+            matches!(
+                lines[*i],
+                Line::Instr {
+                    instr: Instr::Call(..),
+                    ..
+                }
+            ) && in_tail_position(i + 1)
+        })
+        .collect();
+    for i in tail_calls {
+        if let Line::Instr {
+            instr: instr @ Instr::Call(..),
+            ..
+        } = &mut lines[i]
+            && let Instr::Call(nargs, label) = instr.clone()
+        {
+            *instr = Instr::TailCall(nargs, label);
+        }
+    }
+    lines
+}
 #[derive(Debug, Clone)]
 pub struct CompiledProgram {
     pub(crate) instructions: Vec<VmInstr>,
@@ -95,6 +154,52 @@ pub struct CompiledProgram {
     pub(crate) filename_table: Vec<(BytecodeIndex, u32)>,
     pub(crate) lineno_table: Vec<(BytecodeIndex, u32)>,
     pub(crate) function_name_table: Vec<(BytecodeIndex, u32)>,
+    pub(crate) tests: Vec<TestFunction>,
+    pub(crate) functions: Vec<ExportedFunction>,
+    // indexed by the operand of `HostFunc`
+    pub(crate) host_functions: Vec<HostFunctionSignature>,
+    // where a function called by the embedder returns to
+    pub(crate) call_return: BytecodeIndex,
+    // loads the shared libraries for a function called by the embedder before <main> has run
+    pub(crate) load_libs: BytecodeIndex,
+    pub(crate) debug_info: DebugInfo,
+}
+impl CompiledProgram {
+    pub fn tests(&self) -> &[TestFunction] {
+        &self.tests
+    }
+    // the functions of the main file that can be called with `Vm::call`
+    pub fn functions(&self) -> &[ExportedFunction] {
+        &self.functions
+    }
+    pub fn function(&self, name: &str) -> Option<&ExportedFunction> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+    pub fn host_functions(&self) -> &[HostFunctionSignature] {
+        &self.host_functions
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostFunctionSignature {
+    pub name: String,
+    pub args: Vec<String>,
+    pub ret: String,
+}
+// A top-level function of the main file whose type has no type parameters.
+#[derive(Debug, Clone)]
+pub struct ExportedFunction {
+    pub name: String,
+    // the types of the arguments and the result as written in Eon, e.g. `array<int>`
+    pub args: Vec<String>,
+    pub ret: String,
+    pub(crate) entry: BytecodeIndex,
+}
+#[derive(Debug, Clone)]
+pub struct TestFunction {
+    pub name: String,
+    pub file: PathBuf,
+    pub line: usize,
+    pub(crate) entry: BytecodeIndex,
 }
 pub type BytecodeIndex = u32;
 impl Translator {
@@ -124,6 +229,7 @@ impl Translator {
                 lineno,
                 file_id,
                 func_id,
+                ..
             } = line
             {
                 {
@@ -172,24 +278,40 @@ impl Translator {
         let line_no = file.line_number_for_index(location.lo as usize);
         st.curr_file = file_id;
         st.curr_lineno = line_no;
+        st.curr_span = (location.lo, location.hi);
     }
     fn update_curr_function(&self, st: &mut TranslatorState, name: &str) {
         let function_name_id = st.function_name_arena.insert(name.to_string());
         st.curr_func = function_name_id;
     }
-    pub(crate) fn translate(&self) -> CompiledProgram {
+    pub(crate) fn translate(&self) -> Result<CompiledProgram, ErrorSummary> {
+        self.translate_(false)
+    }
+    // also emits an entry point for every `test fn` in the main file
+    pub(crate) fn translate_with_tests(&self) -> Result<CompiledProgram, ErrorSummary> {
+        self.translate_(true)
+    }
+    fn emit_load_libs(&self, st: &mut TranslatorState) {
+        for (i, lib) in self.statics.dylibs.iter().enumerate() {
+            self.emit(st, Instr::PushString(lib.to_str().unwrap().to_string()));
+            self.emit(st, Instr::LoadLib);
+            for s in self.statics.dylib_to_funcs[&(i as u32)].iter() {
+                self.emit(st, Instr::PushString(s.to_string()));
+                self.emit(st, Instr::LoadForeignFunc);
+            }
+        }
+    }
+    fn translate_(&self, with_tests: bool) -> Result<CompiledProgram, ErrorSummary> {
         let mut st = TranslatorState::default();
+        let mut test_labels = vec![];
+        let mut exported = vec![];
+        let call_return = make_label("call_return");
+        let load_libs = make_label("load_libs");
         {
             let st = &mut st;
             let monomorph_env = MonomorphEnv::empty();
-            for (i, lib) in self.statics.dylibs.iter().enumerate() {
-                self.emit(st, Instr::PushString(lib.to_str().unwrap().to_string()));
-                self.emit(st, Instr::LoadLib);
-                for s in self.statics.dylib_to_funcs[&(i as u32)].iter() {
-                    self.emit(st, Instr::PushString(s.to_string()));
-                    self.emit(st, Instr::LoadForeignFunc);
-                }
-            }
+            self.enter_scope(st, make_label("main"));
+            self.emit_load_libs(st);
             if let Some(file) = self.file_asts.first() {
                 let mut locals = HashSet::default();
                 self.update_curr_function(st, "<main>");
@@ -221,12 +343,63 @@ impl Translator {
                 self.emit(st, Instr::StoreOffset(-1));
                 self.emit(st, Instr::Stop);
             }
+            if with_tests && let Some(file) = self.file_asts.first() {
+                for item in &file.items {
+                    let ItemKind::FuncDef(f) = &*item.kind else {
+                        continue;
+                    };
+                    if !f.test {
+                        continue;
+                    }
+                    let label = make_label(&format!("test_{}", f.name.v));
+                    self.update_curr_function(st, &f.name.v);
+                    self.update_current_file_and_lineno(st, f.name.node());
+                    self.enter_scope(st, label.clone());
+                    self.emit_load_libs(st);
+                    let func_name = &self.statics.fully_qualified_names[&f.name.id];
+                    self.handle_func_call(st, None, func_name, f);
+                    self.emit(st, Instr::Pop);
+                    self.emit(st, Instr::Stop);
+                    test_labels.push((f.clone(), label));
+                }
+            }
+            if let Some(file) = self.file_asts.first() {
+                for item in &file.items {
+                    let ItemKind::FuncDef(f) = &*item.kind else {
+                        continue;
+                    };
+                    if f.test {
+                        continue;
+                    }
+                    let Some(Monotype::Function(args, ret)) = self
+                        .statics
+                        .solution_of_node(f.name.node())
+                        .and_then(|ty| ty.monotype())
+                    else {
+                        continue;
+                    };
+                    let func_name = &self.statics.fully_qualified_names[&f.name.id];
+                    let desc = FuncDesc {
+                        kind: FuncKind::NamedFunc(f.clone()),
+                        overload_ty: None,
+                    };
+                    let label = self.get_func_label(st, desc, None, func_name);
+                    let args = args.iter().map(|arg| arg.to_string()).collect();
+                    exported.push((f.name.v.clone(), args, ret.to_string(), label));
+                }
+            }
             while !st.funcs_to_generate.is_empty() {
                 let mut iteration = Vec::new();
                 mem::swap(&mut (iteration), &mut st.funcs_to_generate);
                 for desc in iteration {
-                    if let FuncKind::NamedFunc(f) = &desc.kind {
-                        self.update_curr_function(st, &f.name.v);
+                    match &desc.kind {
+                        FuncKind::NamedFunc(f) => {
+                            self.update_curr_function(st, &f.name.v);
+                            self.update_current_file_and_lineno(st, f.name.node());
+                        }
+                        FuncKind::AnonymousFunc(e) => {
+                            self.update_current_file_and_lineno(st, e.node());
+                        }
                     }
                     let (func_ty, args, body) = match &desc.kind {
                         FuncKind::NamedFunc(f) => (
@@ -246,8 +419,8 @@ impl Translator {
                         monomorph_env.update(&func_ty, overload_ty);
                     }
                     let return_label = make_label("return");
-                    let label = st.func_map.get(&desc).unwrap();
-                    self.emit(st, Line::Label(label.clone()));
+                    let label = st.func_map.get(&desc).unwrap().clone();
+                    self.enter_scope(st, label.clone());
                     let mut locals = HashSet::default();
                     collect_locals_expr(body, &mut locals);
                     let locals_count = locals.len();
@@ -256,6 +429,16 @@ impl Translator {
                     for (i, arg) in args.iter().rev().enumerate() {
                         offset_table.entry(arg.0.id).or_insert(-(i as i32) - 1);
                     }
+                    for arg in args.iter() {
+                        self.add_debug_local(
+                            st,
+                            label.clone(),
+                            &arg.0.v,
+                            offset_table[&arg.0.id],
+                            self.statics.solution_of_node(arg.0.node()),
+                            &monomorph_env,
+                        );
+                    }
                     for (i, local) in locals.iter().enumerate() {
                         offset_table.entry(*local).or_insert((i) as i32);
                     }
@@ -267,16 +450,108 @@ impl Translator {
                     self.emit(st, Instr::Return(nargs as u32));
                 }
             }
+            // keeps the result of a function called by the embedder, like the end of <main>
+            self.emit(st, call_return.clone());
+            self.emit(st, Instr::StoreOffset(-1));
+            self.emit(st, Instr::Stop);
+            self.emit(st, load_libs.clone());
+            self.emit_load_libs(st);
+            self.emit(st, Instr::Stop);
         }
-        st.lines = optimize(st.lines);
+        let entry_points: Vec<Label> = test_labels
+            .iter()
+            .map(|(_, label)| label)
+            .chain(exported.iter().map(|(_, _, _, label)| label))
+            .chain([&call_return, &load_libs])
+            .cloned()
+            .collect();
+        let checked_entry_points: Vec<Label> =
+            test_labels.iter().map(|(_, label)| label.clone()).collect();
+        st.lines = match optimize(
+            mem::take(&mut st.lines),
+            &entry_points,
+            &checked_entry_points,
+        ) {
+            Ok(lines) => lines,
+            Err(errors) => {
+                let errors = errors
+                    .into_iter()
+                    .map(|error| Error::ConstantEvaluation {
+                        file_id: error.file_id,
+                        range: error.span.0 as usize..error.span.1 as usize,
+                        msg: error.msg.to_string(),
+                    })
+                    .collect();
+                return Err(ErrorSummary {
+                    msg: "".to_string(),
+                    more: Some((self._files.clone(), errors)),
+                });
+            }
+        };
+        st.lines = mark_tail_calls(mem::take(&mut st.lines));
         self.create_source_location_tables(&mut st);
         let constants = gather_constants(&st.lines);
-        let (instructions, _) = remove_labels(&st.lines, &constants);
+        let (instructions, label_map) = remove_labels(&st.lines, &constants);
+        let mut debug_info = mem::take(&mut st.debug_info);
+        for (label, scope) in mem::take(&mut st.scope_labels) {
+            let start = label_map[&label] as BytecodeIndex;
+            if debug_info.scopes.last().is_some_and(|last| last.0 == start) {
+                debug_info.scopes.pop();
+            }
+            debug_info.scopes.push((start, scope));
+        }
+        for (label, local) in mem::take(&mut st.local_labels) {
+            debug_info.locals.push(LocalVar {
+                live_from: label_map[&label] as BytecodeIndex,
+                ..local
+            });
+        }
+        let tests = test_labels
+            .into_iter()
+            .map(|(f, label)| {
+                let file = self._files.get(f.name.loc.file_id).unwrap();
+                TestFunction {
+                    name: f.name.v.clone(),
+                    file: file.nominal_path.clone(),
+                    line: file.line_number_for_index(f.name.loc.lo as usize),
+                    entry: label_map[&label] as BytecodeIndex,
+                }
+            })
+            .collect();
+        let functions = exported
+            .into_iter()
+            .map(|(name, args, ret, label)| ExportedFunction {
+                name,
+                args,
+                ret,
+                entry: label_map[&label] as BytecodeIndex,
+            })
+            .collect();
+        let host_functions = self
+            .statics
+            .host_funcs
+            .iter()
+            .map(|decl| {
+                let ty = self.statics.solution_of_node(decl.name.node());
+                let (args, ret) = match ty.and_then(|ty| ty.monotype()) {
+                    Some(Monotype::Function(args, ret)) => {
+                        (args.iter().map(|arg| arg.to_string()).collect(), ret.to_string())
+                    }
+                    // generic host functions can't be called through a typed registry
+                    _ => (vec![], "?".to_string()),
+                };
+                HostFunctionSignature {
+                    name: decl.name.v.clone(),
+                    args,
+                    ret,
+                }
+            })
+            .collect();
         let mut filename_arena = vec![];
         for file_data in self._files.files.iter() {
             filename_arena.push(file_data.name().to_string());
         }
-        CompiledProgram {
+        Ok(CompiledProgram {
             instructions,
             int_constants: constants.int_constants.into_iter().collect(),
             float_constants: constants
@@ -291,7 +566,13 @@ impl Translator {
             filename_table: st.filename_table,
             lineno_table: st.lineno_table,
             function_name_table: st.function_name_table,
-        }
+            tests,
+            functions,
+            host_functions,
+            call_return: label_map[&call_return] as BytecodeIndex,
+            load_libs: label_map[&load_libs] as BytecodeIndex,
+            debug_info,
+        })
     }
     fn translate_expr(
         &self,
@@ -302,52 +583,10 @@ impl Translator {
     ) {
         self.update_current_file_and_lineno(st, expr.node());
         match &*expr.kind {
-            ExprKind::Variable(_) => match &self.statics.resolution_map[&expr.id] {
-                Declaration::EnumVariant { variant, .. } => {
-                    self.emit(st, Instr::PushNil(1));
-                    self.emit(
-                        st,
-                        Instr::ConstructVariant {
-                            tag: *variant as u16,
-                        },
-                    );
-                }
-                Declaration::Var(node) => {
-                    let idx = offset_table.get(&node.id()).unwrap();
-                    self.emit(st, Instr::LoadOffset(*idx));
-                }
-                Declaration::Builtin(b) => match b {
-                    BuiltinOperation::Newline => {
-                        self.emit(st, Instr::PushString("\n".to_owned()));
-                    }
-                    _ => {
-                        unimplemented!()
-                    }
-                },
-                Declaration::InterfaceOutputType { .. } | Declaration::BuiltinType(_) => {
-                    unreachable!()
-                }
-                Declaration::FreeFunction(f) => {
-                    let name = &self.statics.fully_qualified_names[&f.name.id];
-                    self.emit(
-                        st,
-                        Instr::MakeClosure {
-                            func_addr: name.clone(),
-                        },
-                    );
-                }
-                Declaration::_ForeignFunction { .. }
-                | Declaration::HostFunction(..)
-                | Declaration::InterfaceMethod { .. }
-                | Declaration::MemberFunction { .. } => unimplemented!(),
-                Declaration::Struct(_)
-                | Declaration::Enum { .. }
-                | Declaration::InterfaceDef(_) => {
-                }
-                Declaration::Array | Declaration::Polytype(_) => {
-                    unreachable!()
-                }
-            },
+            ExprKind::Variable(_) => {
+                let decl = &self.statics.resolution_map[&expr.id];
+                self.translate_decl_value(decl, offset_table, st);
+            }
             ExprKind::MemberAccessLeadingDot(ident) => match self.statics.resolution_map[&ident.id]
             {
                 Declaration::EnumVariant { variant, .. } => {
@@ -379,6 +618,10 @@ impl Translator {
             ExprKind::BinOp(left, op, right) => {
                 self.translate_expr(left, offset_table, monomorph_env, st);
                 self.translate_expr(right, offset_table, monomorph_env, st);
+                // the operation belongs to the whole expression, not to `right`. A binary
+                // expression is located at its operator, so its span is widened to the operands.
+                self.update_current_file_and_lineno(st, expr.node());
+                st.curr_span = (left.loc.lo, right.loc.hi);
                 let mut helper = |monomorph_env: &MonomorphEnv, method_name: &str| {
                     let iface_method = self
                         .statics
@@ -509,7 +752,8 @@ impl Translator {
                     | ExprKind::Str(_)
                     | ExprKind::Array(_)
                     | ExprKind::BinOp(..)
-                    | ExprKind::Tuple(..) => panic!("lhs of FuncAp not a function"),
+                    | ExprKind::Tuple(..)
+                    | ExprKind::StructLiteral(..) => panic!("lhs of FuncAp not a function"),
                     ExprKind::MemberFuncAp(..) => unimplemented!(),
                     ExprKind::Unwrap(..) => unimplemented!(),
                     ExprKind::IfElse(_expr, _expr1, _expr2) => unimplemented!(),
@@ -541,6 +785,43 @@ impl Translator {
                 }
                 self.emit(st, Instr::ConstructStruct(exprs.len() as u16));
             }
+            ExprKind::StructLiteral(_, base, fields) => {
+                let Type::Nominal(Nominal::Struct(struct_def), _) =
+                    self.statics.solution_of_node(expr.node()).unwrap()
+                else {
+                    panic!("not a struct");
+                };
+                if let Some(base) = base {
+                    self.translate_expr(base, offset_table, monomorph_env, st);
+                    self.emit(st, Instr::StoreOffset(offset_table[&base.id]));
+                }
+                // the values are evaluated in source order, so if that isn't the declaration
+                // order they go through locals
+                let in_order = fields.is_sorted_by_key(|(name, _)| {
+                    struct_def.fields.iter().position(|field| field.name.v == name.v)
+                });
+                if !in_order {
+                    for (_, value) in fields {
+                        self.translate_expr(value, offset_table, monomorph_env, st);
+                        self.emit(st, Instr::StoreOffset(offset_table[&value.id]));
+                    }
+                }
+                for (idx, field) in struct_def.fields.iter().enumerate() {
+                    if let Some((_, value)) = fields.iter().find(|(name, _)| name.v == field.name.v)
+                    {
+                        if in_order {
+                            self.translate_expr(value, offset_table, monomorph_env, st);
+                        } else {
+                            self.emit(st, Instr::LoadOffset(offset_table[&value.id]));
+                        }
+                    } else {
+                        let base = base.as_ref().unwrap();
+                        self.emit(st, Instr::LoadOffset(offset_table[&base.id]));
+                        self.emit(st, Instr::GetField(idx as u16));
+                    }
+                }
+                self.emit(st, Instr::ConstructStruct(struct_def.fields.len() as u16));
+            }
             ExprKind::IfElse(cond, then_block, else_block) => {
                 self.translate_expr(cond, offset_table, monomorph_env, st);
                 let then_label = make_label("then");
@@ -553,7 +834,11 @@ impl Translator {
                 self.emit(st, Line::Label(end_label));
             }
             ExprKind::MemberAccess(accessed, field_name) => {
-                if let Some(Declaration::EnumVariant { variant, .. }) =
+                if let Some(Declaration::Module(_)) = self.statics.resolution_map.get(&accessed.id)
+                {
+                    let decl = &self.statics.resolution_map[&field_name.id];
+                    self.translate_decl_value(decl, offset_table, st);
+                } else if let Some(Declaration::EnumVariant { variant, .. }) =
                     &self.statics.resolution_map.get(&field_name.id)
                 {
                     self.emit(st, Instr::PushNil(1));
@@ -597,7 +882,7 @@ impl Translator {
                 }
                 for (i, arm) in arms.iter().enumerate() {
                     self.emit(st, Line::Label(arm_labels[i].clone()));
-                    self.handle_pat_binding(&arm.pat, offset_table, st);
+                    self.handle_pat_binding(&arm.pat, offset_table, monomorph_env, st);
                     self.translate_stmt(&arm.stmt, true, offset_table, monomorph_env, st);
                     if i != arms.len() - 1 {
                         self.emit(st, Instr::Jump(end_label.clone()));
@@ -685,6 +970,59 @@ impl Translator {
             }
         }
     }
+    fn translate_decl_value(
+        &self,
+        decl: &Declaration,
+        offset_table: &OffsetTable,
+        st: &mut TranslatorState,
+    ) {
+        match decl {
+            Declaration::EnumVariant { variant, .. } => {
+                self.emit(st, Instr::PushNil(1));
+                self.emit(
+                    st,
+                    Instr::ConstructVariant {
+                        tag: *variant as u16,
+                    },
+                );
+            }
+            Declaration::Var(node) => {
+                let idx = offset_table.get(&node.id()).unwrap();
+                self.emit(st, Instr::LoadOffset(*idx));
+            }
+            Declaration::Builtin(b) => match b {
+                BuiltinOperation::Newline => {
+                    self.emit(st, Instr::PushString("\n".to_owned()));
+                }
+                _ => {
+                    unimplemented!()
+                }
+            },
+            Declaration::InterfaceOutputType { .. } | Declaration::BuiltinType(_) => {
+                unreachable!()
+            }
+            Declaration::FreeFunction(f) => {
+                let name = &self.statics.fully_qualified_names[&f.name.id];
+                self.emit(
+                    st,
+                    Instr::MakeClosure {
+                        func_addr: name.clone(),
+                    },
+                );
+            }
+            Declaration::_ForeignFunction { .. }
+            | Declaration::HostFunction(..)
+            | Declaration::InterfaceMethod { .. }
+            | Declaration::MemberFunction { .. } => unimplemented!(),
+            Declaration::Struct(_)
+            | Declaration::Enum { .. }
+            | Declaration::InterfaceDef(_)
+            | Declaration::Module(_) => {}
+            Declaration::Array | Declaration::Handle | Declaration::Polytype(_) => {
+                unreachable!()
+            }
+        }
+    }
     fn translate_func_ap(
         &self,
         decl: &Declaration,
@@ -776,6 +1114,9 @@ impl Translator {
             Declaration::Enum { .. } => {
                 panic!("can't call enum name as ctor");
             }
+            Declaration::Module(_) => {
+                panic!("can't call module as function");
+            }
             Declaration::Builtin(b) => match b {
                 BuiltinOperation::AddInt => {
                     self.emit(st, Instr::AddInt);
@@ -877,6 +1218,7 @@ impl Translator {
             Declaration::InterfaceOutputType { .. }
             | Declaration::InterfaceDef(_)
             | Declaration::Array
+            | Declaration::Handle
             | Declaration::Polytype(_)
             | Declaration::BuiltinType(_) => {
                 unreachable!()
@@ -995,7 +1337,7 @@ impl Translator {
         match &*stmt.kind {
             StmtKind::Let(_, pat, expr) => {
                 self.translate_expr(expr, offset_table, monomorph_env, st);
-                self.handle_pat_binding(&pat.0, offset_table, st);
+                self.handle_pat_binding(&pat.0, offset_table, monomorph_env, st);
                 if is_last {
                     self.emit(st, Instr::PushNil(1));
                 }
@@ -1122,7 +1464,7 @@ impl Translator {
                 self.emit(st, Instr::EqualInt);
                 self.emit(st, Instr::Not);
                 self.emit(st, Instr::JumpIf(end_label.clone()));
-                self.handle_pat_binding(pat, offset_table, st);
+                self.handle_pat_binding(pat, offset_table, monomorph_env, st);
                 st.loop_stack.push(EnclosingLoop {
                     start_label: start_label.clone(),
                     end_label: end_label.clone(),
@@ -1188,23 +1530,39 @@ impl Translator {
             }
         }
     }
-    fn handle_pat_binding(&self, pat: &Rc<Pat>, locals: &OffsetTable, st: &mut TranslatorState) {
+    fn handle_pat_binding(
+        &self,
+        pat: &Rc<Pat>,
+        locals: &OffsetTable,
+        monomorph_env: &MonomorphEnv,
+        st: &mut TranslatorState,
+    ) {
         match &*pat.kind {
-            PatKind::Binding(_) => {
+            PatKind::Binding(name) => {
                 let idx = locals.get(&pat.id).unwrap();
                 self.emit(st, Instr::StoreOffset(*idx));
+                let live_from = make_label("bind");
+                self.emit(st, Line::Label(live_from.clone()));
+                self.add_debug_local(
+                    st,
+                    live_from,
+                    name,
+                    *idx,
+                    self.statics.solution_of_node(pat.node()),
+                    monomorph_env,
+                );
             }
             PatKind::Tuple(pats) => {
                 self.emit(st, Instr::DeconstructStruct);
                 for pat in pats.iter() {
-                    self.handle_pat_binding(pat, locals, st);
+                    self.handle_pat_binding(pat, locals, monomorph_env, st);
                 }
             }
             PatKind::Variant(_prefixes, _, inner) => {
                 if let Some(inner) = inner {
                     self.emit(st, Instr::DeconstructVariant);
                     self.emit(st, Instr::Pop);
-                    self.handle_pat_binding(inner, locals, st);
+                    self.handle_pat_binding(inner, locals, monomorph_env, st);
                 } else {
                     self.emit(st, Instr::Pop);
                 }
@@ -1220,6 +1578,111 @@ impl Translator {
         }
     }
 }
+impl Translator {
+    // starts the code of a new function; locals recorded from here on belong to it
+    fn enter_scope(&self, st: &mut TranslatorState, label: Label) {
+        st.curr_scope = st.scope_labels.len() as u32;
+        st.scope_labels.push((label.clone(), st.curr_scope));
+        self.emit(st, Line::Label(label));
+    }
+    fn add_debug_local(
+        &self,
+        st: &mut TranslatorState,
+        live_from: Label,
+        name: &str,
+        offset: i32,
+        ty: Option<Type>,
+        monomorph_env: &MonomorphEnv,
+    ) {
+        let ty = match ty {
+            Some(ty) => self.debug_type(st, &ty.subst(monomorph_env)),
+            None => self.debug_type_unknown(st),
+        };
+        let local = LocalVar {
+            scope: st.curr_scope,
+            name: name.to_string(),
+            offset,
+            ty,
+            live_from: 0,
+        };
+        st.local_labels.push((live_from, local));
+    }
+    fn debug_type_unknown(&self, st: &mut TranslatorState) -> u32 {
+        st.debug_info.types.push(DebugType::Unknown);
+        st.debug_info.types.len() as u32 - 1
+    }
+    fn debug_type(&self, st: &mut TranslatorState, ty: &Type) -> u32 {
+        if let Some(id) = st.debug_types.get(ty) {
+            return *id;
+        }
+        // registered before the components so recursive types refer back to it
+        let id = self.debug_type_unknown(st);
+        st.debug_types.insert(ty.clone(), id);
+        let debug_ty = match ty {
+            Type::Poly(..) | Type::InterfaceOutput(..) => DebugType::Unknown,
+            Type::Void | Type::Never => DebugType::Void,
+            Type::Int => DebugType::Int,
+            Type::Float => DebugType::Float,
+            Type::Bool => DebugType::Bool,
+            Type::String => DebugType::String,
+            Type::Function(..) => DebugType::Function,
+            Type::Tuple(elems) => {
+                DebugType::Tuple(elems.iter().map(|elem| self.debug_type(st, elem)).collect())
+            }
+            Type::Nominal(Nominal::Array, params) => match params.first() {
+                Some(elem) => DebugType::Array(self.debug_type(st, elem)),
+                None => DebugType::Unknown,
+            },
+            Type::Nominal(Nominal::Handle, _) => DebugType::Handle,
+            Type::Nominal(Nominal::Struct(struct_def), params) => {
+                let env = MonomorphEnv::empty();
+                for (ty_arg, param) in struct_def.ty_args.iter().zip(params) {
+                    env.extend(PolytypeDeclaration::Ordinary(ty_arg.clone()), param.clone());
+                }
+                let fields = struct_def
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        let ty = match field.ty.to_solved_type(&self.statics) {
+                            Some(ty) => self.debug_type(st, &ty.subst(&env)),
+                            None => self.debug_type_unknown(st),
+                        };
+                        (field.name.v.clone(), ty)
+                    })
+                    .collect();
+                DebugType::Struct {
+                    name: struct_def.name.v.clone(),
+                    fields,
+                }
+            }
+            Type::Nominal(Nominal::Enum(enum_def), params) => {
+                let env = MonomorphEnv::empty();
+                for (ty_arg, param) in enum_def.ty_args.iter().zip(params) {
+                    env.extend(PolytypeDeclaration::Ordinary(ty_arg.clone()), param.clone());
+                }
+                let variants = enum_def
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        let data = variant.data.as_ref().map(|data| {
+                            match data.to_solved_type(&self.statics) {
+                                Some(ty) => self.debug_type(st, &ty.subst(&env)),
+                                None => self.debug_type_unknown(st),
+                            }
+                        });
+                        (variant.ctor.v.clone(), data)
+                    })
+                    .collect();
+                DebugType::Enum {
+                    name: enum_def.name.v.clone(),
+                    variants,
+                }
+            }
+        };
+        st.debug_info.types[id as usize] = debug_ty;
+        id
+    }
+}
 fn collect_locals_expr(expr: &Expr, locals: &mut HashSet<NodeId>) {
     match &*expr.kind {
         ExprKind::Block(statements) => {
@@ -1252,6 +1715,19 @@ fn collect_locals_expr(expr: &Expr, locals: &mut HashSet<NodeId>) {
             collect_locals_expr(left, locals);
             collect_locals_expr(right, locals);
         }
+        ExprKind::StructLiteral(_, base, fields) => {
+            if let Some(base) = base {
+                locals.insert(base.id);
+                collect_locals_expr(base, locals);
+            }
+            for (_, expr) in fields {
+                // for values given out of declaration order
+                if fields.len() > 1 {
+                    locals.insert(expr.id);
+                }
+                collect_locals_expr(expr, locals);
+            }
+        }
         ExprKind::MemberAccess(accessed, _) => {
             collect_locals_expr(accessed, locals);
         }
@@ -1419,4 +1895,4 @@ impl Type {
             _ => self.clone(),
         }
     }
-}
\ No newline at end of file
+}
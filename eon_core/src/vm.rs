@@ -18,27 +18,33 @@ pub type EonFloat = f64;
 #[cfg(feature = "ffi")]
 use crate::addons::EON_VM_FUNCS;
 #[cfg(feature = "ffi")]
-use crate::addons::EonVmFunctions;
-use crate::translate_bytecode::{BytecodeIndex, CompiledProgram};
+use crate::addons::{EonVmFunctions, ForeignFunction, StaticModule};
+use crate::addons::HandleView;
+use crate::debugger::DebugInfo;
+use crate::profiler::Profiler;
+use crate::translate_bytecode::{
+    BytecodeIndex, CompiledProgram, ExportedFunction, HostFunctionSignature, TestFunction,
+};
 use core::fmt;
 #[cfg(feature = "ffi")]
 use libloading::Library;
 use std::error::Error;
-#[cfg(feature = "ffi")]
 use std::ffi::c_void;
 use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Instant;
 use std::{
     cell::Cell,
     fmt::{Display, Formatter},
     mem,
 };
 pub struct Vm<Value: ValueTrait = PackedValue> {
-    program: Vec<Instr>,
-    pc: ProgramCounter,
-    stack_base: usize,
-    value_stack: Vec<Value>,
-    call_stack: Vec<CallFrame>,
-    heap: Vec<ManagedObject<Value>>,
+    pub(crate) program: Vec<Instr>,
+    pub(crate) pc: ProgramCounter,
+    pub(crate) stack_base: usize,
+    pub(crate) value_stack: Vec<Value>,
+    pub(crate) call_stack: Vec<CallFrame>,
+    pub(crate) heap: Vec<ManagedObject<Value>>,
     heap_group: HeapGroup,
     int_constants: Vec<i64>,
     float_constants: Vec<f64>,
@@ -48,31 +54,75 @@ pub struct Vm<Value: ValueTrait = PackedValue> {
     filename_table: Vec<(BytecodeIndex, u32)>,
     lineno_table: Vec<(BytecodeIndex, u32)>,
     function_name_table: Vec<(BytecodeIndex, u32)>,
-    pending_host_func: Option<u16>,
-    error: Option<Box<VmError>>,
-    done: bool,
+    pub(crate) pending_host_func: Option<u16>,
+    pub(crate) error: Option<Box<VmError>>,
+    pub(crate) done: bool,
+    max_call_depth: usize,
+    // in values, checked when a function is called
+    max_stack_size: usize,
+    // execution budget for untrusted programs, checked between instructions
+    fuel: Option<u64>,
+    deadline: Option<Instant>,
+    deadline_check_interval: u32,
+    steps_until_deadline_check: u32,
+    max_memory: Option<usize>,
+    // bytes used by heap objects, estimated between collections
+    allocated: usize,
+    pub(crate) limit_hit: Option<Limit>,
+    sandboxed: bool,
+    // calls from native code into Eon that haven't returned yet
+    native_calls: u32,
+    pub(crate) debug_info: DebugInfo,
+    pub(crate) functions: Vec<ExportedFunction>,
+    pub(crate) host_functions: Vec<HostFunctionSignature>,
+    pub(crate) call_return: BytecodeIndex,
+    pub(crate) load_libs: BytecodeIndex,
+    // set once <main>, a test or `load_libs` has loaded the shared libraries
+    pub(crate) libs_loaded: bool,
+    pub(crate) profiler: Option<Box<Profiler>>,
+    // times each instruction was executed
+    pub(crate) coverage: Option<Vec<u64>>,
     #[cfg(feature = "ffi")]
-    libs: Vec<Library>,
+    static_modules: Vec<&'static StaticModule>,
     #[cfg(feature = "ffi")]
-    foreign_functions: Vec<unsafe extern "C" fn(*mut c_void, *const EonVmFunctions) -> ()>,
+    libs: Vec<NativeLibrary>,
+    #[cfg(feature = "ffi")]
+    foreign_functions: Vec<ForeignFunction>,
 }
+#[cfg(feature = "ffi")]
+enum NativeLibrary {
+    Dynamic(Library),
+    Static(&'static StaticModule),
+}
+// OutOfSteps, Timeout and OutOfMemory can be resumed with `run` after raising the limit
+#[derive(Debug)]
 pub enum VmStatus {
     Done,
     PendingHostFunc(u16),
     OutOfSteps,
+    Timeout,
+    OutOfMemory,
     Error(Box<VmError>),
 }
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Limit {
+    Fuel,
+    Deadline,
+    Memory,
+}
 #[derive(Clone, Debug)]
 pub struct VmError {
     kind: VmErrorKind,
     location: VmErrorLocation,
     trace: Vec<VmErrorLocation>,
 }
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct VmErrorLocation {
-    filename: String,
-    lineno: u32,
-    function_name: String,
+    pub(crate) filename: String,
+    pub(crate) lineno: u32,
+    pub(crate) function_name: String,
+    // frames between this location and the one called from it that tail calls replaced
+    pub(crate) tail_calls: u32,
 }
 #[derive(Clone, Debug)]
 pub enum VmErrorKind {
@@ -86,6 +136,13 @@ pub enum VmErrorKind {
     LibLoadFailure(String),
     SymbolLoadFailure(String),
     InternalError(String),
+    StackOverflow,
+    OutOfFuel,
+    Timeout,
+    OutOfMemory,
+    Sandboxed(String),
+    HostFunction(String),
+    ForeignFunction { name: String, message: String },
 }
 #[derive(Clone, Debug)]
 pub enum ValueKind {
@@ -97,10 +154,14 @@ pub enum ValueKind {
     Array,
     Enum,
     Struct,
+    Handle,
     HeapObject,
     FunctionObject,
 }
 pub type ErrorLocation = (String, u32);
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 100_000;
+pub const DEFAULT_MAX_STACK_SIZE: usize = 1 << 22;
+pub const DEFAULT_DEADLINE_CHECK_INTERVAL: u32 = 1024;
 impl Vm {
     pub fn new(program: CompiledProgram) -> Self {
         Self {
@@ -122,12 +183,59 @@ impl Vm {
             pending_host_func: None,
             error: None,
             done: false,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            max_stack_size: DEFAULT_MAX_STACK_SIZE,
+            fuel: None,
+            deadline: None,
+            deadline_check_interval: DEFAULT_DEADLINE_CHECK_INTERVAL,
+            steps_until_deadline_check: DEFAULT_DEADLINE_CHECK_INTERVAL,
+            max_memory: None,
+            allocated: 0,
+            limit_hit: None,
+            sandboxed: false,
+            native_calls: 0,
+            debug_info: program.debug_info,
+            functions: program.functions,
+            host_functions: program.host_functions,
+            call_return: program.call_return,
+            load_libs: program.load_libs,
+            libs_loaded: false,
+            profiler: None,
+            coverage: None,
+            #[cfg(feature = "ffi")]
+            static_modules: Vec::new(),
             #[cfg(feature = "ffi")]
             libs: Vec::new(),
             #[cfg(feature = "ffi")]
             foreign_functions: Vec::new(),
         }
     }
+    pub fn for_test(program: CompiledProgram, test: &TestFunction) -> Self {
+        let entry = test.entry;
+        let mut vm = Self::new(program);
+        vm.pc = ProgramCounter(entry);
+        vm
+    }
+    // Loads the shared libraries unless <main> or a test already has. The limits are meant for the
+    // program itself, so they don't apply.
+    pub(crate) fn load_libs(&mut self) -> Result<(), Box<VmError>> {
+        if self.libs_loaded {
+            return Ok(());
+        }
+        let fuel = self.fuel.take();
+        let deadline = self.deadline.take();
+        let max_memory = self.max_memory.take();
+        self.pc = ProgramCounter(self.load_libs);
+        self.run();
+        self.fuel = fuel;
+        self.deadline = deadline;
+        self.max_memory = max_memory;
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.libs_loaded = true;
+        Ok(())
+    }
 }
 impl<Value: ValueTrait> Vm<Value> {
     pub fn status(&self) -> VmStatus {
@@ -136,8 +244,10 @@ impl<Value: ValueTrait> Vm<Value> {
         } else if self.is_done() {
             VmStatus::Done
         } else {
-            match &self.error {
-                Some(err) => VmStatus::Error(err.clone()),
+            match (&self.error, self.limit_hit) {
+                (Some(err), _) => VmStatus::Error(err.clone()),
+                (None, Some(Limit::Deadline)) => VmStatus::Timeout,
+                (None, Some(Limit::Memory)) => VmStatus::OutOfMemory,
                 _ => VmStatus::OutOfSteps,
             }
         }
@@ -168,9 +278,47 @@ impl<Value: ValueTrait> Vm<Value> {
     }
     #[inline(always)]
     pub fn push_str(&mut self, s: String) {
-        self.heap
-            .push(ManagedObject::new(ManagedObjectKind::String(s)));
-        let r = self.heap_reference(self.heap.len() - 1);
+        let r = self.alloc(ManagedObjectKind::String(s));
+        self.push(r);
+    }
+    /// Pushes a handle owning `ptr`. `tag` identifies what `ptr` points to, so native code can
+    /// check it before using the pointer it gets back from `view_handle`.
+    ///
+    /// # Safety
+    /// `finalizer` must be safe to call with `ptr`, at most once, from any thread the vm is
+    /// moved to.
+    pub unsafe fn push_handle(&mut self, ptr: *mut c_void, tag: u64, finalizer: Option<Finalizer>) {
+        let handle = NativeHandle {
+            ptr,
+            tag,
+            finalizer,
+        };
+        let r = self.alloc(ManagedObjectKind::Handle(Arc::new(handle)));
+        self.push(r);
+    }
+    // the handle on top of the stack
+    pub fn view_handle(&self) -> HandleView {
+        let index = self.top().get_heap_index(self, ValueKind::Handle);
+        match &self.heap[index].kind {
+            ManagedObjectKind::Handle(handle) => HandleView {
+                ptr: handle.ptr,
+                tag: handle.tag,
+                owner: Arc::as_ptr(handle) as *const c_void,
+            },
+            _ => self.fail_wrong_type(ValueKind::Handle),
+        }
+    }
+    /// Pushes the handle a view was taken from again, without another finalizer.
+    ///
+    /// # Safety
+    /// The handle must not have been collected since `view` was taken, so no gc may have run.
+    pub unsafe fn push_handle_view(&mut self, view: HandleView) {
+        let owner = view.owner as *const NativeHandle;
+        let handle = unsafe {
+            Arc::increment_strong_count(owner);
+            Arc::from_raw(owner)
+        };
+        let r = self.alloc(ManagedObjectKind::Handle(handle));
         self.push(r);
     }
     #[inline(always)]
@@ -188,9 +336,7 @@ impl<Value: ValueTrait> Vm<Value> {
     #[inline(always)]
     pub fn construct_variant(&mut self, tag: u16) {
         let value = self.pop();
-        self.heap
-            .push(ManagedObject::new(ManagedObjectKind::Enum { tag, value }));
-        let r = self.heap_reference(self.heap.len() - 1);
+        let r = self.alloc(ManagedObjectKind::Enum { tag, value });
         self.value_stack.push(r);
     }
     #[inline(always)]
@@ -201,17 +347,13 @@ impl<Value: ValueTrait> Vm<Value> {
     pub fn construct_struct(&mut self, n: u16) {
         let fields = self.pop_n(n as usize);
         let fields = fields.into_boxed_slice();
-        self.heap
-            .push(ManagedObject::new(ManagedObjectKind::Struct(fields)));
-        let r = self.heap_reference(self.heap.len() - 1);
+        let r = self.alloc(ManagedObjectKind::Struct(fields));
         self.push(r);
     }
     #[inline(always)]
     pub fn construct_array(&mut self, n: usize) {
         let fields = self.pop_n(n);
-        self.heap
-            .push(ManagedObject::new(ManagedObjectKind::DynArray(fields)));
-        let r = self.heap_reference(self.heap.len() - 1);
+        let r = self.alloc(ManagedObjectKind::DynArray(fields));
         self.push(r);
     }
     #[inline(always)]
@@ -269,6 +411,58 @@ impl<Value: ValueTrait> Vm<Value> {
     pub fn get_error(&self) -> Option<Box<VmError>> {
         self.error.clone()
     }
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+    pub fn set_max_stack_size(&mut self, values: usize) {
+        self.max_stack_size = values;
+    }
+    // How many more instructions may run; `None` is unlimited.
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
+    }
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+    // Reading the clock is slow, so the deadline is only checked every `steps` instructions.
+    pub fn set_deadline_check_interval(&mut self, steps: u32) {
+        self.deadline_check_interval = steps.max(1);
+        self.steps_until_deadline_check = self.deadline_check_interval;
+    }
+    // Heap objects may use up to `bytes`. The heap is collected before giving up.
+    pub fn set_max_memory(&mut self, bytes: Option<usize>) {
+        self.max_memory = bytes;
+    }
+    pub fn memory_usage(&self) -> usize {
+        self.allocated
+    }
+    // Foreign functions of the module are called directly instead of being loaded from its
+    // shared library.
+    #[cfg(feature = "ffi")]
+    pub fn add_static_module(&mut self, module: &'static StaticModule) {
+        self.static_modules.push(module);
+    }
+    // A sandboxed vm refuses to load shared libraries, so it can't call foreign functions.
+    pub fn set_sandboxed(&mut self, sandboxed: bool) {
+        self.sandboxed = sandboxed;
+    }
+    // The error to report when the embedder gives up on a program that hit a limit.
+    pub fn limit_error(&self) -> Option<Box<VmError>> {
+        let kind = match self.limit_hit? {
+            Limit::Fuel => VmErrorKind::OutOfFuel,
+            Limit::Deadline => VmErrorKind::Timeout,
+            Limit::Memory => VmErrorKind::OutOfMemory,
+        };
+        Some(Box::new(self.make_error(kind)))
+    }
+    // called before a function call pushes a frame
+    #[inline(always)]
+    fn stack_overflowed(&self) -> bool {
+        self.call_stack.len() >= self.max_call_depth || self.value_stack.len() > self.max_stack_size
+    }
     #[inline(always)]
     fn fail(&self, kind: VmErrorKind) -> ! {
         panic!(
@@ -281,7 +475,7 @@ impl<Value: ValueTrait> Vm<Value> {
         )
     }
     #[inline(always)]
-    fn make_error(&self, kind: VmErrorKind) -> VmError {
+    pub(crate) fn make_error(&self, kind: VmErrorKind) -> VmError {
         VmError {
             kind,
             location: self.pc_to_error_location(self.pc),
@@ -345,6 +539,7 @@ pub enum Instr {
     Jump(ProgramCounter),
     JumpIf(ProgramCounter),
     Call(CallData),
+    TailCall(CallData),
     CallFuncObj,
     CallExtern(u32),
     Return(u32),
@@ -650,14 +845,18 @@ impl ValueTrait for PackedValue {
     }
 }
 #[derive(Debug)]
-struct CallFrame {
-    pc: ProgramCounter,
-    stack_base: usize,
-    nargs: u8,
+pub(crate) struct CallFrame {
+    pub(crate) pc: ProgramCounter,
+    pub(crate) stack_base: usize,
+    pub(crate) nargs: u8,
+    // calls that replaced this frame, which are missing from stack traces
+    pub(crate) tail_calls: u32,
+    // where the last of them was made, if there were any
+    pub(crate) last_tail_call: ProgramCounter,
 }
 #[derive(Debug, Clone)]
-struct ManagedObject<Value: ValueTrait> {
-    kind: ManagedObjectKind<Value>,
+pub(crate) struct ManagedObject<Value: ValueTrait> {
+    pub(crate) kind: ManagedObjectKind<Value>,
     forwarding_pointer: Cell<Option<usize>>,
 }
 impl<Value: ValueTrait> ManagedObject<Value> {
@@ -670,23 +869,105 @@ impl<Value: ValueTrait> ManagedObject<Value> {
     }
 }
 #[derive(Debug, Clone)]
-enum ManagedObjectKind<Value: ValueTrait> {
+pub(crate) enum ManagedObjectKind<Value: ValueTrait> {
     Enum { tag: u16, value: Value },
     DynArray(Vec<Value>),
     Struct(Box<[Value]>),
     String(String),
+    // shared, so the copy made when gc moves the object doesn't finalize it
+    Handle(Arc<NativeHandle>),
+}
+pub type Finalizer = unsafe extern "C" fn(ptr: *mut c_void);
+// A pointer owned by native code. The finalizer runs once the last copy is dropped, which
+// happens when a gc finds the handle unreachable or when the vm is dropped.
+#[derive(Debug)]
+pub(crate) struct NativeHandle {
+    ptr: *mut c_void,
+    tag: u64,
+    finalizer: Option<Finalizer>,
+}
+// the pointer is only ever handed back to the native code that created it
+unsafe impl Send for NativeHandle {}
+unsafe impl Sync for NativeHandle {}
+impl Drop for NativeHandle {
+    fn drop(&mut self) {
+        if let Some(finalizer) = self.finalizer {
+            unsafe { finalizer(self.ptr) }
+        }
+    }
+}
+impl<Value: ValueTrait> ManagedObjectKind<Value> {
+    // the object and what it owns, as counted against the memory limit
+    fn nbytes(&self) -> usize {
+        size_of::<ManagedObject<Value>>()
+            + match self {
+                ManagedObjectKind::Enum { .. } => 0,
+                ManagedObjectKind::DynArray(fields) => fields.len() * size_of::<Value>(),
+                ManagedObjectKind::Struct(fields) => fields.len() * size_of::<Value>(),
+                ManagedObjectKind::String(s) => s.len(),
+                ManagedObjectKind::Handle(_) => size_of::<NativeHandle>(),
+            }
+    }
 }
 impl<Value: ValueTrait> Vm<Value> {
     pub fn run(&mut self) {
         self.validate();
-        while self.step() {}
+        self.limit_hit = None;
+        if self.fuel.is_none() && self.deadline.is_none() && self.max_memory.is_none() {
+            while self.step() {}
+        } else {
+            while self.within_limits() && self.step() {}
+        }
     }
     pub fn run_n_steps(&mut self, mut steps: u32) {
         self.validate();
-        while steps > 0 && self.step() {
+        self.limit_hit = None;
+        while steps > 0 && self.within_limits() && self.step() {
             steps -= 1;
         }
     }
+    // charges one instruction against the budget
+    #[inline(always)]
+    fn within_limits(&mut self) -> bool {
+        if let Some(fuel) = &mut self.fuel {
+            if *fuel == 0 {
+                self.limit_hit = Some(Limit::Fuel);
+                return false;
+            }
+            *fuel -= 1;
+        }
+        if let Some(deadline) = self.deadline {
+            self.steps_until_deadline_check -= 1;
+            if self.steps_until_deadline_check == 0 {
+                self.steps_until_deadline_check = self.deadline_check_interval;
+                if Instant::now() >= deadline {
+                    self.refund_fuel();
+                    self.limit_hit = Some(Limit::Deadline);
+                    return false;
+                }
+            }
+        }
+        if let Some(max_memory) = self.max_memory
+            && self.allocated > max_memory
+        {
+            // the values on the stack are the only roots between instructions, unless native
+            // code that called back into Eon still looks at heap objects
+            if self.native_calls == 0 {
+                self.gc();
+            }
+            if self.allocated > max_memory {
+                self.refund_fuel();
+                self.limit_hit = Some(Limit::Memory);
+                return false;
+            }
+        }
+        true
+    }
+    fn refund_fuel(&mut self) {
+        if let Some(fuel) = &mut self.fuel {
+            *fuel += 1;
+        }
+    }
     fn validate(&self) {
         if self.pending_host_func.is_some() {
             panic!("must handle pending host func");
@@ -698,6 +979,12 @@ impl<Value: ValueTrait> Vm<Value> {
     #[inline(always)]
     fn step(&mut self) -> bool {
         let instr = self.program[self.pc.get()];
+        if let Some(profiler) = &mut self.profiler {
+            profiler.count(self.pc);
+        }
+        if let Some(coverage) = &mut self.coverage {
+            coverage[self.pc.get()] += 1;
+        }
         self.pc.0 += 1;
         match instr {
             Instr::PushNil(n) => {
@@ -716,9 +1003,7 @@ impl<Value: ValueTrait> Vm<Value> {
             }
             Instr::PushString(idx) => {
                 let s = &self.static_strings[idx as usize];
-                self.heap
-                    .push(ManagedObject::new(ManagedObjectKind::String(s.clone())));
-                let r = self.heap_reference(self.heap.len() - 1);
+                let r = self.alloc(ManagedObjectKind::String(s.clone()));
                 self.value_stack.push(r);
             }
             Instr::Pop => {
@@ -891,33 +1176,74 @@ impl<Value: ValueTrait> Vm<Value> {
                 }
             }
             Instr::Call(call_data) => {
+                if self.stack_overflowed() {
+                    self.error = Some(Box::new(self.make_error(VmErrorKind::StackOverflow)));
+                    return false;
+                }
                 let nargs = call_data.get_nargs();
                 let target = call_data.get_addr();
                 self.call_stack.push(CallFrame {
                     pc: self.pc,
                     stack_base: self.stack_base,
                     nargs: nargs as u8,
+                    tail_calls: 0,
+                    last_tail_call: ProgramCounter(0),
                 });
                 self.pc = ProgramCounter(target);
                 self.stack_base = self.value_stack.len();
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.enter(target);
+                }
+            }
+            Instr::TailCall(call_data) => {
+                let nargs = call_data.get_nargs() as usize;
+                let target = call_data.get_addr();
+                let Some(frame) = self.call_stack.last_mut() else {
+                    self.fail(VmErrorKind::Underflow)
+                };
+                // the new arguments replace the current ones and the callee returns to our caller
+                let args_start = self.stack_base - frame.nargs as usize;
+                frame.nargs = nargs as u8;
+                frame.tail_calls = frame.tail_calls.saturating_add(1);
+                frame.last_tail_call = self.pc;
+                let len = self.value_stack.len();
+                self.value_stack.copy_within(len - nargs..len, args_start);
+                self.value_stack.truncate(args_start + nargs);
+                self.pc = ProgramCounter(target);
+                self.stack_base = self.value_stack.len();
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.exit();
+                    profiler.enter(target);
+                }
             }
             Instr::CallFuncObj => {
+                if self.stack_overflowed() {
+                    self.error = Some(Box::new(self.make_error(VmErrorKind::StackOverflow)));
+                    return false;
+                }
                 let nargs = self.pop_int();
                 let addr = self.pop_addr();
                 self.call_stack.push(CallFrame {
                     pc: self.pc,
                     stack_base: self.stack_base,
                     nargs: nargs as u8,
+                    tail_calls: 0,
+                    last_tail_call: ProgramCounter(0),
                 });
                 self.pc = addr;
                 self.stack_base = self.value_stack.len();
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.enter(addr.0);
+                }
             }
             Instr::Return(nargs) => {
-                if nargs != 0 {
-                    let idx = self.stack_base.wrapping_add_signed(-(nargs as isize));
-                    let v = self.pop();
-                    self.value_stack[idx] = v;
-                }
+                // the result replaces the first argument, or the first local without arguments.
+                // It has to be moved even then: the truncate below keeps only the first slot, so a
+                // function without arguments but with locals would return its first local
+                let idx = self.stack_base.wrapping_add_signed(-(nargs as isize));
+                let v = self.pop();
+                self.value_stack.truncate(idx);
+                self.value_stack.push(v);
                 let frame = self.call_stack.pop();
                 let Some(frame) = frame else { self.fail(VmErrorKind::Underflow) };
                 self.pc = frame.pc;
@@ -925,10 +1251,16 @@ impl<Value: ValueTrait> Vm<Value> {
                 self.stack_base = frame.stack_base;
                 self.value_stack
                     .truncate(old_stack_base - (frame.nargs as usize) + 1);
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.exit();
+                }
             }
             Instr::Stop => {
                 self.value_stack.truncate(1);
                 self.done = true;
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.stop();
+                }
                 return false;
             }
             Instr::Panic => {
@@ -1010,6 +1342,7 @@ impl<Value: ValueTrait> Vm<Value> {
                 match &mut self.heap[heap_index].kind {
                     ManagedObjectKind::DynArray(fields) => {
                         fields.push(rvalue);
+                        self.allocated += size_of::<Value>();
                     }
                     _ => self.fail_wrong_type(ValueKind::Array),
                 }
@@ -1039,25 +1372,19 @@ impl<Value: ValueTrait> Vm<Value> {
                 let mut new_str = String::with_capacity(a_str.len() + b_str.len());
                 new_str.push_str(a_str);
                 new_str.push_str(b_str);
-                self.heap
-                    .push(ManagedObject::new(ManagedObjectKind::String(new_str)));
-                let r = self.heap_reference(self.heap.len() - 1);
+                let r = self.alloc(ManagedObjectKind::String(new_str));
                 self.push(r);
             }
             Instr::IntToString => {
                 let n = self.pop_int();
                 let s = n.to_string();
-                self.heap
-                    .push(ManagedObject::new(ManagedObjectKind::String(s)));
-                let r = self.heap_reference(self.heap.len() - 1);
+                let r = self.alloc(ManagedObjectKind::String(s));
                 self.push(r);
             }
             Instr::FloatToString => {
                 let f = self.pop().get_float(self);
                 let s = f.to_string();
-                self.heap
-                    .push(ManagedObject::new(ManagedObjectKind::String(s)));
-                let r = self.heap_reference(self.heap.len() - 1);
+                let r = self.alloc(ManagedObjectKind::String(s));
                 self.push(r);
             }
             Instr::HostFunc(eff) => {
@@ -1065,33 +1392,60 @@ impl<Value: ValueTrait> Vm<Value> {
                 return false;
             }
             Instr::LoadLib => {
+                if self.sandboxed {
+                    let libname = self.pop().view_string(self);
+                    let what = format!("shared library `{libname}`");
+                    self.error = Some(Box::new(self.make_error(VmErrorKind::Sandboxed(what))));
+                    return false;
+                }
                 if cfg!(not(feature = "ffi")) {
                     self.fail(VmErrorKind::FfiNotEnabled);
                 }
                 #[cfg(feature = "ffi")]
                 {
                     let libname = self.pop().view_string(self);
-                    let lib = unsafe { Library::new(libname) };
-                    let Ok(lib) = lib else {
-                        self.fail(VmErrorKind::LibLoadFailure(libname.clone()))
-                    };
-                    self.libs.push(lib);
+                    // modules compiled into the binary don't need their shared library
+                    let linked = self
+                        .static_modules
+                        .iter()
+                        .find(|module| module.provides_library(libname));
+                    if let Some(module) = linked {
+                        self.libs.push(NativeLibrary::Static(module));
+                    } else {
+                        let lib = unsafe { Library::new(libname) };
+                        let Ok(lib) = lib else {
+                            self.fail(VmErrorKind::LibLoadFailure(libname.clone()))
+                        };
+                        self.libs.push(NativeLibrary::Dynamic(lib));
+                    }
                 }
+                self.libs_loaded = true;
             }
             Instr::LoadForeignFunc => {
+                if self.sandboxed {
+                    let symbol_name = self.pop().view_string(self);
+                    let what = format!("foreign function `{symbol_name}`");
+                    self.error = Some(Box::new(self.make_error(VmErrorKind::Sandboxed(what))));
+                    return false;
+                }
                 if cfg!(not(feature = "ffi")) {
                     self.fail(VmErrorKind::FfiNotEnabled);
                 }
                 #[cfg(feature = "ffi")]
                 {
                     let symbol_name = self.pop().view_string(self);
-                    let lib = self.libs.last().expect("no libraries have been loaded");
-                    let symbol  =
-                        unsafe { lib.get(symbol_name.as_bytes()) };
-                    let Ok(symbol) = symbol else {
+                    let symbol = match self.libs.last().expect("no libraries have been loaded") {
+                        NativeLibrary::Dynamic(lib) => {
+                            unsafe { lib.get::<ForeignFunction>(symbol_name.as_bytes()) }
+                                .ok()
+                                .map(|symbol| *symbol)
+                        }
+                        NativeLibrary::Static(module) => module.function(symbol_name),
+                    };
+                    let Some(symbol) = symbol else {
                         self.fail(VmErrorKind::SymbolLoadFailure(symbol_name.clone()));
                     };
-                    self.foreign_functions.push(*symbol);
+                    self.foreign_functions.push(symbol);
                 }
             }
             Instr::CallExtern(_func_id) => {
@@ -1105,12 +1459,16 @@ impl<Value: ValueTrait> Vm<Value> {
                         let eon_vm_functions_ptr = &EON_VM_FUNCS as *const EonVmFunctions;
                         self.foreign_functions[_func_id as usize](vm_ptr, eon_vm_functions_ptr);
                     };
+                    // a call back into Eon failed
+                    if self.error.is_some() {
+                        return false;
+                    }
                 }
             }
         }
         true
     }
-    fn pc_to_error_location(&self, pc: ProgramCounter) -> VmErrorLocation {
+    pub(crate) fn pc_to_error_location(&self, pc: ProgramCounter) -> VmErrorLocation {
         let file_id = match self
             .filename_table
             .binary_search_by_key(&(pc.0), |pair| pair.0)
@@ -1144,17 +1502,31 @@ impl<Value: ValueTrait> Vm<Value> {
             filename,
             lineno,
             function_name,
+            tail_calls: 0,
         }
     }
     #[inline(always)]
     fn make_stack_trace(&self) -> Vec<VmErrorLocation> {
         let mut ret = vec![];
         for frame in &self.call_stack {
-            ret.push(self.pc_to_error_location(frame.pc));
+            ret.push(VmErrorLocation {
+                tail_calls: frame.tail_calls.saturating_sub(1),
+                ..self.pc_to_error_location(frame.pc)
+            });
+            // the function that made the last tail call, e.g. the caller of a failed assert
+            if frame.tail_calls > 0 {
+                ret.push(self.pc_to_error_location(frame.last_tail_call));
+            }
         }
         ret
     }
     #[inline(always)]
+    fn alloc(&mut self, kind: ManagedObjectKind<Value>) -> Value {
+        self.allocated += kind.nbytes();
+        self.heap.push(ManagedObject::new(kind));
+        self.heap_reference(self.heap.len() - 1)
+    }
+    #[inline(always)]
     fn heap_reference(&mut self, idx: usize) -> Value {
         Value::from(HeapReference::new(idx, self.heap_group))
     }
@@ -1233,18 +1605,74 @@ impl<Value: ValueTrait> Vm<Value> {
                         ));
                     }
                 }
-                ManagedObjectKind::String(_) => {}
+                ManagedObjectKind::String(_) | ManagedObjectKind::Handle(_) => {}
             }
             new_heap.extend(to_add);
             i += 1;
         }
         mem::swap(&mut self.heap, &mut new_heap);
         self.heap_group = new_heap_group;
+        self.allocated = self.heap.iter().map(|obj| obj.kind.nbytes()).sum();
     }
     #[inline(always)]
     fn push(&mut self, x: impl Into<Value>) {
         self.value_stack.push(x.into());
     }
+    // a foreign function panicked, the vm stops once it returns
+    pub(crate) fn fail_foreign_function(&mut self, name: String, message: String) {
+        let kind = VmErrorKind::ForeignFunction { name, message };
+        self.error = Some(Box::new(self.make_error(kind)));
+    }
+    #[inline(always)]
+    pub fn pop_function(&mut self) -> ProgramCounter {
+        self.pop_addr()
+    }
+    #[inline(always)]
+    pub fn push_function(&mut self, func: ProgramCounter) {
+        self.push(func);
+    }
+    // Calls a function object from native code and runs until it returns. The arguments are on
+    // top of the stack and are replaced by the result. On failure the error is set and the vm
+    // stops once the native code returns. Running out of fuel, time or memory inside the call is
+    // such a failure: the native code's frame can't be suspended, so unlike at the top level the
+    // vm can't be resumed with more fuel or a later deadline.
+    pub fn call_function(&mut self, func: ProgramCounter, nargs: u8) -> bool {
+        if self.stack_overflowed() {
+            self.error = Some(Box::new(self.make_error(VmErrorKind::StackOverflow)));
+            return false;
+        }
+        let depth = self.call_stack.len();
+        self.call_stack.push(CallFrame {
+            pc: self.pc,
+            stack_base: self.stack_base,
+            nargs,
+            tail_calls: 0,
+            last_tail_call: ProgramCounter(0),
+        });
+        self.pc = func;
+        self.stack_base = self.value_stack.len();
+        if let Some(profiler) = &mut self.profiler {
+            profiler.enter(func.0);
+        }
+        self.native_calls += 1;
+        while self.call_stack.len() > depth && self.within_limits() && self.step() {}
+        self.native_calls -= 1;
+        if self.call_stack.len() == depth {
+            return true;
+        }
+        if self.error.is_none() {
+            let error = match self.pending_host_func.take() {
+                Some(eff) => Box::new(self.make_error(VmErrorKind::HostFunction(format!(
+                    "`{}` can't be called from a callback of native code",
+                    self.host_functions[eff as usize].name
+                )))),
+                None => self.limit_error().expect("call stopped without an error"),
+            };
+            self.limit_hit = None;
+            self.error = Some(error);
+        }
+        false
+    }
     #[inline(always)]
     pub fn pop_int(&mut self) -> EonInt {
         self.pop().get_int(self)
@@ -1309,13 +1737,30 @@ impl Display for VmError {
             .max()
             .unwrap_or(10);
         writeln!(f, "[traceback]")?;
-        for location in std::iter::once(&self.location).chain(self.trace.iter().rev()) {
+        // runs of the same frame, as left by deep recursion, are printed once
+        let mut locations = std::iter::once(&self.location)
+            .chain(self.trace.iter().rev())
+            .peekable();
+        while let Some(location) = locations.next() {
+            match location.tail_calls {
+                0 => {}
+                1 => writeln!(f, "    ... 1 tail call elided")?,
+                n => writeln!(f, "    ... {n} tail calls elided")?,
+            }
+            let mut repeated = 1;
+            while locations.next_if_eq(&location).is_some() {
+                repeated += 1;
+            }
             let file_and_line = format!("{}:{}", location.filename, location.lineno);
-            writeln!(
+            write!(
                 f,
                 "    {:width$} in `{}`",
                 file_and_line, location.function_name
-            )?
+            )?;
+            if repeated > 1 {
+                write!(f, " repeated {repeated} times")?;
+            }
+            writeln!(f)?
         }
         Ok(())
     }
@@ -1347,6 +1792,27 @@ impl Display for VmErrorKind {
             VmErrorKind::InternalError(s) => {
                 write!(f, "internal error: {s}")
             }
+            VmErrorKind::StackOverflow => {
+                write!(f, "error: stack overflow")
+            }
+            VmErrorKind::OutOfFuel => {
+                write!(f, "error: instruction limit reached")
+            }
+            VmErrorKind::Timeout => {
+                write!(f, "error: time limit reached")
+            }
+            VmErrorKind::OutOfMemory => {
+                write!(f, "error: memory limit reached")
+            }
+            VmErrorKind::Sandboxed(s) => {
+                write!(f, "error: can't load {s} in a sandbox")
+            }
+            VmErrorKind::HostFunction(s) => {
+                write!(f, "error in host function: {s}")
+            }
+            VmErrorKind::ForeignFunction { name, message } => {
+                write!(f, "error in foreign function `{name}`: {message}")
+            }
             VmErrorKind::WrongType { expected } => {
                 write!(
                     f,
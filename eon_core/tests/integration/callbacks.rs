@@ -0,0 +1,114 @@
+use crate::helper::unwrap_or_panic;
+use eon_core::vm::{ValueTrait, Vm, VmStatus};
+use eon_core::{MockFileProvider, compile_bytecode};
+// stands in for a native module calling back into Eon
+fn run(src: &str) -> Vm {
+    run_with_fuel(src, None)
+}
+fn run_with_fuel(src: &str, fuel: Option<u64>) -> Vm {
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.set_fuel(fuel);
+    let names: Vec<_> = vm.host_functions().iter().map(|f| f.name.clone()).collect();
+    loop {
+        vm.run();
+        let Some(index) = vm.get_pending_host_func() else {
+            break;
+        };
+        vm.clear_pending_host_func();
+        match names[index as usize].as_str() {
+            "apply_twice" => {
+                let x = vm.pop_int();
+                let f = vm.pop_function();
+                vm.push_int(x);
+                if !vm.call_function(f, 1) {
+                    break;
+                }
+                if !vm.call_function(f, 1) {
+                    break;
+                }
+            }
+            "print_string" => {
+                vm.pop();
+                vm.push_nil();
+            }
+            name => panic!("unexpected host function {name}"),
+        }
+    }
+    vm
+}
+#[test]
+fn native_code_calls_eon_functions() {
+    let vm = run(r#"
+host fn apply_twice(f: int -> int, x: int) -> int
+fn add_three(x: int) -> int {
+    let y = x + 1
+    y + 2
+}
+let a = apply_twice(add_three, 1)
+let b = apply_twice(x -> x * 3, 2)
+a * 100 + b
+"#);
+    let VmStatus::Done = vm.status() else {
+        panic!("{:?}", vm.status())
+    };
+    assert_eq!(vm.top().get_int(&vm), 718);
+}
+#[test]
+fn callback_errors() {
+    let vm = run(r#"
+host fn apply_twice(f: int -> int, x: int) -> int
+fn crash(x: int) -> int {
+    x / 0
+}
+apply_twice(crash, 1)
+"#);
+    let VmStatus::Error(err) = vm.status() else {
+        panic!("{:?}", vm.status())
+    };
+    // the trace goes through the native call
+    assert!(err.to_string().starts_with("error: division by zero"), "{err}");
+    assert!(err.to_string().contains("crash"), "{err}");
+
+    let vm = run(r#"
+host fn apply_twice(f: int -> int, x: int) -> int
+fn noisy(x: int) -> int {
+    println("hi")
+    x
+}
+apply_twice(noisy, 1)
+"#);
+    let VmStatus::Error(err) = vm.status() else {
+        panic!("{:?}", vm.status())
+    };
+    assert!(
+        err.to_string().starts_with(
+            "error in host function: `print_string` can't be called from a callback of native code"
+        ),
+        "{err}"
+    );
+}
+#[test]
+fn limits_are_fatal_in_callbacks() {
+    let vm = run_with_fuel(
+        r#"
+host fn apply_twice(f: int -> int, x: int) -> int
+fn spin(x: int) -> int {
+    var i = 0
+    while i < 1000000 {
+        i = i + 1
+    }
+    x
+}
+apply_twice(spin, 1)
+"#,
+        Some(10_000),
+    );
+    let VmStatus::Error(err) = vm.status() else {
+        panic!("{:?}", vm.status())
+    };
+    assert!(err.to_string().starts_with("error: instruction limit reached"), "{err}");
+}
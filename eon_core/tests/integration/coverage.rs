@@ -0,0 +1,59 @@
+use crate::helper::unwrap_or_panic;
+use eon_core::MockFileProvider;
+use eon_core::compile_tests;
+use eon_core::coverage::Coverage;
+use eon_core::vm::Vm;
+use std::collections::BTreeMap;
+#[test]
+fn coverage_of_tests() {
+    let src = r#"fn abs(n) {
+  if n < 0 {
+    return 0 - n
+  }
+
+  // comment
+  n
+}
+test fn positive() {
+  assert_eq(abs(3), 3)
+}
+test fn negative() {
+  assert_eq(abs(0 - 3), 3)
+}
+"#;
+    let program = unwrap_or_panic(compile_tests(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let test = &program.tests()[0];
+    let mut vm = Vm::for_test(program.clone(), test);
+    assert!(vm.coverage().is_none());
+    vm.enable_coverage();
+    vm.run();
+    let mut coverage = vm.coverage().unwrap();
+    assert!(coverage.files.contains_key("prelude.en"));
+    coverage.exclude_prelude();
+    // the other test's lines are misses
+    let lines: BTreeMap<u32, u64> =
+        [(2, 1), (3, 0), (7, 1), (9, 1), (10, 1), (12, 0), (13, 0)].into();
+    assert_eq!(coverage.files["main.en"], lines);
+    assert_eq!(coverage.file_summary("main.en"), (4, 7));
+
+    let mut vm = Vm::for_test(program.clone(), &program.tests()[1]);
+    vm.enable_coverage();
+    vm.run();
+    let mut all = Coverage::new();
+    all.merge(&coverage);
+    all.merge(&vm.coverage().unwrap());
+    all.exclude_prelude();
+    assert_eq!(all.files["main.en"][&2], 2);
+    assert_eq!(all.files["main.en"][&3], 1);
+    assert_eq!(all.file_summary("main.en"), (7, 7));
+
+    let lcov = coverage.lcov(|file| format!("/src/{file}"));
+    assert_eq!(
+        lcov,
+        "TN:\nSF:/src/main.en\nDA:2,1\nDA:3,0\nDA:7,1\nDA:9,1\nDA:10,1\nDA:12,0\nDA:13,0\nLH:4\nLF:7\nend_of_record\n"
+    );
+    assert!(coverage.summary().contains("main.en      4/7      57.14%"));
+}
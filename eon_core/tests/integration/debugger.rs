@@ -0,0 +1,116 @@
+use crate::helper::unwrap_or_panic;
+use eon_core::MockFileProvider;
+use eon_core::compile_bytecode;
+use eon_core::debugger::{DebugEvent, Debugger, Local, StepMode};
+use eon_core::vm::Vm;
+fn debug_vm(src: &str) -> Vm {
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    Vm::new(program)
+}
+fn local(name: &str, value: &str) -> Local {
+    Local {
+        name: name.into(),
+        value: value.into(),
+    }
+}
+fn line(vm: &Vm) -> u32 {
+    vm.source_location().line
+}
+const SRC: &str = r#"type Point = { x: int y: int }
+type Shape = circle(float) | rect(float, float)
+fn add(a, b) {
+  let sum = a + b
+  sum
+}
+fn main() {
+  let p = Point(1, 2)
+  let xs = [1, 2, 3]
+  let s: Shape = .rect(1.5, 2.0)
+  let total = add(p.x, p.y)
+  let name = "eon"
+  total
+}
+main()
+"#;
+#[test]
+fn debugger_breakpoints() {
+    let mut vm = debug_vm(SRC);
+    let mut debugger = Debugger::new();
+    let breakpoint = debugger.add_breakpoint(&vm, "main.en", 4).unwrap();
+    assert_eq!((breakpoint.id, breakpoint.line), (1, 4));
+    // blank or declaration-only lines move to the next line with code
+    let breakpoint = debugger.add_breakpoint(&vm, "main.en", 1).unwrap();
+    assert_eq!(breakpoint.line, 3);
+    assert!(debugger.remove_breakpoint(2));
+    assert!(debugger.add_breakpoint(&vm, "main.en", 100).is_none());
+    assert!(matches!(
+        debugger.resume(&mut vm, StepMode::Continue),
+        DebugEvent::Breakpoint(1)
+    ));
+    let frames: Vec<_> = vm
+        .frames()
+        .into_iter()
+        .map(|frame| (frame.function, frame.line))
+        .collect();
+    assert_eq!(
+        frames,
+        vec![("add".into(), 4), ("main".into(), 11), ("<main>".into(), 15)]
+    );
+    assert_eq!(vm.locals(0), vec![local("a", "1"), local("b", "2")]);
+    assert!(matches!(
+        debugger.resume(&mut vm, StepMode::Continue),
+        DebugEvent::Done
+    ));
+}
+#[test]
+fn debugger_stepping() {
+    let mut vm = debug_vm(SRC);
+    let mut debugger = Debugger::new();
+    debugger.add_breakpoint(&vm, "main.en", 11).unwrap();
+    debugger.resume(&mut vm, StepMode::Continue);
+    assert_eq!(line(&vm), 11);
+    assert!(matches!(debugger.resume(&mut vm, StepMode::In), DebugEvent::Step));
+    assert_eq!(vm.source_location().function, "add");
+    assert_eq!(vm.call_depth(), 2);
+    debugger.resume(&mut vm, StepMode::In);
+    assert_eq!(line(&vm), 4);
+    debugger.resume(&mut vm, StepMode::Over);
+    assert_eq!(line(&vm), 5);
+    debugger.resume(&mut vm, StepMode::Out);
+    assert_eq!((vm.source_location().function.as_str(), line(&vm)), ("main", 11));
+    debugger.resume(&mut vm, StepMode::Over);
+    assert_eq!(line(&vm), 12);
+    // stepping over a call doesn't stop inside it
+    let mut vm = debug_vm(SRC);
+    let mut debugger = Debugger::new();
+    debugger.add_breakpoint(&vm, "main.en", 11).unwrap();
+    debugger.resume(&mut vm, StepMode::Continue);
+    debugger.resume(&mut vm, StepMode::Over);
+    assert_eq!((vm.source_location().function.as_str(), line(&vm)), ("main", 12));
+}
+#[test]
+fn debugger_locals() {
+    let mut vm = debug_vm(SRC);
+    let mut debugger = Debugger::new();
+    debugger.add_breakpoint(&vm, "main.en", 13).unwrap();
+    debugger.resume(&mut vm, StepMode::Continue);
+    assert_eq!(
+        vm.locals(0),
+        vec![
+            local("p", "Point { x: 1, y: 2 }"),
+            local("xs", "[1, 2, 3]"),
+            local("s", "Shape.rect(1.5, 2.0)"),
+            local("total", "3"),
+            local("name", "\"eon\""),
+        ]
+    );
+    // variables aren't shown before they are bound
+    let mut vm = debug_vm(SRC);
+    let mut debugger = Debugger::new();
+    debugger.add_breakpoint(&vm, "main.en", 9).unwrap();
+    debugger.resume(&mut vm, StepMode::Continue);
+    assert_eq!(vm.locals(0), vec![local("p", "Point { x: 1, y: 2 }")]);
+}
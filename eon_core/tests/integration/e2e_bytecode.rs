@@ -1,10 +1,12 @@
 use crate::helper::unwrap_or_panic;
 use eon_core::MockFileProvider;
 use eon_core::compile_bytecode;
+use eon_core::compile_tests;
 use eon_core::vm::VmStatus;
 use eon_core::vm::{ValueTrait, Vm};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 #[test]
 fn arithmetic() {
     let src = r#"
@@ -188,6 +190,146 @@ x.age
     assert_eq!(top.get_int(&vm), 36);
 }
 #[test]
+fn struct_named_fields() {
+    let src = r#"
+type Point = {
+    x: int
+    y: int
+}
+let p = Point { y: 5, x: 2 }
+p.x * 10 + p.y
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.run();
+    let top = vm.top();
+    assert_eq!(top.get_int(&vm), 25);
+}
+#[test]
+fn struct_field_shorthand() {
+    let src = r#"
+type Point = {
+    x: int
+    y: int
+}
+let y = 7
+let x = 3
+let p = Point { y, x }
+p.x * 10 + p.y
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.run();
+    let top = vm.top();
+    assert_eq!(top.get_int(&vm), 37);
+}
+#[test]
+fn struct_fields_evaluated_in_source_order() {
+    let src = r#"
+type Triple = {
+    a: string
+    b: string
+    c: string
+}
+fn note(log: array<string>, s: string) -> string {
+    log.push(s)
+    s
+}
+fn make(log: array<string>) -> Triple {
+    Triple { c: note(log, "c"), a: note(log, "a"), b: note(log, "b") }
+}
+let log = []
+let t = make(log)
+let u = Triple { ..t, b: note(log, "y"), a: note(log, "x") }
+ToString.str(log) & " " & t.a & t.b & t.c & " " & u.a & u.b & u.c
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.run();
+    let top = vm.top();
+    assert_eq!(top.view_string(&vm), "[ c, a, b, y, x ] abc xyc");
+}
+#[test]
+fn capitalized_names_before_blocks_in_conditions() {
+    let src = r#"
+type Point = {
+    x: int
+    y: int
+}
+let LIMIT = 3
+let x = 5
+let a = if x < LIMIT { x } else { LIMIT }
+var i = 0
+while i < LIMIT { i = i + 1 }
+let b = match LIMIT { 3 -> 1, _ -> 0 }
+// a struct literal in a condition is parenthesized
+let y = 4
+let c = if (Point { x, y }).y == 4 { 1 } else { 0 }
+a * 1000 + i * 100 + b * 10 + c
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.run();
+    let top = vm.top();
+    assert_eq!(top.get_int(&vm), 3311);
+}
+#[test]
+fn struct_update() {
+    let src = r#"
+type person = {
+    name: string
+    age: int
+    city: string
+}
+let old = person { name: "Alice", age: 30, city: "Paris" }
+let new = { ..old, age: 31 }
+old.age = 99
+new.name & " " & new.age & " " & new.city
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.run();
+    let top = vm.top();
+    assert_eq!(top.view_string(&vm), "Alice 31 Paris");
+}
+#[test]
+fn struct_update_generic() {
+    let src = r#"
+type Pair<T> = {
+    items: array<T>
+    first: T
+}
+fn with_first(p: Pair<T>, x: T) -> Pair<T> {
+    Pair { ..p, first: x }
+}
+let p = with_first(Pair { items: ["a", "b"], first: "a" }, "c")
+p.items.len()
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.run();
+    let top = vm.top();
+    assert_eq!(top.get_int(&vm), 2);
+}
+#[test]
 fn array_assign_and_access() {
     let src = r#"
 let arr = [ 1, 2, 3, 4 ]
@@ -532,6 +674,35 @@ g
     assert_eq!(top.get_float(&vm), 2.0);
 }
 #[test]
+fn float_comparisons() {
+    let src = r#"
+let a = 1.5
+let b = 2.5
+let results = [
+  Num.less_than_or_equal(a, b),
+  Num.less_than_or_equal(a, a),
+  not(Num.less_than_or_equal(b, a)),
+  Num.greater_than(b, a),
+  not(Num.greater_than(a, a)),
+  Num.greater_than_or_equal(a, a),
+  not(Num.greater_than_or_equal(a, b))
+]
+var all = true
+for r in results {
+  all = all and r
+}
+all
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.run();
+    let top = vm.top();
+    assert!(top.get_bool(&vm));
+}
+#[test]
 fn array_push() {
     let src = r#"
 let arr = [1, 2, 3, 4, 5]
@@ -743,6 +914,31 @@ m!
     let VmStatus::Error(_) = vm.status() else { panic!() };
 }
 #[test]
+fn result_type() {
+    let src = r#"
+fn parse_digit(s: string) -> result<int, string> {
+    if s == "7" { result.ok(7) } else { result.err("not a digit: " & s) }
+}
+let a = match parse_digit("7") {
+    .ok(n) -> n,
+    .err(_) -> 0
+}
+let b = match parse_digit("x") {
+    .ok(n) -> n,
+    .err(msg) -> if msg == "not a digit: x" { 14 } else { 0 }
+}
+a * 100 + b
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.run();
+    let top = vm.top();
+    assert_eq!(top.get_int(&vm), 714);
+}
+#[test]
 fn garbage_collection_once() {
     let src = r#"
 var i = 0
@@ -797,7 +993,7 @@ x
 #[test]
 fn import_all() {
     let util = r#"
-fn foo(a: int, b) {
+pub fn foo(a: int, b) {
   a + b
 }
 "#;
@@ -824,10 +1020,10 @@ foo(2, 2)
 #[test]
 fn import_single() {
     let util = r#"
-fn foo(a: int, b) {
+pub fn foo(a: int, b) {
   a * b
 }
-fn bar(a: int, b) {
+pub fn bar(a: int, b) {
   a + b
 }
 "#;
@@ -854,10 +1050,10 @@ foo(6, 7)
 #[test]
 fn import_except() {
     let util = r#"
-fn foo(a: int, b) {
+pub fn foo(a: int, b) {
   a * b
 }
-fn bar(a: int, b) {
+pub fn bar(a: int, b) {
   a + b
 }
 "#;
@@ -884,13 +1080,13 @@ foo(6, 7)
 #[test]
 fn import_single_plural() {
     let util = r#"
-fn foo(a: int, b) {
+pub fn foo(a: int, b) {
   a * b
 }
-fn foo2(a: int, b) {
+pub fn foo2(a: int, b) {
   a * b
 }
-fn bar(a: int, b) {
+pub fn bar(a: int, b) {
   a + b
 }
 "#;
@@ -917,13 +1113,13 @@ foo(6, 7) + foo2(6, 7)
 #[test]
 fn import_except_plural() {
     let util = r#"
-fn foo(a: int, b) {
+pub fn foo(a: int, b) {
   a * b
 }
-fn bar(a: int, b) {
+pub fn bar(a: int, b) {
   a + b
 }
-fn bar2(a: int, b) {
+pub fn bar2(a: int, b) {
   a + b
 }
 "#;
@@ -951,6 +1147,189 @@ foo(6, 7)
     assert_eq!(top.get_int(&vm), 42);
 }
 #[test]
+fn import_qualified() {
+    let util = r#"
+pub fn foo(a: int, b) {
+  a * b
+}
+pub fn bar(a: int, b) {
+  a + b
+}
+"#;
+    let main = r#"
+use util.bar
+util.foo(6, 7) + bar(1, 1)
+"#;
+    let mut files: HashMap<PathBuf, String> = HashMap::default();
+    files.insert("main.en".into(), main.into());
+    files.insert("util.en".into(), util.into());
+    let file_provider = MockFileProvider::new(files);
+    let program = compile_bytecode("main.en", file_provider);
+    if let Err(e) = program {
+        panic!("{}", e);
+    }
+    let mut vm = Vm::new(program.unwrap());
+    vm.run();
+    let top = vm.top();
+    assert_eq!(top.get_int(&vm), 44);
+}
+#[test]
+fn import_nested_directory() {
+    let http = r#"
+pub type Status = Ok | NotFound
+pub fn status_code(s: Status) -> int {
+  match s {
+    .Ok -> 200,
+    .NotFound -> 404
+  }
+}
+"#;
+    let main = r#"
+use net/http except status_code
+let f = http.status_code
+f(http.Status.NotFound) + http.status_code(Status.Ok)
+"#;
+    let mut files: HashMap<PathBuf, String> = HashMap::default();
+    files.insert("main.en".into(), main.into());
+    files.insert("net/http.en".into(), http.into());
+    let file_provider = MockFileProvider::new(files);
+    let program = compile_bytecode("main.en", file_provider);
+    if let Err(e) = program {
+        panic!("{}", e);
+    }
+    let mut vm = Vm::new(program.unwrap());
+    vm.run();
+    let top = vm.top();
+    assert_eq!(top.get_int(&vm), 604);
+}
+#[test]
+fn import_private_item() {
+    let util = r#"
+fn secret() -> int {
+  42
+}
+pub fn open() -> int {
+  secret()
+}
+"#;
+    let main = r#"
+use util
+util.secret()
+"#;
+    let mut files: HashMap<PathBuf, String> = HashMap::default();
+    files.insert("main.en".into(), main.into());
+    files.insert("util.en".into(), util.into());
+    let file_provider = MockFileProvider::new(files);
+    let Err(e) = compile_bytecode("main.en", file_provider) else {
+        panic!("expected private item error");
+    };
+    assert!(e.to_string().contains("This item is private to `util`"));
+}
+#[test]
+fn import_cycle() {
+    let a = r#"
+use b
+pub fn foo() -> int {
+  1
+}
+"#;
+    let b = r#"
+use a
+pub fn bar() -> int {
+  2
+}
+"#;
+    let main = r#"
+use a
+foo()
+"#;
+    let mut files: HashMap<PathBuf, String> = HashMap::default();
+    files.insert("main.en".into(), main.into());
+    files.insert("a.en".into(), a.into());
+    files.insert("b.en".into(), b.into());
+    let file_provider = MockFileProvider::new(files);
+    let Err(e) = compile_bytecode("main.en", file_provider) else {
+        panic!("expected import cycle error");
+    };
+    assert!(e.to_string().contains("cycle: a -> b -> a"));
+}
+#[test]
+fn import_items_of_one_module() {
+    let util = r#"
+pub fn foo() -> int {
+  1
+}
+pub fn bar() -> int {
+  2
+}
+"#;
+    let main = r#"
+use util.foo
+use util.bar
+use util
+use util
+foo() * 100 + bar() * 10 + util.foo()
+"#;
+    let mut files: HashMap<PathBuf, String> = HashMap::default();
+    files.insert("main.en".into(), main.into());
+    files.insert("util.en".into(), util.into());
+    let file_provider = MockFileProvider::new(files);
+    let program = unwrap_or_panic(compile_bytecode("main.en", file_provider));
+    let mut vm = Vm::new(program);
+    vm.run();
+    assert_eq!(vm.top().get_int(&vm), 121);
+}
+#[test]
+fn import_name_clash() {
+    let red = r#"
+pub type Color = | Red | Crimson
+"#;
+    let blue = r#"
+pub type Color = | Blue | Navy
+"#;
+    let main = r#"
+use red
+use blue
+1
+"#;
+    let mut files: HashMap<PathBuf, String> = HashMap::default();
+    files.insert("main.en".into(), main.into());
+    files.insert("red.en".into(), red.into());
+    files.insert("blue.en".into(), blue.into());
+    let file_provider = MockFileProvider::new(files);
+    let Err(e) = compile_bytecode("main.en", file_provider) else {
+        panic!("expected name clash error");
+    };
+    assert!(e.to_string().contains("`Color` was declared more than once"));
+}
+#[test]
+fn import_modules_with_the_same_name() {
+    let mut files: HashMap<PathBuf, String> = HashMap::default();
+    files.insert("main.en".into(), "use a/util\nuse b/util\nx()".into());
+    files.insert("a/util.en".into(), "pub fn x() -> int { 1 }".into());
+    files.insert("b/util.en".into(), "pub fn y() -> int { 2 }".into());
+    let Err(e) = compile_bytecode("main.en", MockFileProvider::new(files)) else {
+        panic!("expected name clash error");
+    };
+    let e = e.to_string();
+    assert!(e.contains("`util` was declared more than once"), "{e}");
+    assert!(e.contains("main.en:2:5"), "{e}");
+}
+#[test]
+fn import_missing_module() {
+    let main = r#"
+use nowhere/util
+1
+"#;
+    let mut files: HashMap<PathBuf, String> = HashMap::default();
+    files.insert("main.en".into(), main.into());
+    let file_provider = MockFileProvider::new(files);
+    let Err(e) = compile_bytecode("main.en", file_provider) else {
+        panic!("expected missing module error");
+    };
+    assert!(e.to_string().contains("Could not find module `nowhere/util`"));
+}
+#[test]
 fn format_append() {
     let src = r#"
 format_append(format_append(123, true), false)
@@ -1103,7 +1482,7 @@ p.fullname() & " " & c.fullname()
     let mut vm = Vm::new(program);
     vm.run();
     let top = vm.top();
-    assert_eq!(top.view_string(&vm), "Aaditya Raj  red!");
+    assert_eq!(top.view_string(&vm), "Aaditya Raj red!");
 }
 #[test]
 fn member_function_fully_qualified() {
@@ -1140,7 +1519,7 @@ Person.fullname(p) & " " & Color.shout(Color.Red)
     let mut vm = Vm::new(program);
     vm.run();
     let top = vm.top();
-    assert_eq!(top.view_string(&vm), "Aaditya Raj  red!");
+    assert_eq!(top.view_string(&vm), "Aaditya Raj red!");
 }
 #[test]
 fn interface_method_fully_qualified() {
@@ -1274,4 +1653,333 @@ sum
     vm.run();
     let top = vm.top();
     assert_eq!(top.get_int(&vm), 6)
-}
\ No newline at end of file
+}
+#[test]
+fn test_functions() {
+    let src = r#"
+fn double(n) = n * 2
+test fn doubles() {
+    assert_eq(double(2), 4)
+}
+test fn wrong() {
+    assert_eq(double(2), 5)
+}
+test fn asserts() {
+    assert(double(0) == 0)
+}
+double(1)
+"#;
+    let program = unwrap_or_panic(compile_tests(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let names: Vec<_> = program.tests().iter().map(|test| test.name.as_str()).collect();
+    assert_eq!(names, vec!["doubles", "wrong", "asserts"]);
+    assert_eq!(program.tests()[1].line, 6);
+    for test in program.tests() {
+        let mut vm = Vm::for_test(program.clone(), test);
+        vm.run();
+        match vm.status() {
+            VmStatus::Done => assert_ne!(test.name, "wrong"),
+            VmStatus::Error(err) => {
+                assert_eq!(test.name, "wrong");
+                assert!(err.to_string().contains("left: 4"));
+                // the assert is a tail call, but the line that made it is kept
+                let caller = err.to_string().lines().nth(5).unwrap().to_string();
+                assert_eq!(caller, "    main.en:7      in `wrong`");
+            }
+            _ => panic!(),
+        }
+    }
+}
+#[test]
+fn stack_overflow() {
+    let src = r#"
+fn depth(n) {
+    if n == 0 { 0 } else { 1 + depth(n - 1) }
+}
+depth(1000000)
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.run();
+    let VmStatus::Error(err) = vm.status() else { panic!() };
+    let err = err.to_string();
+    assert!(err.starts_with("error: stack overflow"), "{err}");
+    // the recursive frames are shown once
+    assert!(err.contains("main.en:3 in `depth` repeated 100000 times"), "{err}");
+    assert!(err.lines().count() < 10, "{err}");
+}
+#[test]
+fn stack_limits() {
+    let src = r#"
+fn depth(n) {
+    if n == 0 { 0 } else { 1 + depth(n - 1) }
+}
+depth(40)
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program.clone());
+    vm.set_max_call_depth(50);
+    vm.run();
+    assert_eq!(vm.top().get_int(&vm), 40);
+
+    let mut vm = Vm::new(program.clone());
+    vm.set_max_call_depth(30);
+    vm.run();
+    let VmStatus::Error(err) = vm.status() else { panic!() };
+    assert!(err.to_string().contains("repeated 30 times"), "{err}");
+
+    let mut vm = Vm::new(program);
+    vm.set_max_stack_size(20);
+    vm.run();
+    let VmStatus::Error(err) = vm.status() else { panic!() };
+    assert!(err.to_string().starts_with("error: stack overflow"), "{err}");
+}
+#[test]
+fn fuel() {
+    let src = r#"
+var i = 0
+while true {
+    i = i + 1
+}
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.set_fuel(Some(1000));
+    vm.run();
+    let VmStatus::OutOfSteps = vm.status() else { panic!() };
+    assert_eq!(vm.fuel(), Some(0));
+    let err = vm.limit_error().unwrap().to_string();
+    assert!(err.starts_with("error: instruction limit reached"), "{err}");
+    // the embedder can top up and resume
+    vm.set_fuel(Some(1000));
+    vm.run();
+    let VmStatus::OutOfSteps = vm.status() else { panic!() };
+
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file("1 + 2"),
+    ));
+    let mut vm = Vm::new(program);
+    vm.set_fuel(Some(1000));
+    vm.run();
+    let VmStatus::Done = vm.status() else { panic!() };
+    assert!(vm.fuel().unwrap() < 1000);
+    assert!(vm.limit_error().is_none());
+}
+#[test]
+fn deadline() {
+    let src = r#"
+var i = 0
+while true {
+    i = i + 1
+}
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.set_deadline(Some(Instant::now() + Duration::from_millis(20)));
+    vm.set_deadline_check_interval(100);
+    vm.run();
+    let VmStatus::Timeout = vm.status() else { panic!() };
+    let err = vm.limit_error().unwrap().to_string();
+    assert!(err.starts_with("error: time limit reached"), "{err}");
+    vm.set_deadline(Some(Instant::now() + Duration::from_millis(20)));
+    vm.run();
+    let VmStatus::Timeout = vm.status() else { panic!() };
+}
+#[test]
+fn memory_limit() {
+    let src = r#"
+let xs: array<string> = []
+while true {
+    xs.push("hello" & "world")
+}
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.set_max_memory(Some(100_000));
+    vm.run();
+    let VmStatus::OutOfMemory = vm.status() else { panic!() };
+    assert!(vm.memory_usage() > 100_000);
+    let err = vm.limit_error().unwrap().to_string();
+    assert!(err.starts_with("error: memory limit reached"), "{err}");
+    vm.set_max_memory(Some(200_000));
+    vm.run();
+    let VmStatus::OutOfMemory = vm.status() else { panic!() };
+    assert!(vm.memory_usage() > 200_000);
+
+    // garbage is collected before the limit is reached
+    let src = r#"
+var i = 0
+while i < 10000 {
+    let s = "hello" & "world"
+    i = i + 1
+}
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.set_max_memory(Some(10_000));
+    vm.run();
+    let VmStatus::Done = vm.status() else { panic!() };
+}
+#[cfg(feature = "ffi")]
+#[test]
+fn sandbox() {
+    let mut files = HashMap::new();
+    files.insert(
+        PathBuf::from("main.en"),
+        "use random\nrandom.random_int(1, 10)".to_string(),
+    );
+    files.insert(
+        PathBuf::from("random.en"),
+        "pub foreign fn random_int(min: int, max: int) -> int".to_string(),
+    );
+    let program = unwrap_or_panic(compile_bytecode("main.en", MockFileProvider::new(files)));
+    let mut vm = Vm::new(program);
+    vm.set_sandboxed(true);
+    vm.run();
+    let VmStatus::Error(err) = vm.status() else { panic!() };
+    let err = err.to_string();
+    assert!(err.starts_with("error: can't load shared library"), "{err}");
+    assert!(err.contains("eon_module_random"), "{err}");
+}
+#[cfg(feature = "ffi")]
+unsafe extern "C" fn shout(
+    vm: *mut std::ffi::c_void,
+    vm_funcs: *const eon_core::addons::EonVmFunctions,
+) {
+    use eon_core::addons::VmFfiType;
+    unsafe {
+        let vm_funcs = &*vm_funcs;
+        let s = String::from_vm_unsafe(vm, vm_funcs);
+        s.to_uppercase().to_vm_unsafe(vm, vm_funcs);
+    }
+}
+#[cfg(feature = "ffi")]
+static GREET: eon_core::addons::StaticModule = eon_core::addons::StaticModule {
+    name: "greet",
+    files: &[("greet.en", "pub foreign fn shout(s: string) -> string")],
+    functions: &[("EON_ffi$greet$shout", shout)],
+};
+#[cfg(feature = "ffi")]
+#[test]
+fn static_module() {
+    // no shared library is loaded for a linked module
+    let vm = crate::helper::run_with_modules("use greet\nshout(\"hi\")", &[&GREET]);
+    assert_eq!(vm.top().view_string(&vm), "HI");
+}
+#[cfg(feature = "ffi")]
+#[test]
+fn project_files_come_before_static_modules() {
+    let dir = std::env::temp_dir().join(format!("eon_static_module_clash_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("main.en"), "use greet\nshout(\"hi\")").unwrap();
+    std::fs::write(
+        dir.join("greet.en"),
+        "pub fn shout(s: string) -> string { s & \"!\" }",
+    )
+    .unwrap();
+    let mut file_provider = eon_core::OsFileProvider::with_roots(vec![dir.clone()], dir.clone());
+    file_provider.add_static_module(&GREET);
+    let program = compile_bytecode("main.en", file_provider);
+    std::fs::remove_dir_all(&dir).unwrap();
+    let mut vm = Vm::new(unwrap_or_panic(program));
+    vm.add_static_module(&GREET);
+    vm.run();
+    let VmStatus::Done = vm.status() else {
+        panic!("{:?}", vm.status())
+    };
+    assert_eq!(vm.top().view_string(&vm), "hi!");
+}
+#[cfg(feature = "ffi")]
+unsafe extern "C" fn or_default(
+    vm: *mut std::ffi::c_void,
+    vm_funcs: *const eon_core::addons::EonVmFunctions,
+) {
+    use eon_core::addons::VmFfiType;
+    unsafe {
+        let vm_funcs = &*vm_funcs;
+        let (n, default) = <(Option<i64>, i64)>::from_vm_unsafe(vm, vm_funcs);
+        n.unwrap_or(default).to_vm_unsafe(vm, vm_funcs);
+    }
+}
+#[cfg(feature = "ffi")]
+static OPTIONS: eon_core::addons::StaticModule = eon_core::addons::StaticModule {
+    name: "options",
+    files: &[(
+        "options.en",
+        "pub foreign fn or_default(pair: (option<int>, int)) -> int",
+    )],
+    functions: &[("EON_ffi$options$or_default", or_default)],
+};
+#[cfg(feature = "ffi")]
+#[test]
+fn foreign_option_arguments() {
+    let src = "use options
+or_default((.none, 5)) + or_default((.some(10), 0))";
+    let vm = crate::helper::run_with_modules(src, &[&OPTIONS]);
+    assert_eq!(vm.top().get_int(&vm), 15);
+}
+#[test]
+fn zero_arg_function_with_locals() {
+    let src = r#"
+fn answer() -> int {
+    let x = 40
+    x + 2
+}
+answer()
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.run();
+    let top = vm.top();
+    assert_eq!(top.get_int(&vm), 42);
+}
+#[test]
+fn zero_arg_functions_keep_the_callers_locals() {
+    let src = r#"
+fn seven() -> int {
+    let a = 3
+    let b = 4
+    a + b
+}
+fn twice() -> int {
+    let x = seven()
+    let y = seven()
+    x * 10 + y
+}
+let before = 1
+let n = twice()
+before + n + seven()
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.run();
+    let top = vm.top();
+    assert_eq!(top.get_int(&vm), 85);
+}
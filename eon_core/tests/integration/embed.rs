@@ -0,0 +1,90 @@
+use crate::helper::unwrap_or_panic;
+use eon_core::MockFileProvider;
+use eon_core::compile_bytecode;
+use eon_core::embed::CallError;
+use eon_core::vm::{VmStatus, Vm};
+const SRC: &str = r#"
+fn add(a: int, b: int) -> int {
+    a + b
+}
+fn greet(name: string) -> string {
+    "hello " & name
+}
+fn total(xs: array<int>) -> int {
+    var sum = 0
+    for x in xs {
+        sum = sum + x
+    }
+    sum
+}
+fn lookup(xs: array<int>, i: int) -> option<int> {
+    if i < xs.len() { option.some(xs[i]) } else { option.none }
+}
+fn answer() -> int {
+    let x = 40
+    x + 2
+}
+fn crash(n: int) -> int {
+    n / 0
+}
+fn identity(x: T) -> T {
+    x
+}
+add(1, 2)
+"#;
+fn vm() -> Vm {
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(SRC),
+    ));
+    let names: Vec<_> = program.functions().iter().map(|f| f.name.as_str()).collect();
+    // generic functions aren't exported
+    assert_eq!(names, vec!["add", "greet", "total", "lookup", "answer", "crash"]);
+    assert_eq!(program.function("lookup").unwrap().args, vec!["array<int>", "int"]);
+    assert_eq!(program.function("lookup").unwrap().ret, "option<int>");
+    Vm::new(program)
+}
+#[test]
+fn call_functions() {
+    let mut vm = vm();
+    assert_eq!(vm.call::<_, i64>("add", (2, 3)).unwrap(), 5);
+    assert_eq!(
+        vm.call::<_, String>("greet", ("eon".to_string(),)).unwrap(),
+        "hello eon"
+    );
+    assert_eq!(vm.call::<_, i64>("total", (vec![1, 2, 3, 4],)).unwrap(), 10);
+    assert_eq!(
+        vm.call::<_, Option<i64>>("lookup", (vec![5, 6], 1)).unwrap(),
+        Some(6)
+    );
+    assert_eq!(vm.call::<_, Option<i64>>("lookup", (vec![5, 6], 2)).unwrap(), None);
+    assert_eq!(vm.call::<_, i64>("answer", ()).unwrap(), 42);
+    // calls can follow a run of <main>
+    let mut vm = self::vm();
+    vm.run();
+    let VmStatus::Done = vm.status() else { panic!() };
+    assert_eq!(vm.call::<_, i64>("add", (20, 22)).unwrap(), 42);
+}
+#[test]
+fn call_errors() {
+    let mut vm = vm();
+    let Err(CallError::UnknownFunction(name)) = vm.call::<_, i64>("missing", ()) else {
+        panic!()
+    };
+    assert_eq!(name, "missing");
+    let err = vm.call::<_, i64>("add", (1, "two".to_string())).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "function has type `fn(int, int) -> int`, but was called as `fn(int, string) -> int`"
+    );
+    assert!(matches!(
+        vm.call::<_, String>("add", (1, 2)),
+        Err(CallError::WrongSignature { .. })
+    ));
+    let Err(CallError::Error(err)) = vm.call::<_, i64>("crash", (1,)) else {
+        panic!()
+    };
+    assert!(err.to_string().contains("division by zero"), "{err}");
+    // the vm can be reused after an error
+    assert_eq!(vm.call::<_, i64>("add", (1, 1)).unwrap(), 2);
+}
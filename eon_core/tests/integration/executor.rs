@@ -0,0 +1,118 @@
+use crate::helper::unwrap_or_panic;
+use eon_core::executor::{Executor, HostReply, TaskId};
+use eon_core::vm::{ValueTrait, Vm, VmStatus};
+use eon_core::{MockFileProvider, compile_bytecode};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+fn vm(src: &str) -> Vm {
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    Vm::new(program)
+}
+fn host_function_name(vm: &Vm, idx: u16) -> String {
+    vm.host_functions()[idx as usize].name.clone()
+}
+#[test]
+fn round_robin() {
+    let src = r#"
+host fn mark() -> void
+var i = 0
+while i < 3 {
+    var j = 0
+    while j < 1000 {
+        j = j + 1
+    }
+    mark()
+    i = i + 1
+}
+"#;
+    let order = Rc::new(RefCell::new(vec![]));
+    let order2 = order.clone();
+    let mut executor = Executor::new(move |vm, _, completer| {
+        order2.borrow_mut().push(completer.task());
+        vm.push_nil();
+        vm.clear_pending_host_func();
+        HostReply::Ready
+    });
+    executor.set_step_budget(100);
+    for _ in 0..3 {
+        executor.spawn(vm(src));
+    }
+    let finished = executor.run();
+    assert_eq!(finished.len(), 3);
+    for (_, vm) in &finished {
+        let VmStatus::Done = vm.status() else { panic!() };
+    }
+    // no task runs ahead of the others
+    let expected: Vec<TaskId> = (0..9).map(|i| TaskId(i % 3)).collect();
+    assert_eq!(*order.borrow(), expected);
+}
+#[test]
+fn sleeping_and_waiting_tasks() {
+    let sleeper = r#"
+host fn sleep_ms(ms: int) -> void
+sleep_ms(50)
+1
+"#;
+    let fetcher = r#"
+host fn fetch(n: int) -> int
+fetch(20) + 1
+"#;
+    let mut executor = Executor::new(|vm, idx, completer| {
+        match host_function_name(vm, idx).as_str() {
+            "sleep_ms" => {
+                let ms = vm.pop_int();
+                HostReply::Sleep(Instant::now() + Duration::from_millis(ms as u64))
+            }
+            "fetch" => {
+                let n = vm.pop_int();
+                std::thread::spawn(move || {
+                    std::thread::sleep(Duration::from_millis(5));
+                    completer.complete(n * 2);
+                });
+                HostReply::Wait
+            }
+            name => HostReply::Error(format!("unknown host function {name}")),
+        }
+    });
+    let start = Instant::now();
+    let sleeping = executor.spawn(vm(sleeper));
+    let fetching = executor.spawn(vm(fetcher));
+    let finished = executor.run();
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    // the fetch doesn't wait for the sleep
+    assert_eq!(finished[0].0, fetching);
+    assert_eq!(finished[1].0, sleeping);
+    let vm = &finished[0].1;
+    assert_eq!(vm.top().get_int(vm), 41);
+    let vm = &finished[1].1;
+    assert_eq!(vm.top().get_int(vm), 1);
+}
+#[test]
+fn failing_host_functions() {
+    let src = r#"
+host fn fetch(n: int) -> int
+fetch(1)
+"#;
+    let mut executor = Executor::new(|vm, _, completer| {
+        let n = vm.pop_int();
+        if n == 1 {
+            // dropping the completer fails the task instead of leaving it waiting
+            drop(completer);
+            HostReply::Wait
+        } else {
+            HostReply::Error("no network".to_string())
+        }
+    });
+    executor.spawn(vm(src));
+    executor.spawn(vm("host fn fetch(n: int) -> int\nfetch(2)"));
+    let mut finished = executor.run();
+    finished.sort_by_key(|(id, _)| *id);
+    let VmStatus::Error(err) = finished[0].1.status() else { panic!() };
+    assert!(err.to_string().contains("never completed"), "{err}");
+    let VmStatus::Error(err) = finished[1].1.status() else { panic!() };
+    assert!(err.to_string().starts_with("error in host function: no network"), "{err}");
+}
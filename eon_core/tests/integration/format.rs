@@ -0,0 +1,178 @@
+use crate::helper::unwrap_or_panic;
+use eon_core::FileData;
+use eon_core::{MockFileProvider, compile_bytecode};
+use eon_core::formatter::format;
+use std::path::PathBuf;
+fn fmt(src: &str) -> String {
+    let file_data = FileData::new("test.en".into(), "test.en".into(), src.to_owned());
+    unwrap_or_panic(format(&file_data))
+}
+#[test]
+fn format_is_idempotent_on_examples() {
+    let mut files = vec![];
+    for dir in ["../examples", "../modules"] {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path: PathBuf = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "en") {
+                files.push(path);
+            }
+        }
+    }
+    assert!(!files.is_empty());
+    for path in files {
+        let src = std::fs::read_to_string(&path).unwrap();
+        let once = fmt(&src);
+        let twice = fmt(&once);
+        assert_eq!(once, twice, "formatting {} is not idempotent", path.display());
+    }
+}
+#[test]
+fn format_canonical_layout() {
+    let src = r#"
+
+
+type Shape =
+    | Circle(float)
+    | Rect(float,float)
+type Point = { x: int
+    y: int }
+fn area(s:Shape)->float { match s { .Circle(r) -> 3.14*r*r, .Rect(w,h) -> w*h } }
+
+
+
+fn main() {
+    let p = Point(1,2)
+    if (p.x==1) { println("one") }
+}
+"#;
+    let expected = r#"type Shape =
+  | Circle(float)
+  | Rect(float, float)
+type Point = {
+  x: int
+  y: int
+}
+fn area(s: Shape) -> float {
+  match s {
+    .Circle(r) -> 3.14 * r * r,
+    .Rect(w, h) -> w * h
+  }
+}
+
+fn main() {
+  let p = Point(1, 2)
+  if p.x == 1 {
+    println("one")
+  }
+}
+"#;
+    assert_eq!(fmt(src), expected);
+}
+#[test]
+fn format_wraps_long_lines() {
+    let src = r#"fn f(x) {
+  match x {
+    .some(value) -> a_function_with_a_long_name(first_argument, second_argument, third_argument_that_overflows),
+    _ -> [first_element_value, second_element_value, third_element_value, fourth_element_value, 5, 6],
+  }
+}
+"#;
+    let expected = r#"fn f(x) {
+  match x {
+    .some(value) -> a_function_with_a_long_name(
+      first_argument,
+      second_argument,
+      third_argument_that_overflows,
+    ),
+    _ -> [
+      first_element_value,
+      second_element_value,
+      third_element_value,
+      fourth_element_value,
+      5,
+      6,
+    ]
+  }
+}
+"#;
+    assert_eq!(fmt(src), expected);
+}
+#[test]
+fn format_keeps_comments() {
+    let src = r#"// header
+use list   // trailing
+/* block
+   comment */
+type Shape =
+    | Circle(float) // radius
+    // between variants
+    | Rect(float, float)
+fn area(s: Shape) -> float {
+    // leading
+    match s {
+        .Circle(r) -> 3.14 * r * r,  // circle
+        .Rect(w, h) -> w * h,
+    }
+    // dangling
+}
+// final
+"#;
+    let expected = r#"// header
+use list // trailing
+/* block
+   comment */
+type Shape =
+  | Circle(float) // radius
+  // between variants
+  | Rect(float, float)
+fn area(s: Shape) -> float {
+  // leading
+  match s {
+    .Circle(r) -> 3.14 * r * r, // circle
+    .Rect(w, h) -> w * h
+  }
+  // dangling
+}
+// final
+"#;
+    assert_eq!(fmt(src), expected);
+}
+#[test]
+fn format_reports_syntax_errors() {
+    let file_data = FileData::new("test.en".into(), "test.en".into(), "let x = \n".into());
+    assert!(format(&file_data).is_err());
+}
+#[test]
+fn format_keeps_struct_literals_in_conditions_parenthesized() {
+    let src = r#"type Point = {
+  x: int
+  y: int
+}
+let x = 1
+let y = 4
+if (Point { x, y }).y == 4 { x } else { y }
+while 3 < (Point { x, y }).x { let p = Point { x, y } }
+"#;
+    let expected = r#"type Point = {
+  x: int
+  y: int
+}
+let x = 1
+let y = 4
+if (Point { x, y }).y == 4 {
+  x
+} else {
+  y
+}
+while 3 < (Point { x, y }).x {
+  let p = Point { x, y }
+}
+"#;
+    assert_eq!(fmt(src), expected);
+    assert_eq!(fmt(expected), expected);
+    // and the output still parses
+    unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(expected),
+    ));
+}
@@ -0,0 +1,102 @@
+use crate::helper::unwrap_or_panic;
+use eon_core::vm::{ValueTrait, Vm, VmStatus};
+use eon_core::{MockFileProvider, compile_bytecode};
+use std::ffi::c_void;
+use std::sync::Mutex;
+static FINALIZED: Mutex<Vec<i64>> = Mutex::new(vec![]);
+unsafe extern "C" fn finalize(ptr: *mut c_void) {
+    let n = unsafe { Box::from_raw(ptr as *mut i64) };
+    FINALIZED.lock().unwrap().push(*n);
+}
+fn run(vm: &mut Vm) {
+    let names: Vec<_> = vm.host_functions().iter().map(|f| f.name.clone()).collect();
+    loop {
+        vm.run();
+        let Some(index) = vm.get_pending_host_func() else {
+            break;
+        };
+        match names[index as usize].as_str() {
+            "counter" => {
+                let start = vm.pop_int();
+                let ptr = Box::into_raw(Box::new(start)) as *mut c_void;
+                unsafe { vm.push_handle(ptr, 7, Some(finalize)) };
+            }
+            "bump" => {
+                let view = vm.view_handle();
+                assert_eq!(view.tag, 7);
+                vm.pop();
+                let n = unsafe { &mut *(view.ptr as *mut i64) };
+                *n += 1;
+                vm.push_int(*n);
+            }
+            "same" => {
+                // returns the handle it was given, which keeps a single owner
+                let view = vm.view_handle();
+                vm.pop();
+                unsafe { vm.push_handle_view(view) };
+            }
+            "collect" => {
+                vm.gc();
+                vm.push_nil();
+            }
+            name => panic!("unexpected host function {name}"),
+        }
+        vm.clear_pending_host_func();
+    }
+}
+#[test]
+fn handles_are_finalized() {
+    let src = r#"
+host fn counter(start: int) -> handle<int>
+host fn bump(c: handle<int>) -> int
+host fn same(c: handle<int>) -> handle<int>
+host fn collect() -> void
+fn temporary() -> int {
+    let t = counter(100)
+    bump(t)
+}
+let c = same(counter(10))
+temporary()
+collect()
+bump(c)
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    run(&mut vm);
+    let VmStatus::Done = vm.status() else {
+        panic!("{:?}", vm.status())
+    };
+    assert_eq!(vm.top().get_int(&vm), 11);
+    // only the handle made in `temporary` was unreachable when `collect` ran
+    assert_eq!(*FINALIZED.lock().unwrap(), vec![101]);
+    drop(vm);
+    assert_eq!(*FINALIZED.lock().unwrap(), vec![101, 11]);
+}
+#[test]
+fn handle_type_errors() {
+    let src = r#"
+host fn counter(start: int) -> handle<int>
+let c: handle<string> = counter(1)
+"#;
+    assert!(compile_bytecode("main.en", MockFileProvider::single_file(src)).is_err());
+}
+#[test]
+fn handle_tags_check_the_type() {
+    // two types with the same name
+    mod a {
+        pub struct Counter(pub i64);
+    }
+    mod b {
+        pub struct Counter;
+    }
+    let mut handle = eon_core::addons::Handle::new(a::Counter(3));
+    unsafe {
+        assert!(handle.get::<b::Counter>().is_none());
+        assert!(handle.get::<i64>().is_none());
+        handle.get_mut::<a::Counter>().unwrap().0 += 1;
+        assert_eq!(handle.get::<a::Counter>().unwrap().0, 4);
+    }
+}
@@ -6,4 +6,42 @@ pub fn unwrap_or_panic<T>(result: Result<T, ErrorSummary>) -> T {
             panic!("{}", e.to_string_ansi());
         }
     }
-}
\ No newline at end of file
+}
+// compiles `src` as main.en along with the Eon files of the linked `modules`
+#[cfg(feature = "ffi")]
+pub fn vm_with_modules(
+    src: &str,
+    modules: &[&'static eon_core::addons::StaticModule],
+) -> eon_core::vm::Vm {
+    use eon_core::vm::Vm;
+    use eon_core::{MockFileProvider, compile_bytecode};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    let mut files = HashMap::new();
+    files.insert(PathBuf::from("main.en"), src.to_string());
+    for module in modules {
+        for (path, source) in module.files {
+            files.insert(PathBuf::from(path), source.to_string());
+        }
+    }
+    let program = unwrap_or_panic(compile_bytecode("main.en", MockFileProvider::new(files)));
+    let mut vm = Vm::new(program);
+    for module in modules {
+        vm.add_static_module(module);
+    }
+    vm
+}
+// like `vm_with_modules`, but runs the program to the end
+#[cfg(feature = "ffi")]
+pub fn run_with_modules(
+    src: &str,
+    modules: &[&'static eon_core::addons::StaticModule],
+) -> eon_core::vm::Vm {
+    use eon_core::vm::VmStatus;
+    let mut vm = vm_with_modules(src, modules);
+    vm.run();
+    let VmStatus::Done = vm.status() else {
+        panic!("{:?}", vm.status())
+    };
+    vm
+}
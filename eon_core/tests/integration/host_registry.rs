@@ -0,0 +1,100 @@
+use crate::helper::unwrap_or_panic;
+use eon_core::host::HostError;
+use eon_core::vm::{ValueTrait, Vm, VmStatus};
+use eon_core::{HostRegistry, MockFileProvider, compile_bytecode_with_registry};
+use std::cell::RefCell;
+use std::rc::Rc;
+#[test]
+fn registered_functions() {
+    let log = Rc::new(RefCell::new(vec![]));
+    let mut registry = HostRegistry::new();
+    registry
+        .register("add", "(a: int, b: int) -> int", |(a, b): (i64, i64)| a + b)
+        .register("shout", "(s: string) -> string", |(s,): (String,)| {
+            s.to_uppercase()
+        })
+        .register("lookup", "(i: int) -> option<int>", |(i,): (i64,)| {
+            (i < 3).then_some(i * 10)
+        });
+    let log2 = log.clone();
+    registry.bind("print_string", move |(s,): (String,)| {
+        log2.borrow_mut().push(s);
+    });
+    let src = r#"
+println(shout("hi"))
+let x = match lookup(2) {
+    .some(n) -> n,
+    .none -> 0
+}
+add(x, 1)
+"#;
+    let program = unwrap_or_panic(compile_bytecode_with_registry(
+        "main.en",
+        &registry,
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    // readline isn't used, but the program still declares it
+    assert_eq!(
+        registry.check(&vm),
+        Err(HostError::Unimplemented("readline".to_string()))
+    );
+    let VmStatus::Done = registry.run(&mut vm).unwrap() else {
+        panic!()
+    };
+    assert_eq!(vm.top().get_int(&vm), 21);
+    assert_eq!(*log.borrow(), vec!["HI\n".to_string()]);
+}
+#[test]
+fn registry_errors() {
+    let mut registry = HostRegistry::with_stdio();
+    registry.register("twice", "(n: int) -> int", |(n,): (i64,)| n * 2);
+    // a typo in the signature is reported like any other compile error
+    let mut bad = HostRegistry::new();
+    bad.register("twice", "(n: innt) -> int", |(n,): (i64,)| n * 2);
+    assert!(
+        compile_bytecode_with_registry("main.en", &bad, MockFileProvider::single_file("twice(2)"))
+            .is_err()
+    );
+
+    let program = unwrap_or_panic(compile_bytecode_with_registry(
+        "main.en",
+        &registry,
+        MockFileProvider::single_file("twice(21)"),
+    ));
+    let mut vm = Vm::new(program.clone());
+    registry.check(&vm).unwrap();
+    registry.run(&mut vm).unwrap();
+    assert_eq!(vm.top().get_int(&vm), 42);
+
+    // the closure's types must match the declaration
+    registry.bind("twice", |(s,): (String,)| s);
+    let mut vm = Vm::new(program);
+    let err = registry.run(&mut vm).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "host function `twice` has type `fn(int) -> int`, but was registered as `fn(string) -> string`"
+    );
+}
+#[test]
+fn failing_host_functions() {
+    let mut registry = HostRegistry::with_stdio();
+    registry.try_bind("print_string", |(_,): (String,)| -> Result<(), String> {
+        Err("can't write to stdout: broken pipe".to_string())
+    });
+    let program = unwrap_or_panic(compile_bytecode_with_registry(
+        "main.en",
+        &registry,
+        MockFileProvider::single_file("let x = 1\nprintln(x)\nx"),
+    ));
+    let mut vm = Vm::new(program);
+    let VmStatus::Error(err) = registry.run(&mut vm).unwrap() else {
+        panic!()
+    };
+    assert!(
+        err.to_string()
+            .starts_with("error in host function: can't write to stdout: broken pipe"),
+        "{err}"
+    );
+    assert!(err.to_string().contains("main.en:2"), "{err}");
+}
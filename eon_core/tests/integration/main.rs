@@ -1,3 +1,14 @@
+mod callbacks;
+mod coverage;
+mod debugger;
 mod e2e_bytecode;
+mod embed;
+mod executor;
+mod format;
+mod handles;
+mod host_registry;
 mod helper;
+mod modules;
+mod optimize;
+mod profiler;
 mod typecheck;
\ No newline at end of file
@@ -0,0 +1,86 @@
+// Eon programs that use the native modules in modules/, linked statically
+#![cfg(feature = "ffi")]
+use crate::helper::{run_with_modules, vm_with_modules};
+use eon_core::embed::CallError;
+use eon_core::vm::ValueTrait;
+#[test]
+fn os_spawn() {
+    let src = r#"
+use os
+let options = ProcessOptions(.none, [], .none)
+match spawn(["sh", "-c", "while read line; do echo got $line; done; echo bye >&2"], options) {
+  .ok(p) -> {
+    let _ = process_write(p, "one" & newline & "two" & newline)
+    let first = match process_read_line(p) {
+      .ok(line) -> unwrap(line),
+      .err(msg) -> msg
+    }
+    match process_wait(p) {
+      .ok(out) -> first & "|" & out.stdout & "|" & out.stderr,
+      .err(msg) -> msg
+    }
+  },
+  .err(msg) -> msg
+}
+"#;
+    let vm = run_with_modules(src, &[&eon_module_os::MODULE]);
+    assert_eq!(vm.top().view_string(&vm), "got one|got two\n|bye\n");
+}
+#[test]
+fn random_seeded_sequences_repeat() {
+    let src = r#"
+use random
+fn sample() -> string {
+  let arr = [1, 2, 3, 4, 5, 6, 7, 8]
+  shuffle(arr)
+  "" & random_int(0, 1000) & " " & random_float(0.0, 1.0) & " " & unwrap(random_choice(arr)) & " " & arr
+}
+random_seed(42)
+let first = sample()
+random_seed(42)
+let second = sample()
+let rng = rng_new(42)
+let arr = [1, 2, 3]
+rng_shuffle(rng, arr)
+let third = "" & rng_int(rng, 0, 1000) & " " & rng_normal(rng, 0.0, 1.0) & " " & arr
+let rng2 = rng_new(42)
+let arr2 = [1, 2, 3]
+rng_shuffle(rng2, arr2)
+let fourth = "" & rng_int(rng2, 0, 1000) & " " & rng_normal(rng2, 0.0, 1.0) & " " & arr2
+fn none_of(m: option<int>) -> string {
+  match m {
+    .some(_) -> "some",
+    .none -> "none"
+  }
+}
+let empty: array<int> = []
+first & "|" & second & "|" & third & "|" & fourth & "|" & none_of(random_choice(empty)) & none_of(rng_choice(rng, empty))
+"#;
+    let vm = run_with_modules(src, &[&eon_module_random::MODULE]);
+    let out = vm.top().view_string(&vm);
+    let parts: Vec<_> = out.split('|').collect();
+    assert_eq!(parts[0], parts[1]);
+    assert_eq!(parts[2], parts[3]);
+    assert_eq!(parts[4], "nonenone");
+}
+#[test]
+fn call_before_run_loads_libraries() {
+    let src = r#"
+use random
+fn roll(seed: int) -> int {
+  let rng = rng_new(seed)
+  rng_int(rng, 0, 1000)
+}
+"#;
+    let mut vm = vm_with_modules(src, &[&eon_module_random::MODULE]);
+    let first = vm.call::<_, i64>("roll", (7,)).unwrap();
+    assert!((0..1000).contains(&first));
+    // the libraries are only loaded once
+    assert_eq!(vm.call::<_, i64>("roll", (7,)).unwrap(), first);
+    let mut vm = vm_with_modules(src, &[&eon_module_random::MODULE]);
+    vm.set_sandboxed(true);
+    let Err(CallError::Error(err)) = vm.call::<_, i64>("roll", (7,)) else {
+        panic!()
+    };
+    assert!(err.to_string().contains("sandbox"), "{err}");
+}
@@ -0,0 +1,157 @@
+use crate::helper::unwrap_or_panic;
+use eon_core::MockFileProvider;
+use eon_core::compile_bytecode;
+use eon_core::vm::{ValueTrait, Vm};
+// runs `src` and returns the instructions executed along with the vm
+fn run(src: &str) -> (u64, Vm) {
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.enable_profiling();
+    vm.run();
+    assert!(vm.is_done());
+    let profile = vm.profile().unwrap();
+    let executed = profile.lines.iter().map(|line| line.instructions).sum();
+    (executed, vm)
+}
+fn compile_error(src: &str) -> String {
+    match compile_bytecode("main.en", MockFileProvider::single_file(src)) {
+        Ok(_) => panic!("expected a compile error"),
+        Err(err) => err.to_string(),
+    }
+}
+#[test]
+fn fold_arithmetic() {
+    let (folded, vm) = run("let x = (1 + 2) * (3 + 4) - 10 / 3 mod 2\nx");
+    assert_eq!(vm.top().get_int(&vm), 20);
+    let (literal, _) = run("let x = 20\nx");
+    assert_eq!(folded, literal);
+
+    let (folded, vm) = run("let x = 1.5 * 2.0 + 0.25\nx");
+    assert_eq!(vm.top().get_float(&vm), 3.25);
+    let (literal, _) = run("let x = 3.25\nx");
+    assert_eq!(folded, literal);
+
+    let (folded, vm) = run("let x = 1 + 2 < 4 and 2.0 <= 3.0\nx");
+    assert!(vm.top().get_bool(&vm));
+    let (literal, _) = run("let x = true\nx");
+    assert_eq!(folded, literal);
+}
+#[test]
+fn fold_keeps_wrapping_semantics() {
+    let (_, vm) = run("let x = 9223372036854775807 + 1\nx");
+    assert_eq!(vm.top().get_int(&vm), i64::MIN);
+    let (_, vm) = run("let x = 3 ^ 64\nx");
+    assert_eq!(vm.top().get_int(&vm), 3i64.wrapping_pow(64));
+}
+#[test]
+fn fold_dead_branches() {
+    let (folded, vm) = run("let x = if 1 > 2 { 10 } else { 20 }\nx");
+    assert_eq!(vm.top().get_int(&vm), 20);
+    let (literal, _) = run("let x = 20\nx");
+    assert_eq!(folded, literal);
+    // an error in code that never runs isn't reported
+    let (_, vm) = run("let x = if false { 1 / 0 } else { 2 }\nx");
+    assert_eq!(vm.top().get_int(&vm), 2);
+}
+#[test]
+fn fold_reports_guaranteed_errors() {
+    let err = compile_error("let x = 5\nlet y = 10 / (3 - 3)\ny");
+    assert!(err.contains("Division by zero"), "{err}");
+    // the label points at the operation, not at the whole line
+    assert!(err.contains("main.en:2:9"), "{err}");
+    assert!(compile_error("let y = 1.0 / 0.0\ny").contains("Division by zero"));
+    assert!(compile_error("let y = 7 mod 0\ny").contains("Division by zero"));
+    let err = compile_error("let y = (0 - 9223372036854775807 - 1) mod (0 - 1)\ny");
+    assert!(err.contains("Integer overflow"), "{err}");
+}
+#[test]
+fn fold_errors_only_in_reachable_functions() {
+    // `f` is only exported to the embedder, so it may never run
+    let (_, vm) = run("fn f() -> int { 1 / 0 }\nlet x = 3\nx");
+    assert_eq!(vm.top().get_int(&vm), 3);
+    let err = compile_error("fn f() -> int { 1 / 0 }\nfn g() = f()\ng()");
+    assert!(err.contains("main.en:1:17"), "{err}");
+}
+// runs `vm` to the end a few instructions at a time and returns the deepest call stack seen
+fn max_call_depth(vm: &mut Vm) -> usize {
+    let mut depth = 0;
+    while !vm.is_done() {
+        vm.run_n_steps(10);
+        assert!(vm.get_error().is_none());
+        depth = depth.max(vm.call_depth());
+    }
+    depth
+}
+#[test]
+fn tail_calls_reuse_the_frame() {
+    let src = r#"fn count(n, acc) = if n == 0 acc else count(n - 1, acc + 1)
+fn start() {
+  let x = count(10000, 0)
+  x
+}
+start()
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    assert_eq!(max_call_depth(&mut vm), 2);
+    assert_eq!(vm.top().get_int(&vm), 10000);
+
+    let src = r#"let arr = [0]
+var i = 1
+while i < 500 {
+  arr.push(i)
+  i = i + 1
+}
+ToString.str(arr)
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    assert!(max_call_depth(&mut vm) < 10);
+    let s = vm.top().view_string(&vm);
+    assert!(s.starts_with("[ 0, 1, 2, "));
+    assert!(s.ends_with(", 498, 499 ]"));
+}
+#[test]
+fn tail_calls_keep_the_caller_in_stack_traces() {
+    let src = r#"fn fail(n) = if n == 0 { 1 / n } else fail(n - 1)
+fn start() {
+  let x = fail(3)
+  x
+}
+start()
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    vm.run();
+    let err = vm.get_error().unwrap().to_string();
+    assert!(err.contains("main.en:1 in `fail`"), "{err}");
+    assert!(err.contains("main.en:3 in `start`"), "{err}");
+    // the last tail call is shown and the other replaced frames of `fail` are counted
+    assert!(err.contains("main.en:1 in `fail` repeated 2 times"), "{err}");
+    assert!(err.contains("    ... 2 tail calls elided\n"), "{err}");
+    let fail_at = err.find("in `fail`").unwrap();
+    assert!(fail_at < err.find("elided").unwrap(), "{err}");
+    assert!(err.find("elided").unwrap() < err.find("in `start`").unwrap(), "{err}");
+}
+#[test]
+fn inline_small_wrappers() {
+    let (_, vm) = run("let x = 2\nlet y = 3\nlet z = Num.less_than_or_equal(x, y) and Num.greater_than(y, x)\nz");
+    assert!(vm.top().get_bool(&vm));
+    let profile = vm.profile().unwrap();
+    let names: Vec<_> = profile.functions.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["<main>"]);
+    // the inlined code counts as the caller's
+    assert!(profile.lines.iter().all(|line| line.file == "main.en"));
+}
@@ -0,0 +1,49 @@
+use crate::helper::unwrap_or_panic;
+use eon_core::MockFileProvider;
+use eon_core::compile_bytecode;
+use eon_core::vm::Vm;
+#[test]
+fn profile_counts_calls_and_lines() {
+    let src = r#"fn fib(n) = if n < 2 n else fib(n - 1) + fib(n - 2)
+fn twice() {
+  fib(5) + fib(5)
+}
+twice()
+"#;
+    let program = unwrap_or_panic(compile_bytecode(
+        "main.en",
+        MockFileProvider::single_file(src),
+    ));
+    let mut vm = Vm::new(program);
+    assert!(vm.profile().is_none());
+    vm.enable_profiling();
+    vm.run();
+    assert!(vm.is_done());
+    let profile = vm.profile().unwrap();
+
+    let fib = &profile.functions[0];
+    assert_eq!((fib.name.as_str(), fib.file.as_str()), ("fib", "main.en"));
+    assert_eq!(fib.calls, 30);
+    assert!(fib.inclusive_time >= fib.exclusive_time);
+    let twice = profile
+        .functions
+        .iter()
+        .find(|function| function.name == "twice")
+        .unwrap();
+    assert_eq!(twice.calls, 1);
+    assert!(twice.inclusive_time >= fib.inclusive_time);
+
+    assert_eq!((profile.lines[0].file.as_str(), profile.lines[0].line), ("main.en", 1));
+    assert_eq!(profile.lines[0].instructions, fib.instructions);
+    let total: u64 = profile.lines.iter().map(|line| line.instructions).sum();
+    let in_stacks: u64 = profile.stacks.iter().map(|(_, count)| count).sum();
+    assert_eq!(total, in_stacks);
+
+    let folded = profile.folded_stacks();
+    assert!(folded.contains("<main>;twice "));
+    assert!(folded.contains("<main>;twice;fib;fib;fib;fib "));
+    assert!(!folded.contains("fib;fib;fib;fib;fib;fib"));
+    let summary = profile.summary(1);
+    assert!(summary.contains("fib (main.en)"));
+    assert!(!summary.contains("twice (main.en)"));
+}
@@ -1,5 +1,6 @@
 use crate::helper::unwrap_or_panic;
 use eon_core::MockFileProvider;
+use eon_core::check;
 use eon_core::compile_bytecode;
 #[test]
 fn integer_operators() {
@@ -137,4 +138,92 @@ let x = person("Alice", 30)
 get_age(15)
 "#,
     );
-}
\ No newline at end of file
+}
+#[test]
+fn struct_literal_missing_field() {
+    should_fail(
+        r#"
+type Point = {
+    x: int
+    y: int
+}
+let p = Point { x: 1 }
+"#,
+    );
+}
+#[test]
+fn struct_literal_duplicate_field() {
+    should_fail(
+        r#"
+type Point = {
+    x: int
+    y: int
+}
+let p = Point { x: 1, y: 2, x: 3 }
+"#,
+    );
+}
+#[test]
+fn struct_literal_unknown_field() {
+    should_fail(
+        r#"
+type Point = {
+    x: int
+    y: int
+}
+let p = Point { x: 1, y: 2, z: 3 }
+"#,
+    );
+}
+#[test]
+fn struct_literal_bad_field_type() {
+    should_fail(
+        r#"
+type Point = {
+    x: int
+    y: int
+}
+let p = Point { x: 1, y: "2" }
+"#,
+    );
+}
+#[test]
+fn struct_update_not_a_struct() {
+    should_fail(
+        r#"
+let p = (1, 2)
+let q = { ..p, x: 1 }
+"#,
+    );
+}
+#[test]
+fn check_reports_locations() {
+    let src = r#"
+let x = 1
+let y: string = x
+let z = w
+"#;
+    unwrap_or_panic(check("main.en", MockFileProvider::single_file("let x = 1")));
+    let err = check("main.en", MockFileProvider::single_file(src)).unwrap_err();
+    let reports = err.reports();
+    assert_eq!(reports.len(), 2);
+    let unresolved = reports
+        .iter()
+        .find(|r| r.message == "Could not resolve identifier")
+        .unwrap();
+    assert_eq!(unresolved.severity, "error");
+    let label = &unresolved.labels[0];
+    assert_eq!(label.file.to_str(), Some("main.en"));
+    assert_eq!(&src[label.range.clone()], "w");
+    assert_eq!((label.start_line, label.start_column), (4, 9));
+    assert_eq!((label.end_line, label.end_column), (4, 10));
+}
+#[test]
+fn test_function_with_arguments() {
+    let src = r#"
+test fn takes_arg(n: int) {
+    assert(n == 1)
+}
+"#;
+    assert!(check("main.en", MockFileProvider::single_file(src)).is_err());
+}
@@ -0,0 +1,474 @@
+use convert::{LineIndex, path_to_uri, uri_to_path};
+use eon_core::addons::StaticModule;
+use eon_core::ast::FileData;
+use eon_core::ide::{self, Analysis};
+use eon_core::statics::Report;
+use eon_core::{ErrorSummary, FileProvider};
+use manifest::{Manifest, Project, SearchDirs};
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest, Request as _,
+};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, DocumentSymbol,
+    DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams, GotoDefinitionResponse,
+    Hover, HoverContents, HoverParams, HoverProviderCapability, InitializeParams, Location,
+    MarkupContent, MarkupKind, OneOf, PublishDiagnosticsParams, ServerCapabilities, SymbolKind,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Uri,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::path::{Path, PathBuf};
+mod convert;
+// shared with `eon`, which also uses the parts that check native modules
+#[allow(dead_code)]
+#[path = "../../eon_cli/src/manifest.rs"]
+mod manifest;
+type ServerError = Box<dyn std::error::Error + Send + Sync>;
+fn main() -> Result<(), ServerError> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![".".to_string()]),
+            ..Default::default()
+        }),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+    let params = connection.initialize(serde_json::to_value(capabilities)?)?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+    let mut server = Server::new(params.initialization_options);
+    server.main_loop(connection)?;
+    io_threads.join()?;
+    Ok(())
+}
+struct Server {
+    documents: HashMap<PathBuf, String>,
+    analyses: HashMap<PathBuf, Analysis>,
+    home_dir: PathBuf,
+    // searched after the project's module paths, before ~/.eon/modules
+    module_paths: Vec<PathBuf>,
+    shared_objects: Option<String>,
+}
+impl Server {
+    fn new(options: Option<Value>) -> Self {
+        let option = |key: &str| options.as_ref().and_then(|options| options.get(key));
+        let module_paths: Vec<PathBuf> = option("modulePaths")
+            .and_then(Value::as_array)
+            .map(|paths| paths.iter().filter_map(Value::as_str).map(PathBuf::from).collect())
+            .unwrap_or_default();
+        let shared_objects = option("sharedObjects")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        Self {
+            documents: HashMap::new(),
+            analyses: HashMap::new(),
+            home_dir: home::home_dir().unwrap_or_default(),
+            module_paths,
+            shared_objects,
+        }
+    }
+    fn main_loop(&mut self, connection: Connection) -> Result<(), ServerError> {
+        for msg in &connection.receiver {
+            match msg {
+                Message::Request(req) => {
+                    if connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    let response = self.handle_request(req);
+                    connection.sender.send(Message::Response(response))?;
+                }
+                Message::Notification(not) => {
+                    for not in self.handle_notification(not) {
+                        connection.sender.send(Message::Notification(not))?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+    fn handle_request(&mut self, req: Request) -> Response {
+        let id = req.id.clone();
+        let result = match req.method.as_str() {
+            HoverRequest::METHOD => parse_params(req).map(|params| to_value(self.hover(params))),
+            GotoDefinition::METHOD => {
+                parse_params(req).map(|params| to_value(self.definition(params)))
+            }
+            Completion::METHOD => parse_params(req).map(|params| to_value(self.completion(params))),
+            DocumentSymbolRequest::METHOD => {
+                parse_params(req).map(|params| to_value(self.document_symbols(params)))
+            }
+            _ => {
+                return Response::new_err(
+                    id,
+                    lsp_server::ErrorCode::MethodNotFound as i32,
+                    format!("unhandled method: {}", req.method),
+                );
+            }
+        };
+        response(id, result)
+    }
+    fn handle_notification(&mut self, not: Notification) -> Vec<Notification> {
+        let uri = match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Ok(params) = not.extract::<lsp_types::DidOpenTextDocumentParams>(
+                    DidOpenTextDocument::METHOD,
+                ) else {
+                    return vec![];
+                };
+                self.set_document(&params.text_document.uri, params.text_document.text);
+                params.text_document.uri
+            }
+            DidChangeTextDocument::METHOD => {
+                let Ok(mut params) = not.extract::<lsp_types::DidChangeTextDocumentParams>(
+                    DidChangeTextDocument::METHOD,
+                ) else {
+                    return vec![];
+                };
+                let Some(change) = params.content_changes.pop() else {
+                    return vec![];
+                };
+                self.set_document(&params.text_document.uri, change.text);
+                params.text_document.uri
+            }
+            DidCloseTextDocument::METHOD => {
+                let Ok(params) = not.extract::<lsp_types::DidCloseTextDocumentParams>(
+                    DidCloseTextDocument::METHOD,
+                ) else {
+                    return vec![];
+                };
+                if let Some(path) = uri_to_path(&params.text_document.uri) {
+                    self.documents.remove(&path);
+                    self.analyses.clear();
+                }
+                return vec![publish_diagnostics(params.text_document.uri, vec![])];
+            }
+            _ => return vec![],
+        };
+        let Some(path) = uri_to_path(&uri) else {
+            return vec![];
+        };
+        let diagnostics = match self.analyze(&path, None) {
+            Some(Ok(analysis)) => {
+                let diagnostics = self.diagnostics(&path, analysis.reports());
+                self.analyses.insert(path, analysis);
+                diagnostics
+            }
+            Some(Err(summary)) => self.diagnostics(&path, &summary.reports()),
+            None => vec![],
+        };
+        vec![publish_diagnostics(uri, diagnostics)]
+    }
+    fn set_document(&mut self, uri: &Uri, text: String) {
+        if let Some(path) = uri_to_path(uri) {
+            self.documents.insert(path, text);
+            self.analyses.clear();
+        }
+    }
+    fn text(&self, path: &Path) -> Option<String> {
+        match self.documents.get(path) {
+            Some(text) => Some(text.clone()),
+            None => std::fs::read_to_string(path).ok(),
+        }
+    }
+    fn analyze(
+        &self,
+        path: &Path,
+        replacement: Option<String>,
+    ) -> Option<Result<Analysis, ErrorSummary>> {
+        let project = self.project(path)?;
+        let mut documents = self.documents.clone();
+        if let Some(text) = replacement {
+            documents.insert(path.to_path_buf(), text);
+        }
+        let mut static_files = HashMap::new();
+        for module in static_modules() {
+            for (path, source) in module.files {
+                static_files.insert(PathBuf::from(path), *source);
+            }
+        }
+        let file_provider = Box::new(OverlayFileProvider {
+            roots: project.roots,
+            documents,
+            static_files,
+            shared_objects_dir: project.shared_objects_dir,
+        });
+        match catch_unwind(AssertUnwindSafe(|| {
+            Analysis::new(&project.main_file_name, file_provider)
+        })) {
+            Ok(result) => Some(result),
+            Err(_) => {
+                eprintln!("eon_lsp: analysis of '{}' panicked", path.display());
+                None
+            }
+        }
+    }
+    // `path` as the main file of the project it belongs to, resolved like `eon` does in the
+    // directory of the nearest eon.toml
+    fn project(&self, path: &Path) -> Option<Project> {
+        let mut manifest = None;
+        for dir in path.ancestors().skip(1) {
+            match Manifest::find(dir) {
+                Ok(None) => continue,
+                Ok(Some(found)) => manifest = Some(found),
+                Err(err) => eprintln!("eon_lsp: {err}"),
+            }
+            break;
+        }
+        let current_dir = match &manifest {
+            Some(manifest) => manifest.dir.clone(),
+            None => path.parent()?.to_path_buf(),
+        };
+        let dirs = SearchDirs {
+            current_dir: &current_dir,
+            home_dir: &self.home_dir,
+            modules: None,
+            shared_objects: self.shared_objects.as_deref(),
+        };
+        let mut project = manifest::resolve_project(manifest.as_ref(), path.to_str(), dirs).ok()?;
+        let home_modules = project.roots.pop();
+        project.roots.extend(self.module_paths.iter().cloned());
+        project.roots.extend(home_modules);
+        Some(project)
+    }
+    fn with_analysis<T>(&mut self, path: &Path, f: impl FnOnce(&Analysis) -> T) -> Option<T> {
+        if !self.analyses.contains_key(path) {
+            let analysis = self.analyze(path, None)?.ok()?;
+            self.analyses.insert(path.to_path_buf(), analysis);
+        }
+        catch_unwind(AssertUnwindSafe(|| f(&self.analyses[path]))).ok()
+    }
+    fn diagnostics(&self, path: &Path, reports: &[Report]) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        let text = self.text(path).unwrap_or_default();
+        let line_index = LineIndex::new(&text);
+        for report in reports {
+            let primary = report
+                .labels
+                .iter()
+                .find(|label| label.primary)
+                .or(report.labels.first());
+            let range = match primary {
+                Some(label) if label.file == path => line_index.range(label.range.clone()),
+                Some(_) => continue,
+                None => line_index.range(0..0),
+            };
+            let mut related_information = vec![];
+            for label in &report.labels {
+                if primary.is_some_and(|primary| std::ptr::eq(primary, label)) {
+                    continue;
+                }
+                let Some(uri) = path_to_uri(&label.file) else {
+                    continue;
+                };
+                let Some(label_text) = self.text(&label.file) else {
+                    continue;
+                };
+                related_information.push(DiagnosticRelatedInformation {
+                    location: Location::new(
+                        uri,
+                        LineIndex::new(&label_text).range(label.range.clone()),
+                    ),
+                    message: label.message.clone(),
+                });
+            }
+            let mut message = report.message.clone();
+            for note in &report.notes {
+                message.push('\n');
+                message.push_str(note);
+            }
+            diagnostics.push(Diagnostic {
+                range,
+                severity: Some(match report.severity {
+                    "error" => DiagnosticSeverity::ERROR,
+                    _ => DiagnosticSeverity::WARNING,
+                }),
+                source: Some("eon".to_string()),
+                message,
+                related_information: (!related_information.is_empty())
+                    .then_some(related_information),
+                ..Default::default()
+            });
+        }
+        diagnostics
+    }
+    fn hover(&mut self, params: HoverParams) -> Option<Hover> {
+        let position = params.text_document_position_params;
+        let path = uri_to_path(&position.text_document.uri)?;
+        let text = self.text(&path)?;
+        let line_index = LineIndex::new(&text);
+        let offset = line_index.offset(position.position);
+        let hover = self.with_analysis(&path, |analysis| analysis.hover(&path, offset))??;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```eon\n{}\n```", hover.contents),
+            }),
+            range: Some(line_index.range(hover.range)),
+        })
+    }
+    fn definition(&mut self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let position = params.text_document_position_params;
+        let path = uri_to_path(&position.text_document.uri)?;
+        let text = self.text(&path)?;
+        let offset = LineIndex::new(&text).offset(position.position);
+        let definition =
+            self.with_analysis(&path, |analysis| analysis.definition(&path, offset))??;
+        if !definition.file.is_absolute() {
+            return None;
+        }
+        let target_text = self.text(&definition.file)?;
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            path_to_uri(&definition.file)?,
+            LineIndex::new(&target_text).range(definition.range),
+        )))
+    }
+    fn completion(&mut self, params: CompletionParams) -> Option<CompletionResponse> {
+        let position = params.text_document_position;
+        let path = uri_to_path(&position.text_document.uri)?;
+        let text = self.text(&path)?;
+        let offset = LineIndex::new(&text).offset(position.position);
+        let partial_start = text[..offset]
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .map_or(0, |idx| idx + 1);
+        let dot = partial_start.checked_sub(1)?;
+        if text.as_bytes()[dot] != b'.' {
+            return None;
+        }
+        let replacement = format!("{}{}", &text[..dot], &text[offset..]);
+        let analysis = self.analyze(&path, Some(replacement))?.ok()?;
+        let completions = catch_unwind(AssertUnwindSafe(|| {
+            analysis.member_completions(&path, dot)
+        }))
+        .ok()?;
+        let items = completions
+            .into_iter()
+            .map(|completion| CompletionItem {
+                label: completion.label,
+                kind: Some(match completion.kind {
+                    ide::CompletionKind::Field => CompletionItemKind::FIELD,
+                    ide::CompletionKind::Method => CompletionItemKind::METHOD,
+                    ide::CompletionKind::Function => CompletionItemKind::FUNCTION,
+                    ide::CompletionKind::Variant => CompletionItemKind::ENUM_MEMBER,
+                    ide::CompletionKind::Type => CompletionItemKind::STRUCT,
+                    ide::CompletionKind::Module => CompletionItemKind::MODULE,
+                }),
+                detail: completion.detail,
+                ..Default::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+    fn document_symbols(&mut self, params: DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+        let path = uri_to_path(&params.text_document.uri)?;
+        let text = self.text(&path)?;
+        let line_index = LineIndex::new(&text);
+        let symbols = self.with_analysis(&path, |analysis| analysis.document_symbols(&path))?;
+        Some(DocumentSymbolResponse::Nested(
+            symbols
+                .into_iter()
+                .map(|symbol| document_symbol(&line_index, symbol))
+                .collect(),
+        ))
+    }
+}
+struct OverlayFileProvider {
+    roots: Vec<PathBuf>,
+    documents: HashMap<PathBuf, String>,
+    // the Eon files of the modules in `static_modules`, found after the roots
+    static_files: HashMap<PathBuf, &'static str>,
+    shared_objects_dir: PathBuf,
+}
+impl FileProvider for OverlayFileProvider {
+    fn search_for_file(&self, path: &Path) -> Result<FileData, Box<dyn std::error::Error>> {
+        for root in &self.roots {
+            let desired = root.join(path);
+            if let Some(contents) = self.documents.get(&desired) {
+                return Ok(FileData::new(path.to_owned(), desired, contents.clone()));
+            }
+            if let Ok(contents) = std::fs::read_to_string(&desired) {
+                return Ok(FileData::new(path.to_owned(), desired, contents));
+            }
+        }
+        if let Some(source) = self.static_files.get(path) {
+            return Ok(FileData::new(path.to_owned(), path.to_owned(), source.to_string()));
+        }
+        Err(format!("Could not find desired file: {}", path.display()).into())
+    }
+    fn shared_objects_dir(&self) -> &PathBuf {
+        &self.shared_objects_dir
+    }
+}
+// native modules the `eon` binary links in, whose Eon files aren't installed in a module path
+fn static_modules() -> Vec<&'static StaticModule> {
+    #[cfg(feature = "static-modules")]
+    return vec![
+        &eon_module_os::MODULE,
+        &eon_module_random::MODULE,
+        &eon_module_term::MODULE,
+        &eon_module_time::MODULE,
+    ];
+    #[cfg(not(feature = "static-modules"))]
+    vec![]
+}
+fn parse_params<P: serde::de::DeserializeOwned>(req: Request) -> Result<P, String> {
+    serde_json::from_value(req.params).map_err(|err| err.to_string())
+}
+fn to_value<T: serde::Serialize>(value: T) -> Value {
+    serde_json::to_value(value).unwrap()
+}
+fn response(id: RequestId, result: Result<Value, String>) -> Response {
+    match result {
+        Ok(result) => Response::new_ok(id, result),
+        Err(msg) => Response::new_err(id, lsp_server::ErrorCode::InvalidParams as i32, msg),
+    }
+}
+fn publish_diagnostics(uri: Uri, diagnostics: Vec<Diagnostic>) -> Notification {
+    Notification::new(
+        PublishDiagnostics::METHOD.to_string(),
+        PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        },
+    )
+}
+#[allow(deprecated)]
+fn document_symbol(line_index: &LineIndex, symbol: ide::Symbol) -> DocumentSymbol {
+    DocumentSymbol {
+        name: symbol.name,
+        detail: None,
+        kind: match symbol.kind {
+            ide::SymbolKind::Function => SymbolKind::FUNCTION,
+            ide::SymbolKind::Struct => SymbolKind::STRUCT,
+            ide::SymbolKind::Enum => SymbolKind::ENUM,
+            ide::SymbolKind::Interface => SymbolKind::INTERFACE,
+            ide::SymbolKind::Implementation => SymbolKind::CLASS,
+            ide::SymbolKind::Field => SymbolKind::FIELD,
+            ide::SymbolKind::Variant => SymbolKind::ENUM_MEMBER,
+            ide::SymbolKind::Method => SymbolKind::METHOD,
+            ide::SymbolKind::Variable => SymbolKind::VARIABLE,
+        },
+        tags: None,
+        deprecated: None,
+        range: line_index.range(symbol.range),
+        selection_range: line_index.range(symbol.selection_range),
+        children: (!symbol.children.is_empty()).then(|| {
+            symbol
+                .children
+                .into_iter()
+                .map(|child| document_symbol(line_index, child))
+                .collect()
+        }),
+    }
+}
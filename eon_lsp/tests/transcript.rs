@@ -0,0 +1,229 @@
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+const MAIN: &str = r#"type Point = {
+    x: int
+    y: int
+}
+extend Point {
+    fn sum(self) -> int {
+        self.x + self.y
+    }
+}
+fn make() -> Point {
+    Point(1, 2)
+}
+let p = make()
+println(p.sum())
+"#;
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: i64,
+}
+impl Client {
+    fn start() -> Client {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_eon_lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            // modules must come from the project or be linked in, not from ~/.eon/modules
+            .env("HOME", std::env::temp_dir().join("eon_lsp_home"))
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut client = Client {
+            child,
+            stdin,
+            stdout,
+            next_id: 0,
+        };
+        let result = client.request("initialize", json!({ "capabilities": {} }));
+        assert_eq!(
+            result["capabilities"]["completionProvider"]["triggerCharacters"],
+            json!(["."])
+        );
+        client.notify("initialized", json!({}));
+        client
+    }
+    fn send(&mut self, msg: Value) {
+        let body = msg.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+    fn recv(&mut self) -> Value {
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(len) = line.strip_prefix("Content-Length: ") {
+                content_length = len.parse().unwrap();
+            }
+        }
+        let mut body = vec![0; content_length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        loop {
+            let msg = self.recv();
+            if msg["id"] == json!(id) {
+                assert!(msg.get("error").is_none(), "{msg}");
+                return msg["result"].clone();
+            }
+        }
+    }
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+    fn diagnostics(&mut self, uri: &str) -> Vec<Value> {
+        loop {
+            let msg = self.recv();
+            if msg["method"] == "textDocument/publishDiagnostics" && msg["params"]["uri"] == uri {
+                return msg["params"]["diagnostics"].as_array().unwrap().clone();
+            }
+        }
+    }
+    fn open(&mut self, uri: &str, text: &str) -> Vec<Value> {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": uri, "languageId": "eon", "version": 1, "text": text }
+            }),
+        );
+        self.diagnostics(uri)
+    }
+    fn change(&mut self, uri: &str, version: i32, text: &str) -> Vec<Value> {
+        self.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": uri, "version": version },
+                "contentChanges": [{ "text": text }]
+            }),
+        );
+        self.diagnostics(uri)
+    }
+    fn shutdown(mut self) {
+        self.request("shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+fn document_uri(name: &str) -> String {
+    let dir: PathBuf = std::env::temp_dir().join(format!("eon_lsp_{}", std::process::id()));
+    format!("file://{}/{}", dir.display(), name)
+}
+fn position(uri: &str, line: u32, character: u32) -> Value {
+    json!({
+        "textDocument": { "uri": uri },
+        "position": { "line": line, "character": character }
+    })
+}
+#[test]
+fn diagnostics_on_open_and_change() {
+    let mut client = Client::start();
+    let uri = document_uri("diagnostics.en");
+    assert!(client.open(&uri, MAIN).is_empty());
+    let diagnostics = client.change(&uri, 2, "let x: int = \"hello\"\n");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 0);
+    let diagnostics = client.change(&uri, 3, "let x = \n");
+    assert_eq!(diagnostics.len(), 1);
+    assert!(
+        diagnostics[0]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Syntax error")
+    );
+    assert!(client.change(&uri, 4, "let x = 1\n").is_empty());
+    client.shutdown();
+}
+#[test]
+fn hover_and_definition() {
+    let mut client = Client::start();
+    let uri = document_uri("navigation.en");
+    client.open(&uri, MAIN);
+    let hover = client.request("textDocument/hover", position(&uri, 12, 4));
+    assert_eq!(hover["contents"]["value"], "```eon\np: Point\n```");
+    let hover = client.request("textDocument/hover", position(&uri, 6, 14));
+    assert_eq!(hover["contents"]["value"], "```eon\nint\n```");
+    let definition = client.request("textDocument/definition", position(&uri, 13, 8));
+    assert_eq!(definition["uri"], uri);
+    assert_eq!(
+        definition["range"],
+        json!({ "start": { "line": 12, "character": 4 }, "end": { "line": 12, "character": 5 } })
+    );
+    let definition = client.request("textDocument/definition", position(&uri, 12, 9));
+    assert_eq!(definition["range"]["start"], json!({ "line": 9, "character": 3 }));
+    client.shutdown();
+}
+#[test]
+fn member_completion() {
+    let mut client = Client::start();
+    let uri = document_uri("completion.en");
+    client.open(&uri, &MAIN.replace("println(p.sum())", "println(p.)"));
+    let items = client.request("textDocument/completion", position(&uri, 13, 10));
+    let labels: Vec<_> = items
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    assert_eq!(labels, vec!["sum", "x", "y"]);
+    client.shutdown();
+}
+#[test]
+fn document_symbols() {
+    let mut client = Client::start();
+    let uri = document_uri("symbols.en");
+    client.open(&uri, MAIN);
+    let symbols = client.request(
+        "textDocument/documentSymbol",
+        json!({ "textDocument": { "uri": uri } }),
+    );
+    let names: Vec<_> = symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| symbol["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["Point", "extend Point", "make", "p"]);
+    let fields: Vec<_> = symbols[0]["children"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| field["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, vec!["x", "y"]);
+    assert_eq!(symbols[1]["children"][0]["name"], "sum");
+    client.shutdown();
+}
+#[test]
+fn project_roots_and_linked_modules() {
+    let dir = std::env::temp_dir().join(format!("eon_lsp_project_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("src/app")).unwrap();
+    std::fs::write(dir.join("eon.toml"), "[project]\nsource_roots = [\"src\"]\n").unwrap();
+    std::fs::write(dir.join("src/helpers.en"), "pub fn double(n: int) -> int {\n  n * 2\n}\n")
+        .unwrap();
+    let mut client = Client::start();
+    // helpers.en is in the source root, not next to main.en, and time.en isn't installed
+    let uri = format!("file://{}/src/app/main.en", dir.display());
+    let text = "use helpers\nuse time\nlet t = get_time()\nprintln(double(2))\n";
+    let diagnostics = client.open(&uri, text);
+    assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    let definition = client.request("textDocument/definition", position(&uri, 3, 9));
+    assert_eq!(definition["uri"], format!("file://{}/src/helpers.en", dir.display()));
+    client.shutdown();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
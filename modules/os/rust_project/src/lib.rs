@@ -11,9 +11,14 @@ pub mod ffi {
         pub unsafe extern "C" fn fread(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let path = <String>::from_vm_unsafe(vm, vm_funcs);
-                let ret: Option<String> = os::fread(path);
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<String, String> = os::fread(path);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.fread", payload);
+                }
             }
         }
         /// # Safety
@@ -22,10 +27,15 @@ pub mod ffi {
         pub unsafe extern "C" fn fwrite(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let contents = <String>::from_vm_unsafe(vm, vm_funcs);
-                let path = <String>::from_vm_unsafe(vm, vm_funcs);
-                let ret: () = os::fwrite(path, contents);
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let contents = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<(), String> = os::fwrite(path, contents);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.fwrite", payload);
+                }
             }
         }
         /// # Safety
@@ -34,9 +44,14 @@ pub mod ffi {
         pub unsafe extern "C" fn fexists(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let path = <String>::from_vm_unsafe(vm, vm_funcs);
-                let ret: bool = os::fexists(path);
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: bool = os::fexists(path);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.fexists", payload);
+                }
             }
         }
         /// # Safety
@@ -45,9 +60,14 @@ pub mod ffi {
         pub unsafe extern "C" fn fremove(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let path = <String>::from_vm_unsafe(vm, vm_funcs);
-                let ret: () = os::fremove(path);
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<(), String> = os::fremove(path);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.fremove", payload);
+                }
             }
         }
         /// # Safety
@@ -56,10 +76,15 @@ pub mod ffi {
         pub unsafe extern "C" fn frename(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let new_path = <String>::from_vm_unsafe(vm, vm_funcs);
-                let old_path = <String>::from_vm_unsafe(vm, vm_funcs);
-                let ret: () = os::frename(old_path, new_path);
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let new_path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let old_path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<(), String> = os::frename(old_path, new_path);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.frename", payload);
+                }
             }
         }
         /// # Safety
@@ -68,10 +93,15 @@ pub mod ffi {
         pub unsafe extern "C" fn fcopy(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let dest = <String>::from_vm_unsafe(vm, vm_funcs);
-                let src = <String>::from_vm_unsafe(vm, vm_funcs);
-                let ret: () = os::fcopy(src, dest);
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let dest = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let src = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<(), String> = os::fcopy(src, dest);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.fcopy", payload);
+                }
             }
         }
         /// # Safety
@@ -80,11 +110,697 @@ pub mod ffi {
         pub unsafe extern "C" fn fappend(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let contents = <String>::from_vm_unsafe(vm, vm_funcs);
-                let path = <String>::from_vm_unsafe(vm, vm_funcs);
-                let ret: () = os::fappend(path, contents);
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let contents = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<(), String> = os::fappend(path, contents);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.fappend", payload);
+                }
+            }
+        }
+        pub enum FileKind {
+            File,
+            Dir,
+            Symlink,
+            Other,
+        }
+        impl VmFfiType for FileKind {
+            unsafe fn from_vm_unsafe(vm: *mut c_void, vm_funcs: &EonVmFunctions) -> Self {
+                unsafe {
+                    (vm_funcs.deconstruct_variant)(vm);
+                    let tag = (vm_funcs.pop_int)(vm);
+                    match tag {
+                        0 => {
+                            (vm_funcs.pop_nil)(vm);
+                            FileKind::File
+                        }
+                        1 => {
+                            (vm_funcs.pop_nil)(vm);
+                            FileKind::Dir
+                        }
+                        2 => {
+                            (vm_funcs.pop_nil)(vm);
+                            FileKind::Symlink
+                        }
+                        3 => {
+                            (vm_funcs.pop_nil)(vm);
+                            FileKind::Other
+                        }
+                        _ => panic!("unexpected tag encountered: {tag}"),
+                    }
+                }
+            }
+            unsafe fn to_vm_unsafe(self, vm: *mut c_void, vm_funcs: &EonVmFunctions) {
+                unsafe {
+                    match self {
+                        FileKind::File => {
+                            (vm_funcs.push_nil)(vm);
+                            (vm_funcs.construct_variant)(vm, 0);
+                        }
+                        FileKind::Dir => {
+                            (vm_funcs.push_nil)(vm);
+                            (vm_funcs.construct_variant)(vm, 1);
+                        }
+                        FileKind::Symlink => {
+                            (vm_funcs.push_nil)(vm);
+                            (vm_funcs.construct_variant)(vm, 2);
+                        }
+                        FileKind::Other => {
+                            (vm_funcs.push_nil)(vm);
+                            (vm_funcs.construct_variant)(vm, 3);
+                        }
+                    }
+                }
+            }
+        }
+        pub struct DirEntry {
+            pub name: String,
+            pub kind: FileKind,
+            pub size: i64,
+            pub modified: f64,
+        }
+        impl VmFfiType for DirEntry {
+            unsafe fn from_vm_unsafe(vm: *mut c_void, vm_funcs: &EonVmFunctions) -> Self {
+                unsafe {
+                    (vm_funcs.deconstruct_struct)(vm);
+                    let name = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let kind = <FileKind>::from_vm_unsafe(vm, vm_funcs);
+                    let size = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let modified = <f64>::from_vm_unsafe(vm, vm_funcs);
+                    Self {
+                        name,
+                        kind,
+                        size,
+                        modified,
+                    }
+                }
+            }
+            unsafe fn to_vm_unsafe(self, vm: *mut c_void, vm_funcs: &EonVmFunctions) {
+                unsafe {
+                    self.name.to_vm_unsafe(vm, vm_funcs);
+                    self.kind.to_vm_unsafe(vm, vm_funcs);
+                    self.size.to_vm_unsafe(vm, vm_funcs);
+                    self.modified.to_vm_unsafe(vm, vm_funcs);
+                    (vm_funcs.construct_struct)(vm, 4);
+                }
+            }
+        }
+        pub struct Metadata {
+            pub kind: FileKind,
+            pub size: i64,
+            pub modified: f64,
+            pub readonly: bool,
+        }
+        impl VmFfiType for Metadata {
+            unsafe fn from_vm_unsafe(vm: *mut c_void, vm_funcs: &EonVmFunctions) -> Self {
+                unsafe {
+                    (vm_funcs.deconstruct_struct)(vm);
+                    let kind = <FileKind>::from_vm_unsafe(vm, vm_funcs);
+                    let size = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let modified = <f64>::from_vm_unsafe(vm, vm_funcs);
+                    let readonly = <bool>::from_vm_unsafe(vm, vm_funcs);
+                    Self {
+                        kind,
+                        size,
+                        modified,
+                        readonly,
+                    }
+                }
+            }
+            unsafe fn to_vm_unsafe(self, vm: *mut c_void, vm_funcs: &EonVmFunctions) {
+                unsafe {
+                    self.kind.to_vm_unsafe(vm, vm_funcs);
+                    self.size.to_vm_unsafe(vm, vm_funcs);
+                    self.modified.to_vm_unsafe(vm, vm_funcs);
+                    self.readonly.to_vm_unsafe(vm, vm_funcs);
+                    (vm_funcs.construct_struct)(vm, 4);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$list_dir")]
+        pub unsafe extern "C" fn list_dir(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<Vec<DirEntry>, String> = os::list_dir(path);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.list_dir", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$create_dir")]
+        pub unsafe extern "C" fn create_dir(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<(), String> = os::create_dir(path);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.create_dir", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$remove_dir")]
+        pub unsafe extern "C" fn remove_dir(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<(), String> = os::remove_dir(path);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.remove_dir", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$metadata")]
+        pub unsafe extern "C" fn metadata(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<Metadata, String> = os::metadata(path);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.metadata", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$glob")]
+        pub unsafe extern "C" fn glob(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let pattern = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<Vec<String>, String> = os::glob(pattern);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.glob", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$current_dir")]
+        pub unsafe extern "C" fn current_dir(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let ret: Result<String, String> = os::current_dir();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.current_dir", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$set_current_dir")]
+        pub unsafe extern "C" fn set_current_dir(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<(), String> = os::set_current_dir(path);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.set_current_dir", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$temp_file")]
+        pub unsafe extern "C" fn temp_file(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let ret: Result<String, String> = os::temp_file();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.temp_file", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$temp_dir")]
+        pub unsafe extern "C" fn temp_dir(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let ret: Result<String, String> = os::temp_dir();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.temp_dir", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$path_join")]
+        pub unsafe extern "C" fn path_join(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let base = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: String = os::path_join(base, path);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.path_join", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$path_parent")]
+        pub unsafe extern "C" fn path_parent(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Option<String> = os::path_parent(path);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.path_parent", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$path_file_name")]
+        pub unsafe extern "C" fn path_file_name(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Option<String> = os::path_file_name(path);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.path_file_name", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$path_extension")]
+        pub unsafe extern "C" fn path_extension(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Option<String> = os::path_extension(path);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.path_extension", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$path_normalize")]
+        pub unsafe extern "C" fn path_normalize(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let path = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: String = os::path_normalize(path);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.path_normalize", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$get_env")]
+        pub unsafe extern "C" fn get_env(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let name = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Option<String> = os::get_env(name);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.get_env", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$set_env")]
+        pub unsafe extern "C" fn set_env(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let value = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let name = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: () = os::set_env(name, value);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.set_env", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$remove_env")]
+        pub unsafe extern "C" fn remove_env(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let name = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: () = os::remove_env(name);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.remove_env", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$env_vars")]
+        pub unsafe extern "C" fn env_vars(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let ret: Vec<(String, String)> = os::env_vars();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.env_vars", payload);
+                }
+            }
+        }
+        pub struct ProcessOptions {
+            pub cwd: Option<String>,
+            pub env: Vec<(String, String)>,
+            pub stdin: Option<String>,
+        }
+        impl VmFfiType for ProcessOptions {
+            unsafe fn from_vm_unsafe(vm: *mut c_void, vm_funcs: &EonVmFunctions) -> Self {
+                unsafe {
+                    (vm_funcs.deconstruct_struct)(vm);
+                    let cwd = <Option<String>>::from_vm_unsafe(vm, vm_funcs);
+                    let env = <Vec<(String, String)>>::from_vm_unsafe(vm, vm_funcs);
+                    let stdin = <Option<String>>::from_vm_unsafe(vm, vm_funcs);
+                    Self { cwd, env, stdin }
+                }
+            }
+            unsafe fn to_vm_unsafe(self, vm: *mut c_void, vm_funcs: &EonVmFunctions) {
+                unsafe {
+                    self.cwd.to_vm_unsafe(vm, vm_funcs);
+                    self.env.to_vm_unsafe(vm, vm_funcs);
+                    self.stdin.to_vm_unsafe(vm, vm_funcs);
+                    (vm_funcs.construct_struct)(vm, 3);
+                }
+            }
+        }
+        pub struct ProcessOutput {
+            pub status: i64,
+            pub stdout: String,
+            pub stderr: String,
+        }
+        impl VmFfiType for ProcessOutput {
+            unsafe fn from_vm_unsafe(vm: *mut c_void, vm_funcs: &EonVmFunctions) -> Self {
+                unsafe {
+                    (vm_funcs.deconstruct_struct)(vm);
+                    let status = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let stdout = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let stderr = <String>::from_vm_unsafe(vm, vm_funcs);
+                    Self {
+                        status,
+                        stdout,
+                        stderr,
+                    }
+                }
+            }
+            unsafe fn to_vm_unsafe(self, vm: *mut c_void, vm_funcs: &EonVmFunctions) {
+                unsafe {
+                    self.status.to_vm_unsafe(vm, vm_funcs);
+                    self.stdout.to_vm_unsafe(vm, vm_funcs);
+                    self.stderr.to_vm_unsafe(vm, vm_funcs);
+                    (vm_funcs.construct_struct)(vm, 3);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$exec")]
+        pub unsafe extern "C" fn exec(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let argv = <Vec<String>>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<ProcessOutput, String> = os::exec(argv);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.exec", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$exec_with")]
+        pub unsafe extern "C" fn exec_with(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let options = <ProcessOptions>::from_vm_unsafe(vm, vm_funcs);
+                    let argv = <Vec<String>>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<ProcessOutput, String> = os::exec_with(argv, options);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.exec_with", payload);
+                }
+            }
+        }
+        pub enum Process {
+            Process,
+        }
+        impl VmFfiType for Process {
+            unsafe fn from_vm_unsafe(vm: *mut c_void, vm_funcs: &EonVmFunctions) -> Self {
+                unsafe {
+                    (vm_funcs.deconstruct_variant)(vm);
+                    let tag = (vm_funcs.pop_int)(vm);
+                    match tag {
+                        0 => {
+                            (vm_funcs.pop_nil)(vm);
+                            Process::Process
+                        }
+                        _ => panic!("unexpected tag encountered: {tag}"),
+                    }
+                }
+            }
+            unsafe fn to_vm_unsafe(self, vm: *mut c_void, vm_funcs: &EonVmFunctions) {
+                unsafe {
+                    match self {
+                        Process::Process => {
+                            (vm_funcs.push_nil)(vm);
+                            (vm_funcs.construct_variant)(vm, 0);
+                        }
+                    }
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$spawn")]
+        pub unsafe extern "C" fn spawn(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let options = <ProcessOptions>::from_vm_unsafe(vm, vm_funcs);
+                    let argv = <Vec<String>>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<Handle, String> = os::spawn(argv, options);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.spawn", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$process_id")]
+        pub unsafe extern "C" fn process_id(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let process = <Handle>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: i64 = os::process_id(process);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.process_id", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$process_write")]
+        pub unsafe extern "C" fn process_write(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let input = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let process = <Handle>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<(), String> = os::process_write(process, input);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.process_write", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$process_close_stdin")]
+        pub unsafe extern "C" fn process_close_stdin(
+            vm: *mut c_void,
+            vm_funcs: *const EonVmFunctions,
+        ) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let process = <Handle>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: () = os::process_close_stdin(process);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.process_close_stdin", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$process_read_line")]
+        pub unsafe extern "C" fn process_read_line(
+            vm: *mut c_void,
+            vm_funcs: *const EonVmFunctions,
+        ) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let process = <Handle>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<Option<String>, String> = os::process_read_line(process);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.process_read_line", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$process_wait")]
+        pub unsafe extern "C" fn process_wait(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let process = <Handle>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<ProcessOutput, String> = os::process_wait(process);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.process_wait", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$os$process_kill")]
+        pub unsafe extern "C" fn process_kill(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let process = <Handle>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<(), String> = os::process_kill(process);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "os.process_kill", payload);
+                }
             }
         }
     }
 }
+pub static MODULE: eon_core::addons::StaticModule = eon_core::addons::StaticModule {
+    name: "os",
+    files: &[("os.en", include_str!("../../../os.en"))],
+    functions: &[
+        ("EON_ffi$os$fread", ffi::os::fread),
+        ("EON_ffi$os$fwrite", ffi::os::fwrite),
+        ("EON_ffi$os$fexists", ffi::os::fexists),
+        ("EON_ffi$os$fremove", ffi::os::fremove),
+        ("EON_ffi$os$frename", ffi::os::frename),
+        ("EON_ffi$os$fcopy", ffi::os::fcopy),
+        ("EON_ffi$os$fappend", ffi::os::fappend),
+        ("EON_ffi$os$list_dir", ffi::os::list_dir),
+        ("EON_ffi$os$create_dir", ffi::os::create_dir),
+        ("EON_ffi$os$remove_dir", ffi::os::remove_dir),
+        ("EON_ffi$os$metadata", ffi::os::metadata),
+        ("EON_ffi$os$glob", ffi::os::glob),
+        ("EON_ffi$os$current_dir", ffi::os::current_dir),
+        ("EON_ffi$os$set_current_dir", ffi::os::set_current_dir),
+        ("EON_ffi$os$temp_file", ffi::os::temp_file),
+        ("EON_ffi$os$temp_dir", ffi::os::temp_dir),
+        ("EON_ffi$os$path_join", ffi::os::path_join),
+        ("EON_ffi$os$path_parent", ffi::os::path_parent),
+        ("EON_ffi$os$path_file_name", ffi::os::path_file_name),
+        ("EON_ffi$os$path_extension", ffi::os::path_extension),
+        ("EON_ffi$os$path_normalize", ffi::os::path_normalize),
+        ("EON_ffi$os$get_env", ffi::os::get_env),
+        ("EON_ffi$os$set_env", ffi::os::set_env),
+        ("EON_ffi$os$remove_env", ffi::os::remove_env),
+        ("EON_ffi$os$env_vars", ffi::os::env_vars),
+        ("EON_ffi$os$exec", ffi::os::exec),
+        ("EON_ffi$os$exec_with", ffi::os::exec_with),
+        ("EON_ffi$os$spawn", ffi::os::spawn),
+        ("EON_ffi$os$process_id", ffi::os::process_id),
+        ("EON_ffi$os$process_write", ffi::os::process_write),
+        (
+            "EON_ffi$os$process_close_stdin",
+            ffi::os::process_close_stdin,
+        ),
+        ("EON_ffi$os$process_read_line", ffi::os::process_read_line),
+        ("EON_ffi$os$process_wait", ffi::os::process_wait),
+        ("EON_ffi$os$process_kill", ffi::os::process_kill),
+    ],
+};
@@ -1,30 +1,39 @@
-pub mod env;
-pub mod exec;
+mod dir;
+mod env;
+mod exec;
+mod path;
+pub use dir::*;
+pub use env::*;
+pub use exec::*;
+pub use path::*;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
-pub fn fread(path: String) -> Option<String> {
-    fs::read_to_string(path).ok()
+pub fn fread(path: String) -> Result<String, String> {
+    fs::read_to_string(&path).map_err(|err| format!("can't read {path}: {err}"))
 }
-pub fn fwrite(path: String, content: String) {
-    fs::write(path, content).expect("Unable to write to file");
+pub fn fwrite(path: String, content: String) -> Result<(), String> {
+    fs::write(&path, content).map_err(|err| format!("can't write {path}: {err}"))
 }
 pub fn fexists(path: String) -> bool {
-    fs::exists(path).unwrap()
+    fs::exists(path).unwrap_or(false)
 }
-pub fn fremove(path: String) {
-    fs::remove_file(path).unwrap()
+pub fn fremove(path: String) -> Result<(), String> {
+    fs::remove_file(&path).map_err(|err| format!("can't remove {path}: {err}"))
 }
-pub fn frename(old_path: String, new_path: String) {
-    fs::rename(old_path, new_path).unwrap();
+pub fn frename(old_path: String, new_path: String) -> Result<(), String> {
+    fs::rename(&old_path, &new_path)
+        .map_err(|err| format!("can't rename {old_path} to {new_path}: {err}"))
 }
-pub fn fcopy(src: String, dest: String) {
-    fs::copy(src, dest).unwrap();
+pub fn fcopy(src: String, dest: String) -> Result<(), String> {
+    fs::copy(&src, &dest)
+        .map(|_| ())
+        .map_err(|err| format!("can't copy {src} to {dest}: {err}"))
 }
-pub fn fappend(path: String, content: String) {
+pub fn fappend(path: String, content: String) -> Result<(), String> {
     let mut file = OpenOptions::new()
         .create(true)
         .append(true)
-        .open(path)
-        .unwrap();
-    writeln!(file, "{content}").unwrap();
+        .open(&path)
+        .map_err(|err| format!("can't open {path}: {err}"))?;
+    writeln!(file, "{content}").map_err(|err| format!("can't write {path}: {err}"))
 }
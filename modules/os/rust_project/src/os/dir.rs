@@ -0,0 +1,254 @@
+use crate::ffi::os::{DirEntry, FileKind, Metadata};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::UNIX_EPOCH;
+fn kind_of(file_type: fs::FileType) -> FileKind {
+    if file_type.is_symlink() {
+        FileKind::Symlink
+    } else if file_type.is_dir() {
+        FileKind::Dir
+    } else if file_type.is_file() {
+        FileKind::File
+    } else {
+        FileKind::Other
+    }
+}
+fn modified(metadata: &fs::Metadata) -> f64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0.0, |duration| duration.as_secs_f64())
+}
+pub fn list_dir(path: String) -> Result<Vec<DirEntry>, String> {
+    let error = |err: std::io::Error| format!("can't list {path}: {err}");
+    let mut entries = vec![];
+    for entry in fs::read_dir(&path).map_err(error)? {
+        let entry = entry.map_err(error)?;
+        let metadata = entry.metadata().map_err(error)?;
+        entries.push(DirEntry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            kind: kind_of(entry.file_type().map_err(error)?),
+            size: metadata.len() as i64,
+            modified: modified(&metadata),
+        });
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+pub fn create_dir(path: String) -> Result<(), String> {
+    fs::create_dir_all(&path).map_err(|err| format!("can't create {path}: {err}"))
+}
+pub fn remove_dir(path: String) -> Result<(), String> {
+    fs::remove_dir_all(&path).map_err(|err| format!("can't remove {path}: {err}"))
+}
+pub fn metadata(path: String) -> Result<Metadata, String> {
+    let metadata =
+        fs::symlink_metadata(&path).map_err(|err| format!("can't stat {path}: {err}"))?;
+    Ok(Metadata {
+        kind: kind_of(metadata.file_type()),
+        size: metadata.len() as i64,
+        modified: modified(&metadata),
+        readonly: metadata.permissions().readonly(),
+    })
+}
+pub fn current_dir() -> Result<String, String> {
+    std::env::current_dir()
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .map_err(|err| format!("can't get the current directory: {err}"))
+}
+pub fn set_current_dir(path: String) -> Result<(), String> {
+    std::env::set_current_dir(&path).map_err(|err| format!("can't change to {path}: {err}"))
+}
+// a name that isn't in use yet, tried until creating it succeeds
+fn temp_path(attempt: u32) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = UNIX_EPOCH
+        .elapsed()
+        .map_or(0, |elapsed| elapsed.subsec_nanos());
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("eon-{}-{nanos}-{n}-{attempt}", std::process::id()))
+}
+fn create_temp(create: impl Fn(&Path) -> std::io::Result<()>) -> Result<String, String> {
+    let mut attempt = 0;
+    loop {
+        let path = temp_path(attempt);
+        match create(&path) {
+            Ok(()) => return Ok(path.to_string_lossy().into_owned()),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists && attempt < 100 => {
+                attempt += 1;
+            }
+            Err(err) => return Err(format!("can't create {}: {err}", path.display())),
+        }
+    }
+}
+pub fn temp_file() -> Result<String, String> {
+    create_temp(|path| {
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map(|_| ())
+    })
+}
+pub fn temp_dir() -> Result<String, String> {
+    create_temp(|path| fs::create_dir(path))
+}
+pub fn glob(pattern: String) -> Result<Vec<String>, String> {
+    let pattern = Path::new(&pattern);
+    // the directory the pattern starts from, and the components with wildcards after it
+    let mut base = PathBuf::new();
+    let mut parts = vec![];
+    for component in pattern.components() {
+        let part = component.as_os_str().to_string_lossy().into_owned();
+        if parts.is_empty() && !part.contains(['*', '?']) {
+            base.push(component);
+        } else {
+            parts.push(part);
+        }
+    }
+    if parts.is_empty() {
+        return Ok(if base.exists() {
+            vec![base.to_string_lossy().into_owned()]
+        } else {
+            vec![]
+        });
+    }
+    let mut matches = vec![];
+    let start = if base.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        base.clone()
+    };
+    glob_in(&start, &base, &parts, &mut matches)
+        .map_err(|err| format!("can't search {}: {err}", start.display()))?;
+    matches.sort();
+    matches.dedup();
+    Ok(matches)
+}
+// `dir` is where to look and `shown` is how it's written in the results
+fn glob_in(
+    dir: &Path,
+    shown: &Path,
+    parts: &[String],
+    matches: &mut Vec<String>,
+) -> std::io::Result<()> {
+    let Some((part, rest)) = parts.split_first() else {
+        matches.push(shown.to_string_lossy().into_owned());
+        return Ok(());
+    };
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    if part == "**" {
+        // zero directories, then one more level
+        glob_in(dir, shown, rest, matches)?;
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                let name = entry.file_name();
+                glob_in(&dir.join(&name), &shown.join(&name), parts, matches)?;
+            }
+        }
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name_str = name.to_string_lossy();
+        // like shells, wildcards don't match hidden files
+        if name_str.starts_with('.') && !part.starts_with('.') {
+            continue;
+        }
+        if wildcard_match(part.as_bytes(), name_str.as_bytes()) {
+            glob_in(&dir.join(&name), &shown.join(&name), rest, matches)?;
+        }
+    }
+    Ok(())
+}
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            wildcard_match(&pattern[1..], name)
+                || (!name.is_empty() && wildcard_match(pattern, &name[1..]))
+        }
+        (Some(b'?'), Some(_)) => wildcard_match(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => wildcard_match(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn s(path: &Path) -> String {
+        path.to_string_lossy().into_owned()
+    }
+    #[test]
+    fn list_and_remove() {
+        let root = PathBuf::from(temp_dir().unwrap());
+        create_dir(s(&root.join("sub/deeper"))).unwrap();
+        fs::write(root.join("b.txt"), "hello").unwrap();
+        let entries = list_dir(s(&root)).unwrap();
+        let names: Vec<_> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["b.txt", "sub"]);
+        assert!(matches!(entries[0].kind, FileKind::File));
+        assert_eq!(entries[0].size, 5);
+        assert!(entries[0].modified > 0.0);
+        assert!(matches!(entries[1].kind, FileKind::Dir));
+        let info = metadata(s(&root.join("b.txt"))).unwrap();
+        assert!(matches!(info.kind, FileKind::File));
+        assert!(!info.readonly);
+        remove_dir(s(&root)).unwrap();
+        assert!(!root.exists());
+        assert!(list_dir(s(&root)).err().unwrap().starts_with("can't list"));
+        assert!(metadata(s(&root)).is_err());
+    }
+    #[test]
+    fn temp_files_are_unique() {
+        let a = temp_file().unwrap();
+        let b = temp_file().unwrap();
+        assert_ne!(a, b);
+        assert!(Path::new(&a).is_file());
+        fs::remove_file(a).unwrap();
+        fs::remove_file(b).unwrap();
+    }
+    #[test]
+    fn glob_patterns() {
+        let root = PathBuf::from(temp_dir().unwrap());
+        create_dir(s(&root.join("src/nested"))).unwrap();
+        for file in ["a.en", "b.rs", ".hidden.en", "src/c.en", "src/nested/d.en"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        let relative = |matches: Vec<String>| -> Vec<String> {
+            matches
+                .iter()
+                .map(|path| s(Path::new(path).strip_prefix(&root).unwrap()))
+                .collect()
+        };
+        assert_eq!(relative(glob(s(&root.join("*.en"))).unwrap()), ["a.en"]);
+        assert_eq!(relative(glob(s(&root.join("?.rs"))).unwrap()), ["b.rs"]);
+        assert_eq!(
+            relative(glob(s(&root.join("**/*.en"))).unwrap()),
+            ["a.en", "src/c.en", "src/nested/d.en"]
+        );
+        assert_eq!(
+            relative(glob(s(&root.join("src/*"))).unwrap()),
+            ["src/c.en", "src/nested"]
+        );
+        assert!(glob(s(&root.join("*.txt"))).unwrap().is_empty());
+        // a missing directory has no matches, with or without `**`
+        assert!(glob(s(&root.join("missing/*.en"))).unwrap().is_empty());
+        assert!(glob(s(&root.join("missing/**/*.en"))).unwrap().is_empty());
+        assert!(glob(s(&root.join("a.en/**"))).unwrap().is_empty());
+        remove_dir(s(&root)).unwrap();
+    }
+    #[test]
+    fn wildcards() {
+        assert!(wildcard_match(b"*.en", b"main.en"));
+        assert!(wildcard_match(b"m*n.*", b"main.en"));
+        assert!(!wildcard_match(b"*.en", b"main.rs"));
+        assert!(!wildcard_match(b"?", b""));
+    }
+}
@@ -1,4 +1,43 @@
-#[expect(dead_code, reason = "Reserved for future use in FFI bindings")]
-pub fn get_var(key: String) -> String {
-    std::env::var(key).unwrap_or("".to_string())
+pub fn get_env(name: String) -> Option<String> {
+    std::env::var(name).ok()
+}
+// Changing the environment is only sound while no other thread reads or writes it. An Eon
+// program runs on one thread, but an embedder that runs vms on several threads must not let them
+// call set_env or remove_env; a sandboxed vm can't call them at all.
+pub fn set_env(name: String, value: String) {
+    // the caller upholds the requirement above, see os.en
+    unsafe { std::env::set_var(name, value) }
+}
+pub fn remove_env(name: String) {
+    // same as set_env
+    unsafe { std::env::remove_var(name) }
+}
+pub fn env_vars() -> Vec<(String, String)> {
+    let mut vars: Vec<_> = std::env::vars_os()
+        .map(|(name, value)| {
+            (
+                name.to_string_lossy().into_owned(),
+                value.to_string_lossy().into_owned(),
+            )
+        })
+        .collect();
+    vars.sort();
+    vars
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn set_and_remove() {
+        let name = "EON_ENV_TEST".to_string();
+        set_env(name.clone(), "value".to_string());
+        assert_eq!(get_env(name.clone()).as_deref(), Some("value"));
+        assert!(
+            env_vars()
+                .iter()
+                .any(|(var, value)| *var == name && value == "value")
+        );
+        remove_env(name.clone());
+        assert_eq!(get_env(name), None);
+    }
 }
@@ -1,18 +1,188 @@
-use std::process::Command;
-#[expect(dead_code, reason = "Reserved for future use in FFI bindings")]
-pub fn command(content: String) -> i64 {
-    let content_elems: Vec<_> = content.split(' ').collect();
-    let mut cmd = Command::new(content_elems[0]);
-    for elem in &content_elems[1..] {
-        cmd.arg(elem);
-    }
-    let output = cmd.output();
-    match output {
-        Ok(output) => {
-            print!("{}", String::from_utf8_lossy(&output.stdout));
-            eprint!("{}", String::from_utf8_lossy(&output.stderr));
-            0
+use crate::ffi::os::{ProcessOptions, ProcessOutput};
+use eon_core::addons::Handle;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Stdio};
+use std::thread::{self, JoinHandle};
+fn command(argv: &[String], options: &ProcessOptions) -> Result<Command, String> {
+    let Some((program, args)) = argv.split_first() else {
+        return Err("can't run an empty command".to_string());
+    };
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    if let Some(cwd) = &options.cwd {
+        cmd.current_dir(cwd);
+    }
+    cmd.envs(options.env.iter().map(|(name, value)| (name, value)));
+    Ok(cmd)
+}
+fn spawn_piped(argv: &[String], options: &ProcessOptions) -> Result<Child, String> {
+    let mut cmd = command(argv, options)?;
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("can't run {}: {err}", argv[0]))
+}
+// on its own thread so a child blocked on a full stdout pipe can't stop us from writing
+fn write_stdin(mut stdin: ChildStdin, input: String) -> JoinHandle<()> {
+    thread::spawn(move || {
+        // the child may exit without reading all of it
+        let _ = stdin.write_all(input.as_bytes());
+    })
+}
+fn output(status: ExitStatus, stdout: &[u8], stderr: &[u8]) -> ProcessOutput {
+    ProcessOutput {
+        status: status.code().map_or(-1, i64::from),
+        stdout: String::from_utf8_lossy(stdout).into_owned(),
+        stderr: String::from_utf8_lossy(stderr).into_owned(),
+    }
+}
+pub fn exec(argv: Vec<String>) -> Result<ProcessOutput, String> {
+    let options = ProcessOptions {
+        cwd: None,
+        env: vec![],
+        stdin: None,
+    };
+    exec_with(argv, options)
+}
+pub fn exec_with(argv: Vec<String>, options: ProcessOptions) -> Result<ProcessOutput, String> {
+    let mut child = spawn_piped(&argv, &options)?;
+    let stdin = child.stdin.take().unwrap();
+    let writer = options.stdin.map(|input| write_stdin(stdin, input));
+    let out = child
+        .wait_with_output()
+        .map_err(|err| format!("can't wait for {}: {err}", argv[0]))?;
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+    Ok(output(out.status, &out.stdout, &out.stderr))
+}
+struct RunningProcess {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: Option<BufReader<ChildStdout>>,
+    // collects stderr so a chatty child doesn't block while we read stdout
+    stderr: Option<JoinHandle<Vec<u8>>>,
+    name: String,
+}
+fn process(handle: &mut Handle) -> &mut RunningProcess {
+    // the handle came from the vm and is only used during this call
+    unsafe { handle.get_mut::<RunningProcess>() }.expect("the handle isn't a process")
+}
+pub fn spawn(argv: Vec<String>, options: ProcessOptions) -> Result<Handle, String> {
+    let mut child = spawn_piped(&argv, &options)?;
+    let mut stdin = child.stdin.take();
+    if let Some(input) = options.stdin {
+        write_stdin(stdin.take().unwrap(), input);
+    }
+    let stdout = child.stdout.take().map(BufReader::new);
+    let stderr = child.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut buf = vec![];
+            let _ = stderr.read_to_end(&mut buf);
+            buf
+        })
+    });
+    Ok(Handle::new(RunningProcess {
+        child,
+        stdin,
+        stdout,
+        stderr,
+        name: argv[0].clone(),
+    }))
+}
+pub fn process_id(mut process_handle: Handle) -> i64 {
+    i64::from(process(&mut process_handle).child.id())
+}
+pub fn process_write(mut process_handle: Handle, input: String) -> Result<(), String> {
+    let process = process(&mut process_handle);
+    let Some(stdin) = &mut process.stdin else {
+        return Err(format!("the stdin of {} is closed", process.name));
+    };
+    stdin
+        .write_all(input.as_bytes())
+        .and_then(|()| stdin.flush())
+        .map_err(|err| format!("can't write to {}: {err}", process.name))
+}
+pub fn process_close_stdin(mut process_handle: Handle) {
+    process(&mut process_handle).stdin = None;
+}
+pub fn process_read_line(mut process_handle: Handle) -> Result<Option<String>, String> {
+    let process = process(&mut process_handle);
+    let Some(stdout) = &mut process.stdout else {
+        return Ok(None);
+    };
+    let mut line = String::new();
+    let n = stdout
+        .read_line(&mut line)
+        .map_err(|err| format!("can't read from {}: {err}", process.name))?;
+    if n == 0 {
+        return Ok(None);
+    }
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
         }
-        Err(err) => err.raw_os_error().unwrap() as i64,
+    }
+    Ok(Some(line))
+}
+pub fn process_wait(mut process_handle: Handle) -> Result<ProcessOutput, String> {
+    let process = process(&mut process_handle);
+    let error = |err: std::io::Error| format!("can't wait for {}: {err}", process.name);
+    process.stdin = None;
+    let mut stdout = vec![];
+    if let Some(mut reader) = process.stdout.take() {
+        reader.read_to_end(&mut stdout).map_err(error)?;
+    }
+    let stderr = match process.stderr.take() {
+        Some(thread) => thread.join().unwrap_or_default(),
+        None => vec![],
+    };
+    let status = process.child.wait().map_err(error)?;
+    Ok(output(status, &stdout, &stderr))
+}
+pub fn process_kill(mut process_handle: Handle) -> Result<(), String> {
+    let process = process(&mut process_handle);
+    process
+        .child
+        .kill()
+        .map_err(|err| format!("can't kill {}: {err}", process.name))
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+    #[test]
+    fn exec_captures_output() {
+        let out = exec(argv(&["sh", "-c", "echo out; echo err >&2; exit 3"])).unwrap();
+        assert_eq!(out.status, 3);
+        assert_eq!(out.stdout, "out\n");
+        assert_eq!(out.stderr, "err\n");
+        assert!(exec(vec![]).is_err());
+        let Err(msg) = exec(argv(&["eon-no-such-program"])) else {
+            panic!("ran a missing program")
+        };
+        assert!(msg.starts_with("can't run"));
+    }
+    #[test]
+    fn exec_with_options() {
+        let dir = std::env::temp_dir();
+        let options = ProcessOptions {
+            cwd: Some(dir.to_string_lossy().into_owned()),
+            env: vec![("EON_TEST_VAR".to_string(), "set".to_string())],
+            stdin: Some("from stdin".to_string()),
+        };
+        let out = exec_with(argv(&["sh", "-c", "pwd; echo $EON_TEST_VAR; cat"]), options).unwrap();
+        assert_eq!(out.status, 0);
+        let lines: Vec<_> = out.stdout.lines().collect();
+        assert_eq!(
+            PathBuf::from(lines[0]).canonicalize().unwrap(),
+            dir.canonicalize().unwrap()
+        );
+        assert_eq!(lines[1..], ["set", "from stdin"]);
     }
 }
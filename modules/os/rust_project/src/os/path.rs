@@ -0,0 +1,67 @@
+use std::path::{Component, Path, PathBuf};
+pub fn path_join(base: String, path: String) -> String {
+    Path::new(&base).join(path).to_string_lossy().into_owned()
+}
+pub fn path_parent(path: String) -> Option<String> {
+    Path::new(&path)
+        .parent()
+        .map(|parent| parent.to_string_lossy().into_owned())
+}
+pub fn path_file_name(path: String) -> Option<String> {
+    Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+pub fn path_extension(path: String) -> Option<String> {
+    Path::new(&path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().into_owned())
+}
+pub fn path_normalize(path: String) -> String {
+    let mut ret = PathBuf::new();
+    for component in Path::new(&path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match ret.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    ret.pop();
+                }
+                // `..` at the root is the root
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => ret.push(".."),
+            },
+            _ => ret.push(component),
+        }
+    }
+    if ret.as_os_str().is_empty() {
+        ".".to_string()
+    } else {
+        ret.to_string_lossy().into_owned()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn n(path: &str) -> String {
+        path_normalize(path.to_string())
+    }
+    #[test]
+    fn components() {
+        assert_eq!(path_join("a/b".into(), "c.en".into()), "a/b/c.en");
+        assert_eq!(path_join("a".into(), "/abs".into()), "/abs");
+        assert_eq!(path_parent("a/b/c.en".into()).as_deref(), Some("a/b"));
+        assert_eq!(path_parent("/".into()), None);
+        assert_eq!(path_file_name("a/b/c.en".into()).as_deref(), Some("c.en"));
+        assert_eq!(path_file_name("a/..".into()), None);
+        assert_eq!(path_extension("a/b/c.tar.gz".into()).as_deref(), Some("gz"));
+        assert_eq!(path_extension("a/.hidden".into()), None);
+    }
+    #[test]
+    fn normalize() {
+        assert_eq!(n("a/./b/../c"), "a/c");
+        assert_eq!(n("../a/.."), "..");
+        assert_eq!(n("/../a"), "/a");
+        assert_eq!(n("a/.."), ".");
+        assert_eq!(n("a//b/"), "a/b");
+    }
+}
@@ -5,16 +5,37 @@ pub mod ffi {
         use eon_core::addons::*;
         use std::ffi::c_void;
 
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$random$random_seed")]
+        pub unsafe extern "C" fn random_seed(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let seed = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: () = random::random_seed(seed);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "random.random_seed", payload);
+                }
+            }
+        }
         /// # Safety
         /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
         #[unsafe(export_name = "EON_ffi$random$random_float")]
         pub unsafe extern "C" fn random_float(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let max = <f64>::from_vm_unsafe(vm, vm_funcs);
-                let min = <f64>::from_vm_unsafe(vm, vm_funcs);
-                let ret: f64 = random::random_float(min, max);
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let max = <f64>::from_vm_unsafe(vm, vm_funcs);
+                    let min = <f64>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: f64 = random::random_float(min, max);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "random.random_float", payload);
+                }
             }
         }
         /// # Safety
@@ -23,11 +44,229 @@ pub mod ffi {
         pub unsafe extern "C" fn random_int(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let max = <i64>::from_vm_unsafe(vm, vm_funcs);
-                let min = <i64>::from_vm_unsafe(vm, vm_funcs);
-                let ret: i64 = random::random_int(min, max);
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let max = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let min = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: i64 = random::random_int(min, max);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "random.random_int", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$random$random_int_inclusive")]
+        pub unsafe extern "C" fn random_int_inclusive(
+            vm: *mut c_void,
+            vm_funcs: *const EonVmFunctions,
+        ) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let max = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let min = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: i64 = random::random_int_inclusive(min, max);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "random.random_int_inclusive", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$random$random_bool")]
+        pub unsafe extern "C" fn random_bool(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let probability = <f64>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: bool = random::random_bool(probability);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "random.random_bool", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$random$random_normal")]
+        pub unsafe extern "C" fn random_normal(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let std_dev = <f64>::from_vm_unsafe(vm, vm_funcs);
+                    let mean = <f64>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: f64 = random::random_normal(mean, std_dev);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "random.random_normal", payload);
+                }
+            }
+        }
+        pub enum Rng {
+            Rng,
+        }
+        impl VmFfiType for Rng {
+            unsafe fn from_vm_unsafe(vm: *mut c_void, vm_funcs: &EonVmFunctions) -> Self {
+                unsafe {
+                    (vm_funcs.deconstruct_variant)(vm);
+                    let tag = (vm_funcs.pop_int)(vm);
+                    match tag {
+                        0 => {
+                            (vm_funcs.pop_nil)(vm);
+                            Rng::Rng
+                        }
+                        _ => panic!("unexpected tag encountered: {tag}"),
+                    }
+                }
+            }
+            unsafe fn to_vm_unsafe(self, vm: *mut c_void, vm_funcs: &EonVmFunctions) {
+                unsafe {
+                    match self {
+                        Rng::Rng => {
+                            (vm_funcs.push_nil)(vm);
+                            (vm_funcs.construct_variant)(vm, 0);
+                        }
+                    }
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$random$rng_new")]
+        pub unsafe extern "C" fn rng_new(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let seed = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Handle = random::rng_new(seed);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "random.rng_new", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$random$rng_float")]
+        pub unsafe extern "C" fn rng_float(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let max = <f64>::from_vm_unsafe(vm, vm_funcs);
+                    let min = <f64>::from_vm_unsafe(vm, vm_funcs);
+                    let rng = <Handle>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: f64 = random::rng_float(rng, min, max);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "random.rng_float", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$random$rng_int")]
+        pub unsafe extern "C" fn rng_int(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let max = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let min = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let rng = <Handle>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: i64 = random::rng_int(rng, min, max);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "random.rng_int", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$random$rng_int_inclusive")]
+        pub unsafe extern "C" fn rng_int_inclusive(
+            vm: *mut c_void,
+            vm_funcs: *const EonVmFunctions,
+        ) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let max = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let min = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let rng = <Handle>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: i64 = random::rng_int_inclusive(rng, min, max);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "random.rng_int_inclusive", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$random$rng_bool")]
+        pub unsafe extern "C" fn rng_bool(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let probability = <f64>::from_vm_unsafe(vm, vm_funcs);
+                    let rng = <Handle>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: bool = random::rng_bool(rng, probability);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "random.rng_bool", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$random$rng_normal")]
+        pub unsafe extern "C" fn rng_normal(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let std_dev = <f64>::from_vm_unsafe(vm, vm_funcs);
+                    let mean = <f64>::from_vm_unsafe(vm, vm_funcs);
+                    let rng = <Handle>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: f64 = random::rng_normal(rng, mean, std_dev);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "random.rng_normal", payload);
+                }
             }
         }
     }
 }
+pub static MODULE: eon_core::addons::StaticModule = eon_core::addons::StaticModule {
+    name: "random",
+    files: &[("random.en", include_str!("../../../random.en"))],
+    functions: &[
+        ("EON_ffi$random$random_seed", ffi::random::random_seed),
+        ("EON_ffi$random$random_float", ffi::random::random_float),
+        ("EON_ffi$random$random_int", ffi::random::random_int),
+        (
+            "EON_ffi$random$random_int_inclusive",
+            ffi::random::random_int_inclusive,
+        ),
+        ("EON_ffi$random$random_bool", ffi::random::random_bool),
+        ("EON_ffi$random$random_normal", ffi::random::random_normal),
+        ("EON_ffi$random$rng_new", ffi::random::rng_new),
+        ("EON_ffi$random$rng_float", ffi::random::rng_float),
+        ("EON_ffi$random$rng_int", ffi::random::rng_int),
+        (
+            "EON_ffi$random$rng_int_inclusive",
+            ffi::random::rng_int_inclusive,
+        ),
+        ("EON_ffi$random$rng_bool", ffi::random::rng_bool),
+        ("EON_ffi$random$rng_normal", ffi::random::rng_normal),
+    ],
+};
@@ -1,9 +1,128 @@
-use rand::Rng;
+use eon_core::addons::Handle;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::sync::Mutex;
+// A named algorithm rather than `StdRng`, whose algorithm may change between versions of `rand`,
+// so a seed gives the same sequence in every build.
+type Generator = ChaCha8Rng;
+// The global generator is shared by the whole process, so every vm that loads this module draws
+// from (and reseeds) the same sequence. Use `rng_new` for a generator of your own.
+static GLOBAL: Mutex<Option<Generator>> = Mutex::new(None);
+fn with_global<R>(f: impl FnOnce(&mut Generator) -> R) -> R {
+    let mut global = GLOBAL
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    f(global.get_or_insert_with(Generator::from_os_rng))
+}
+fn rng(handle: &mut Handle) -> &mut Generator {
+    // the handle came from the vm and is only used during this call
+    unsafe { handle.get_mut::<Generator>() }.expect("the handle isn't a generator")
+}
+fn float(rng: &mut Generator, min: f64, max: f64) -> f64 {
+    if min < max {
+        rng.random_range(min..max)
+    } else {
+        min
+    }
+}
+fn int(rng: &mut Generator, min: i64, max: i64) -> i64 {
+    if min < max {
+        rng.random_range(min..max)
+    } else {
+        min
+    }
+}
+fn int_inclusive(rng: &mut Generator, min: i64, max: i64) -> i64 {
+    if min <= max {
+        rng.random_range(min..=max)
+    } else {
+        min
+    }
+}
+fn boolean(rng: &mut Generator, probability: f64) -> bool {
+    // also maps NaN to 0
+    rng.random_bool(probability.clamp(0.0, 1.0).max(0.0))
+}
+// Box-Muller transform
+fn normal(rng: &mut Generator, mean: f64, std_dev: f64) -> f64 {
+    // in (0, 1] so the logarithm is finite
+    let u1 = 1.0 - rng.random::<f64>();
+    let u2 = rng.random::<f64>();
+    let z = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+    mean + std_dev * z
+}
+pub fn random_seed(seed: i64) {
+    *GLOBAL
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) =
+        Some(Generator::seed_from_u64(seed as u64));
+}
 pub fn random_float(min: f64, max: f64) -> f64 {
-    let mut rng = rand::rng();
-    rng.random_range(min..max)
+    with_global(|rng| float(rng, min, max))
 }
 pub fn random_int(min: i64, max: i64) -> i64 {
-    let mut rng = rand::rng();
-    rng.random_range(min..max)
+    with_global(|rng| int(rng, min, max))
+}
+pub fn random_int_inclusive(min: i64, max: i64) -> i64 {
+    with_global(|rng| int_inclusive(rng, min, max))
+}
+pub fn random_bool(probability: f64) -> bool {
+    with_global(|rng| boolean(rng, probability))
+}
+pub fn random_normal(mean: f64, std_dev: f64) -> f64 {
+    with_global(|rng| normal(rng, mean, std_dev))
+}
+pub fn rng_new(seed: i64) -> Handle {
+    Handle::new(Generator::seed_from_u64(seed as u64))
+}
+pub fn rng_float(mut handle: Handle, min: f64, max: f64) -> f64 {
+    float(rng(&mut handle), min, max)
+}
+pub fn rng_int(mut handle: Handle, min: i64, max: i64) -> i64 {
+    int(rng(&mut handle), min, max)
+}
+pub fn rng_int_inclusive(mut handle: Handle, min: i64, max: i64) -> i64 {
+    int_inclusive(rng(&mut handle), min, max)
+}
+pub fn rng_bool(mut handle: Handle, probability: f64) -> bool {
+    boolean(rng(&mut handle), probability)
+}
+pub fn rng_normal(mut handle: Handle, mean: f64, std_dev: f64) -> f64 {
+    normal(rng(&mut handle), mean, std_dev)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn empty_ranges() {
+        let mut rng = Generator::seed_from_u64(1);
+        assert_eq!(int(&mut rng, 5, 5), 5);
+        assert_eq!(int(&mut rng, 5, 2), 5);
+        assert_eq!(float(&mut rng, 1.5, 1.5), 1.5);
+        assert_eq!(int_inclusive(&mut rng, 5, 5), 5);
+        for _ in 0..100 {
+            assert!((0..=2).contains(&int_inclusive(&mut rng, 0, 2)));
+            assert!((0..2).contains(&int(&mut rng, 0, 2)));
+        }
+        assert!(!boolean(&mut rng, 0.0));
+        assert!(boolean(&mut rng, 2.0));
+        assert!(!boolean(&mut rng, f64::NAN));
+    }
+    #[test]
+    fn seeds_are_stable() {
+        let mut rng = Generator::seed_from_u64(42);
+        let ints: Vec<_> = (0..4).map(|_| int(&mut rng, 0, 1000)).collect();
+        // pinned, so a dependency update that changes the sequence fails here
+        assert_eq!(ints, [681, 950, 427, 627]);
+    }
+    #[test]
+    fn normal_distribution() {
+        let mut rng = Generator::seed_from_u64(2);
+        let samples: Vec<_> = (0..10000).map(|_| normal(&mut rng, 10.0, 2.0)).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance =
+            samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        assert!((mean - 10.0).abs() < 0.1, "{mean}");
+        assert!((variance.sqrt() - 2.0).abs() < 0.1, "{variance}");
+    }
 }
@@ -92,8 +92,13 @@ pub mod ffi {
         pub unsafe extern "C" fn enable_raw_mode(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let ret: () = term::enable_raw_mode();
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let ret: () = term::enable_raw_mode();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "term.enable_raw_mode", payload);
+                }
             }
         }
         /// # Safety
@@ -105,8 +110,13 @@ pub mod ffi {
         ) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let ret: () = term::disable_raw_mode();
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let ret: () = term::disable_raw_mode();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "term.disable_raw_mode", payload);
+                }
             }
         }
         /// # Safety
@@ -115,8 +125,13 @@ pub mod ffi {
         pub unsafe extern "C" fn poll_key_event(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let ret: bool = term::poll_key_event();
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let ret: bool = term::poll_key_event();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "term.poll_key_event", payload);
+                }
             }
         }
         /// # Safety
@@ -125,8 +140,13 @@ pub mod ffi {
         pub unsafe extern "C" fn get_key_event(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let ret: KeyCode = term::get_key_event();
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let ret: KeyCode = term::get_key_event();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "term.get_key_event", payload);
+                }
             }
         }
         /// # Safety
@@ -135,8 +155,13 @@ pub mod ffi {
         pub unsafe extern "C" fn clear(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let ret: () = term::clear();
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let ret: () = term::clear();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "term.clear", payload);
+                }
             }
         }
         /// # Safety
@@ -145,8 +170,13 @@ pub mod ffi {
         pub unsafe extern "C" fn hide_cursor(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let ret: () = term::hide_cursor();
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let ret: () = term::hide_cursor();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "term.hide_cursor", payload);
+                }
             }
         }
         /// # Safety
@@ -155,8 +185,13 @@ pub mod ffi {
         pub unsafe extern "C" fn show_cursor(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let ret: () = term::show_cursor();
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let ret: () = term::show_cursor();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "term.show_cursor", payload);
+                }
             }
         }
         /// # Safety
@@ -165,11 +200,16 @@ pub mod ffi {
         pub unsafe extern "C" fn mark(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let y = <i64>::from_vm_unsafe(vm, vm_funcs);
-                let x = <i64>::from_vm_unsafe(vm, vm_funcs);
-                let s = <String>::from_vm_unsafe(vm, vm_funcs);
-                let ret: () = term::mark(s, x, y);
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let y = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let x = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let s = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: () = term::mark(s, x, y);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "term.mark", payload);
+                }
             }
         }
         /// # Safety
@@ -178,9 +218,29 @@ pub mod ffi {
         pub unsafe extern "C" fn flush(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let ret: () = term::flush();
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let ret: () = term::flush();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "term.flush", payload);
+                }
             }
         }
     }
 }
+pub static MODULE: eon_core::addons::StaticModule = eon_core::addons::StaticModule {
+    name: "term",
+    files: &[("term.en", include_str!("../../../term.en"))],
+    functions: &[
+        ("EON_ffi$term$enable_raw_mode", ffi::term::enable_raw_mode),
+        ("EON_ffi$term$disable_raw_mode", ffi::term::disable_raw_mode),
+        ("EON_ffi$term$poll_key_event", ffi::term::poll_key_event),
+        ("EON_ffi$term$get_key_event", ffi::term::get_key_event),
+        ("EON_ffi$term$clear", ffi::term::clear),
+        ("EON_ffi$term$hide_cursor", ffi::term::hide_cursor),
+        ("EON_ffi$term$show_cursor", ffi::term::show_cursor),
+        ("EON_ffi$term$mark", ffi::term::mark),
+        ("EON_ffi$term$flush", ffi::term::flush),
+    ],
+};
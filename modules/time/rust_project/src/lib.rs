@@ -11,8 +11,13 @@ pub mod ffi {
         pub unsafe extern "C" fn get_time(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let ret: f64 = time::get_time();
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let ret: f64 = time::get_time();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.get_time", payload);
+                }
             }
         }
         /// # Safety
@@ -21,10 +26,342 @@ pub mod ffi {
         pub unsafe extern "C" fn sleep(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
             unsafe {
                 let vm_funcs: &EonVmFunctions = &*vm_funcs;
-                let seconds = <f64>::from_vm_unsafe(vm, vm_funcs);
-                let ret: () = time::sleep(seconds);
-                ret.to_vm_unsafe(vm, vm_funcs);
+                let result = catch_foreign_panic(|| {
+                    let seconds = <f64>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: () = time::sleep(seconds);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.sleep", payload);
+                }
+            }
+        }
+        pub struct Duration {
+            pub seconds: i64,
+            pub nanos: i64,
+        }
+        impl VmFfiType for Duration {
+            unsafe fn from_vm_unsafe(vm: *mut c_void, vm_funcs: &EonVmFunctions) -> Self {
+                unsafe {
+                    (vm_funcs.deconstruct_struct)(vm);
+                    let seconds = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let nanos = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    Self { seconds, nanos }
+                }
+            }
+            unsafe fn to_vm_unsafe(self, vm: *mut c_void, vm_funcs: &EonVmFunctions) {
+                unsafe {
+                    self.seconds.to_vm_unsafe(vm, vm_funcs);
+                    self.nanos.to_vm_unsafe(vm, vm_funcs);
+                    (vm_funcs.construct_struct)(vm, 2);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$time$now")]
+        pub unsafe extern "C" fn now(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let ret: Duration = time::now();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.now", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$time$monotonic")]
+        pub unsafe extern "C" fn monotonic(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let ret: Duration = time::monotonic();
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.monotonic", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$time$duration_from_seconds")]
+        pub unsafe extern "C" fn duration_from_seconds(
+            vm: *mut c_void,
+            vm_funcs: *const EonVmFunctions,
+        ) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let seconds = <f64>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Duration = time::duration_from_seconds(seconds);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.duration_from_seconds", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$time$duration_from_millis")]
+        pub unsafe extern "C" fn duration_from_millis(
+            vm: *mut c_void,
+            vm_funcs: *const EonVmFunctions,
+        ) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let millis = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Duration = time::duration_from_millis(millis);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.duration_from_millis", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$time$duration_as_seconds")]
+        pub unsafe extern "C" fn duration_as_seconds(
+            vm: *mut c_void,
+            vm_funcs: *const EonVmFunctions,
+        ) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let duration = <Duration>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: f64 = time::duration_as_seconds(duration);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.duration_as_seconds", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$time$duration_as_millis")]
+        pub unsafe extern "C" fn duration_as_millis(
+            vm: *mut c_void,
+            vm_funcs: *const EonVmFunctions,
+        ) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let duration = <Duration>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: i64 = time::duration_as_millis(duration);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.duration_as_millis", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$time$duration_add")]
+        pub unsafe extern "C" fn duration_add(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let b = <Duration>::from_vm_unsafe(vm, vm_funcs);
+                    let a = <Duration>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Duration = time::duration_add(a, b);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.duration_add", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$time$duration_sub")]
+        pub unsafe extern "C" fn duration_sub(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let b = <Duration>::from_vm_unsafe(vm, vm_funcs);
+                    let a = <Duration>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Duration = time::duration_sub(a, b);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.duration_sub", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$time$duration_compare")]
+        pub unsafe extern "C" fn duration_compare(
+            vm: *mut c_void,
+            vm_funcs: *const EonVmFunctions,
+        ) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let b = <Duration>::from_vm_unsafe(vm, vm_funcs);
+                    let a = <Duration>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: i64 = time::duration_compare(a, b);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.duration_compare", payload);
+                }
+            }
+        }
+        pub struct DateTime {
+            pub year: i64,
+            pub month: i64,
+            pub day: i64,
+            pub hour: i64,
+            pub minute: i64,
+            pub second: i64,
+            pub nanos: i64,
+            pub offset_minutes: i64,
+        }
+        impl VmFfiType for DateTime {
+            unsafe fn from_vm_unsafe(vm: *mut c_void, vm_funcs: &EonVmFunctions) -> Self {
+                unsafe {
+                    (vm_funcs.deconstruct_struct)(vm);
+                    let year = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let month = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let day = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let hour = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let minute = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let second = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let nanos = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let offset_minutes = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    Self {
+                        year,
+                        month,
+                        day,
+                        hour,
+                        minute,
+                        second,
+                        nanos,
+                        offset_minutes,
+                    }
+                }
+            }
+            unsafe fn to_vm_unsafe(self, vm: *mut c_void, vm_funcs: &EonVmFunctions) {
+                unsafe {
+                    self.year.to_vm_unsafe(vm, vm_funcs);
+                    self.month.to_vm_unsafe(vm, vm_funcs);
+                    self.day.to_vm_unsafe(vm, vm_funcs);
+                    self.hour.to_vm_unsafe(vm, vm_funcs);
+                    self.minute.to_vm_unsafe(vm, vm_funcs);
+                    self.second.to_vm_unsafe(vm, vm_funcs);
+                    self.nanos.to_vm_unsafe(vm, vm_funcs);
+                    self.offset_minutes.to_vm_unsafe(vm, vm_funcs);
+                    (vm_funcs.construct_struct)(vm, 8);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$time$to_date_time")]
+        pub unsafe extern "C" fn to_date_time(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let offset_minutes = <i64>::from_vm_unsafe(vm, vm_funcs);
+                    let instant = <Duration>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<DateTime, String> = time::to_date_time(instant, offset_minutes);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.to_date_time", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$time$from_date_time")]
+        pub unsafe extern "C" fn from_date_time(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let date_time = <DateTime>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<Duration, String> = time::from_date_time(date_time);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.from_date_time", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$time$format_rfc3339")]
+        pub unsafe extern "C" fn format_rfc3339(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let date_time = <DateTime>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: String = time::format_rfc3339(date_time);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.format_rfc3339", payload);
+                }
+            }
+        }
+        /// # Safety
+        /// The caller must ensure that `vm` is a valid pointer and `vm_funcs` points to valid function pointers.
+        #[unsafe(export_name = "EON_ffi$time$parse_rfc3339")]
+        pub unsafe extern "C" fn parse_rfc3339(vm: *mut c_void, vm_funcs: *const EonVmFunctions) {
+            unsafe {
+                let vm_funcs: &EonVmFunctions = &*vm_funcs;
+                let result = catch_foreign_panic(|| {
+                    let text = <String>::from_vm_unsafe(vm, vm_funcs);
+                    let ret: Result<DateTime, String> = time::parse_rfc3339(text);
+                    ret.to_vm_unsafe(vm, vm_funcs);
+                });
+                if let Err(payload) = result {
+                    report_panic(vm, vm_funcs, "time.parse_rfc3339", payload);
+                }
             }
         }
     }
 }
+pub static MODULE: eon_core::addons::StaticModule = eon_core::addons::StaticModule {
+    name: "time",
+    files: &[("time.en", include_str!("../../../time.en"))],
+    functions: &[
+        ("EON_ffi$time$get_time", ffi::time::get_time),
+        ("EON_ffi$time$sleep", ffi::time::sleep),
+        ("EON_ffi$time$now", ffi::time::now),
+        ("EON_ffi$time$monotonic", ffi::time::monotonic),
+        (
+            "EON_ffi$time$duration_from_seconds",
+            ffi::time::duration_from_seconds,
+        ),
+        (
+            "EON_ffi$time$duration_from_millis",
+            ffi::time::duration_from_millis,
+        ),
+        (
+            "EON_ffi$time$duration_as_seconds",
+            ffi::time::duration_as_seconds,
+        ),
+        (
+            "EON_ffi$time$duration_as_millis",
+            ffi::time::duration_as_millis,
+        ),
+        ("EON_ffi$time$duration_add", ffi::time::duration_add),
+        ("EON_ffi$time$duration_sub", ffi::time::duration_sub),
+        ("EON_ffi$time$duration_compare", ffi::time::duration_compare),
+        ("EON_ffi$time$to_date_time", ffi::time::to_date_time),
+        ("EON_ffi$time$from_date_time", ffi::time::from_date_time),
+        ("EON_ffi$time$format_rfc3339", ffi::time::format_rfc3339),
+        ("EON_ffi$time$parse_rfc3339", ffi::time::parse_rfc3339),
+    ],
+};
@@ -1,11 +1,106 @@
+mod date;
+use crate::ffi::time::Duration;
+pub use date::*;
+use std::cmp::Ordering;
+use std::sync::OnceLock;
 use std::thread;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
 pub fn get_time() -> f64 {
     let now = SystemTime::now();
     let duration_since_epoch = now.duration_since(UNIX_EPOCH).expect("Time went backwards");
     duration_since_epoch.as_secs_f64()
 }
 pub fn sleep(seconds: f64) {
-    let duration = Duration::from_secs_f64(seconds);
+    let duration = std::time::Duration::from_secs_f64(seconds);
     thread::sleep(duration);
 }
+pub(crate) fn total_nanos(duration: &Duration) -> i128 {
+    i128::from(duration.seconds) * NANOS_PER_SECOND + i128::from(duration.nanos)
+}
+// saturates at the longest durations whose seconds fit in an int
+pub(crate) fn from_nanos(nanos: i128) -> Duration {
+    let nanos = nanos.clamp(
+        i128::from(i64::MIN) * NANOS_PER_SECOND,
+        i128::from(i64::MAX) * NANOS_PER_SECOND + NANOS_PER_SECOND - 1,
+    );
+    Duration {
+        seconds: nanos.div_euclid(NANOS_PER_SECOND) as i64,
+        nanos: nanos.rem_euclid(NANOS_PER_SECOND) as i64,
+    }
+}
+pub fn now() -> Duration {
+    let nanos = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_nanos() as i128,
+        // the clock is set before 1970
+        Err(err) => -(err.duration().as_nanos() as i128),
+    };
+    from_nanos(nanos)
+}
+pub fn monotonic() -> Duration {
+    static START: OnceLock<Instant> = OnceLock::new();
+    from_nanos(START.get_or_init(Instant::now).elapsed().as_nanos() as i128)
+}
+pub fn duration_from_seconds(seconds: f64) -> Duration {
+    // `as` saturates at the bounds of i128 and turns NaN into zero, then from_nanos saturates
+    from_nanos((seconds * 1e9).round() as i128)
+}
+pub fn duration_from_millis(millis: i64) -> Duration {
+    from_nanos(i128::from(millis) * 1_000_000)
+}
+pub fn duration_as_seconds(duration: Duration) -> f64 {
+    duration.seconds as f64 + duration.nanos as f64 / 1e9
+}
+pub fn duration_as_millis(duration: Duration) -> i64 {
+    let millis = total_nanos(&duration).div_euclid(1_000_000);
+    millis.clamp(i128::from(i64::MIN), i128::from(i64::MAX)) as i64
+}
+pub fn duration_add(a: Duration, b: Duration) -> Duration {
+    from_nanos(total_nanos(&a) + total_nanos(&b))
+}
+pub fn duration_sub(a: Duration, b: Duration) -> Duration {
+    from_nanos(total_nanos(&a) - total_nanos(&b))
+}
+pub fn duration_compare(a: Duration, b: Duration) -> i64 {
+    match total_nanos(&a).cmp(&total_nanos(&b)) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn arithmetic() {
+        let a = || duration_from_seconds(1.5);
+        let b = || duration_from_millis(2750);
+        assert_eq!((a().seconds, a().nanos), (1, 500_000_000));
+        let diff = duration_sub(a(), b());
+        assert_eq!((diff.seconds, diff.nanos), (-2, 750_000_000));
+        assert_eq!(duration_as_millis(duration_sub(a(), b())), -1250);
+        assert_eq!(duration_as_seconds(duration_add(a(), b())), 4.25);
+        assert_eq!(duration_compare(a(), b()), -1);
+        assert_eq!(duration_compare(b(), a()), 1);
+        assert_eq!(duration_compare(a(), duration_from_millis(1500)), 0);
+    }
+    #[test]
+    fn saturation() {
+        let max = duration_from_seconds(1e300);
+        assert_eq!((max.seconds, max.nanos), (i64::MAX, 999_999_999));
+        let min = duration_from_seconds(f64::NEG_INFINITY);
+        assert_eq!((min.seconds, min.nanos), (i64::MIN, 0));
+        let nan = duration_from_seconds(f64::NAN);
+        assert_eq!((nan.seconds, nan.nanos), (0, 0));
+        let sum = duration_add(duration_from_seconds(1e300), duration_from_millis(1));
+        assert_eq!(sum.seconds, i64::MAX);
+        assert_eq!(duration_as_millis(duration_from_seconds(1e300)), i64::MAX);
+    }
+    #[test]
+    fn clocks() {
+        let start = monotonic();
+        sleep(0.01);
+        assert!(duration_as_millis(duration_sub(monotonic(), start)) >= 10);
+        assert!(now().seconds > 1_700_000_000);
+    }
+}
@@ -0,0 +1,298 @@
+use super::{from_nanos, total_nanos};
+use crate::ffi::time::{DateTime, Duration};
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+// days since 1970-01-01 in the proleptic Gregorian calendar, from
+// http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+// RFC 3339 offsets have two digits of hours
+const MAX_OFFSET_MINUTES: i64 = 23 * 60 + 59;
+fn in_range(name: &str, value: i64, min: i64, max: i64) -> Result<(), String> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(format!("{name} {value} isn't between {min} and {max}"))
+    }
+}
+pub fn to_date_time(instant: Duration, offset_minutes: i64) -> Result<DateTime, String> {
+    in_range(
+        "offset",
+        offset_minutes,
+        -MAX_OFFSET_MINUTES,
+        MAX_OFFSET_MINUTES,
+    )?;
+    let local = total_nanos(&instant) + i128::from(offset_minutes) * 60 * 1_000_000_000;
+    let Duration { seconds, nanos } = from_nanos(local);
+    let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+    let second_of_day = seconds.rem_euclid(SECONDS_PER_DAY);
+    let date_time = DateTime {
+        year,
+        month,
+        day,
+        hour: second_of_day / 3600,
+        minute: second_of_day / 60 % 60,
+        second: second_of_day % 60,
+        nanos,
+        offset_minutes,
+    };
+    check(&date_time)?;
+    Ok(date_time)
+}
+fn check(date_time: &DateTime) -> Result<(), String> {
+    // RFC 3339 years have four digits, which also keeps the arithmetic from overflowing
+    in_range("year", date_time.year, 0, 9999)?;
+    in_range("month", date_time.month, 1, 12)?;
+    in_range(
+        "day",
+        date_time.day,
+        1,
+        days_in_month(date_time.year, date_time.month),
+    )?;
+    in_range("hour", date_time.hour, 0, 23)?;
+    in_range("minute", date_time.minute, 0, 59)?;
+    in_range("second", date_time.second, 0, 59)?;
+    in_range("nanos", date_time.nanos, 0, 999_999_999)?;
+    in_range(
+        "offset",
+        date_time.offset_minutes,
+        -MAX_OFFSET_MINUTES,
+        MAX_OFFSET_MINUTES,
+    )
+}
+pub fn from_date_time(date_time: DateTime) -> Result<Duration, String> {
+    check(&date_time)?;
+    let days = days_from_civil(date_time.year, date_time.month, date_time.day);
+    let seconds = days * SECONDS_PER_DAY
+        + date_time.hour * 3600
+        + (date_time.minute - date_time.offset_minutes) * 60
+        + date_time.second;
+    Ok(Duration {
+        seconds,
+        nanos: date_time.nanos,
+    })
+}
+pub fn format_rfc3339(date_time: DateTime) -> String {
+    let DateTime {
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        nanos,
+        offset_minutes,
+    } = date_time;
+    let mut ret = format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}");
+    if nanos != 0 {
+        // milli, micro or nanoseconds, whichever is exact
+        if nanos % 1_000_000 == 0 {
+            ret += &format!(".{:03}", nanos / 1_000_000);
+        } else if nanos % 1000 == 0 {
+            ret += &format!(".{:06}", nanos / 1000);
+        } else {
+            ret += &format!(".{nanos:09}");
+        }
+    }
+    if offset_minutes == 0 {
+        ret.push('Z');
+    } else {
+        let sign = if offset_minutes < 0 { '-' } else { '+' };
+        let offset = offset_minutes.abs();
+        ret += &format!("{sign}{:02}:{:02}", offset / 60, offset % 60);
+    }
+    ret
+}
+// reads the fixed-width number at the start of `text`
+fn number(text: &mut &[u8], digits: usize, what: &str) -> Result<i64, String> {
+    let bytes = text
+        .get(..digits)
+        .filter(|bytes| bytes.iter().all(u8::is_ascii_digit));
+    let Some(bytes) = bytes else {
+        return Err(format!("expected {digits} digits for the {what}"));
+    };
+    *text = &text[digits..];
+    Ok(bytes
+        .iter()
+        .fold(0, |n, digit| n * 10 + i64::from(digit - b'0')))
+}
+fn expect(text: &mut &[u8], separators: &[u8], what: &str) -> Result<u8, String> {
+    match text.first() {
+        Some(c) if separators.contains(c) => {
+            *text = &text[1..];
+            Ok(*c)
+        }
+        _ => Err(format!("expected {what}")),
+    }
+}
+pub fn parse_rfc3339(text: String) -> Result<DateTime, String> {
+    let error = |err: String| format!("can't parse {text:?} as a date and time: {err}");
+    let mut rest = text.as_bytes();
+    let s = &mut rest;
+    let mut parse = || -> Result<DateTime, String> {
+        let year = number(s, 4, "year")?;
+        expect(s, b"-", "`-` after the year")?;
+        let month = number(s, 2, "month")?;
+        expect(s, b"-", "`-` after the month")?;
+        let day = number(s, 2, "day")?;
+        expect(s, b"Tt ", "`T` after the date")?;
+        let hour = number(s, 2, "hour")?;
+        expect(s, b":", "`:` after the hour")?;
+        let minute = number(s, 2, "minute")?;
+        expect(s, b":", "`:` after the minute")?;
+        let second = number(s, 2, "second")?;
+        let mut nanos = 0;
+        if s.first() == Some(&b'.') {
+            *s = &s[1..];
+            let digits = s.iter().take_while(|c| c.is_ascii_digit()).count();
+            if digits == 0 {
+                return Err("expected digits after `.`".to_string());
+            }
+            // digits past nanoseconds are dropped
+            for (i, digit) in s[..digits].iter().enumerate() {
+                if i < 9 {
+                    nanos += i64::from(digit - b'0') * 10_i64.pow(8 - i as u32);
+                }
+            }
+            *s = &s[digits..];
+        }
+        let offset_minutes = match expect(s, b"Zz+-", "`Z` or an offset like +02:00")? {
+            b'Z' | b'z' => 0,
+            sign => {
+                let hours = number(s, 2, "offset hours")?;
+                expect(s, b":", "`:` in the offset")?;
+                let minutes = hours * 60 + number(s, 2, "offset minutes")?;
+                if sign == b'-' { -minutes } else { minutes }
+            }
+        };
+        if !s.is_empty() {
+            return Err("unexpected text after the offset".to_string());
+        }
+        let date_time = DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            nanos,
+            offset_minutes,
+        };
+        check(&date_time)?;
+        Ok(date_time)
+    };
+    parse().map_err(error)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn utc(text: &str) -> Duration {
+        from_date_time(parse_rfc3339(text.to_string()).unwrap()).unwrap()
+    }
+    #[test]
+    fn calendar() {
+        for days in [-719468, -1, 0, 59, 365, 11016, 19000, 2932896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+    #[test]
+    fn rfc3339_round_trip() {
+        assert_eq!(utc("1970-01-01T00:00:00Z").seconds, 0);
+        assert_eq!(utc("2024-05-01T12:30:00Z").seconds, 1714566600);
+        assert_eq!(
+            utc("2024-05-01T14:30:00+02:00").seconds,
+            utc("2024-05-01T12:30:00Z").seconds
+        );
+        let date_time = to_date_time(utc("2024-05-01T12:30:00.25Z"), 5 * 60 + 30).unwrap();
+        assert_eq!((date_time.hour, date_time.minute), (18, 0));
+        assert_eq!(format_rfc3339(date_time), "2024-05-01T18:00:00.250+05:30");
+        for text in [
+            "1969-12-31T23:59:59.000001Z",
+            "2000-02-29T00:00:00-08:00",
+            "0001-01-01T00:00:00.123456789Z",
+        ] {
+            let date_time = parse_rfc3339(text.to_string()).unwrap();
+            assert_eq!(format_rfc3339(date_time), text);
+        }
+        let before_epoch = to_date_time(utc("1969-12-31T23:59:59.5Z"), 0).unwrap();
+        assert_eq!((before_epoch.year, before_epoch.second), (1969, 59));
+        assert_eq!(before_epoch.nanos, 500_000_000);
+    }
+    #[test]
+    fn invalid_dates() {
+        for text in [
+            "2023-02-29T00:00:00Z",
+            "2024-13-01T00:00:00Z",
+            "2024-05-01T24:00:00Z",
+            "2024-05-01 12:30",
+            "2024-05-01T12:30:00Zjunk",
+            "24-05-01T12:30:00Z",
+        ] {
+            assert!(parse_rfc3339(text.to_string()).is_err(), "{text}");
+        }
+        let mut date_time = to_date_time(
+            Duration {
+                seconds: 0,
+                nanos: 0,
+            },
+            0,
+        )
+        .unwrap();
+        date_time.day = 32;
+        let Err(msg) = from_date_time(date_time) else {
+            panic!("day 32 was accepted")
+        };
+        assert_eq!(msg, "day 32 isn't between 1 and 31");
+        let epoch = || Duration {
+            seconds: 0,
+            nanos: 0,
+        };
+        assert!(to_date_time(epoch(), 23 * 60 + 59).is_ok());
+        assert!(to_date_time(epoch(), -(23 * 60 + 59)).is_ok());
+        let Err(msg) = to_date_time(epoch(), 24 * 60) else {
+            panic!("an offset of 24 hours was accepted")
+        };
+        assert_eq!(msg, "offset 1440 isn't between -1439 and 1439");
+        date_time = to_date_time(epoch(), 0).unwrap();
+        date_time.year = 999_999_999_999_999;
+        let Err(msg) = from_date_time(date_time) else {
+            panic!("a year that doesn't fit was accepted")
+        };
+        assert_eq!(msg, "year 999999999999999 isn't between 0 and 9999");
+        let far = Duration {
+            seconds: i64::MAX,
+            nanos: 0,
+        };
+        assert!(to_date_time(far, 0).is_err());
+    }
+}
@@ -63,7 +63,7 @@ mod tests {
         let c = arena.alloc(true);
         assert_eq!(*a, 123);
         assert_eq!(*b, 456);
-        assert_eq!(*c, true);
+        assert!(*c);
     }
     #[test]
     fn test_ref_eq() {
@@ -83,8 +83,7 @@ mod tests {
     fn test_storing_refs() {
         let arena = Arena::new();
         let a = arena.alloc(123);
-        let mut my_refs = vec![];
-        my_refs.push(a);
+        let my_refs = [a];
         assert_eq!(*my_refs[0], 123);
     }
     fn identity(n: Ar<i32>) -> Ar<i32> {